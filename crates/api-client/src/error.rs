@@ -0,0 +1,32 @@
+use api_types::error::ErrorBody;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ClientError {
+    #[error("Request: {0}")]
+    Request(#[from] reqwest::Error),
+
+    /// The server answered with an error body
+    #[error("Api {status}: {}", body.msg)]
+    Api { status: u16, body: ErrorBody },
+
+    /// The server answered with a status and body the API doesn't document
+    #[error("UnexpectedResponse {status}: {body}")]
+    UnexpectedResponse { status: u16, body: String },
+
+    #[error("WebSocket: {0}")]
+    WebSocket(#[from] fastwebsockets::WebSocketError),
+
+    #[error("InvalidUrl: {0}")]
+    InvalidUrl(String),
+
+    #[error("Signer: {0}")]
+    Signer(#[from] alloy::signers::Error),
+
+    #[error("NotSignedIn: call a sign-in method or `with_token` first")]
+    NotSignedIn,
+
+    #[error("InvalidFrame: {0}")]
+    InvalidFrame(String),
+}
+
+pub type ClientResult<T> = Result<T, ClientError>;
@@ -0,0 +1,555 @@
+//! Typed client for the http-server API
+//!
+//! ```ignore
+//! let mut client = ApiClient::new("http://localhost:8080");
+//! client.sign_in_evm(&PrivateKeySigner::random()).await?;
+//! let me = client.me().await?;
+//! ```
+
+use std::str::FromStr;
+
+use alloy::signers::{Signer, local::PrivateKeySigner};
+use api_types::{
+    admin::{
+        EvmCursor, SetEvmCursorPayload, SetSolanaCursorPayload, SettingChangesResponse,
+        SettingsResponse, SolanaCursor,
+    },
+    auth::{
+        SignInEvmPayload, SignInResponse, SignInSolPayload, SigningMsgPayload, SigningMsgResponse,
+    },
+    error::ErrorBody,
+    health::{IndexersResponse, Liveness, Readiness},
+    users::MeResponse,
+    webhooks::{
+        CreatedWebhook, Webhook, WebhookDeliveriesResponse, WebhookPayload, WebhooksResponse,
+    },
+};
+use hyper::Uri;
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use solana_sdk::signature::{Keypair, Signer as _};
+
+pub use api_types;
+pub use error::{ClientError, ClientResult};
+pub use ws::RandomU64Feed;
+
+mod error;
+mod ws;
+
+#[derive(Clone)]
+pub struct ApiClient {
+    http: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl ApiClient {
+    /// `base_url` is the server origin, e.g. `http://localhost:8080`
+    pub fn new<U: Into<String>>(base_url: U) -> Self {
+        Self::with_http_client(base_url, reqwest::Client::new())
+    }
+
+    /// Same as [`ApiClient::new`], reusing a configured reqwest client
+    pub fn with_http_client<U: Into<String>>(base_url: U, http: reqwest::Client) -> Self {
+        Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_owned(),
+            token: None,
+        }
+    }
+
+    /// Authenticates later requests with a previously issued token
+    pub fn with_token<T: Into<String>>(mut self, token: T) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// The bearer token in use, if signed in
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// `GET /health/live`
+    pub async fn liveness(&self) -> ClientResult<Liveness> {
+        self.send(self.request(Method::GET, "/health/live")).await
+    }
+
+    /// `GET /health/ready`; an unavailable server is reported in the body, not as an error
+    pub async fn readiness(&self) -> ClientResult<Readiness> {
+        let response = self.request(Method::GET, "/health/ready").send().await?;
+
+        match response.status() {
+            StatusCode::OK | StatusCode::SERVICE_UNAVAILABLE => Ok(response.json().await?),
+            _ => Err(error_of(response).await),
+        }
+    }
+
+    /// `GET /health/indexers`
+    pub async fn indexers(&self) -> ClientResult<IndexersResponse> {
+        self.send(self.request(Method::GET, "/health/indexers"))
+            .await
+    }
+
+    /// `POST /auth/signing-msg`
+    pub async fn request_signing_msg(&self, address: &str) -> ClientResult<SigningMsgResponse> {
+        let payload = SigningMsgPayload {
+            address: address.to_owned(),
+        };
+
+        self.send(
+            self.request(Method::POST, "/auth/signing-msg")
+                .json(&payload),
+        )
+        .await
+    }
+
+    /// `POST /auth/sign-in-evm` with an already signed message
+    pub async fn submit_sign_in_evm(
+        &self,
+        payload: &SignInEvmPayload,
+    ) -> ClientResult<SignInResponse> {
+        self.send(
+            self.request(Method::POST, "/auth/sign-in-evm")
+                .json(payload),
+        )
+        .await
+    }
+
+    /// `POST /auth/sign-in-sol` with an already signed message
+    pub async fn submit_sign_in_sol(
+        &self,
+        payload: &SignInSolPayload,
+    ) -> ClientResult<SignInResponse> {
+        self.send(
+            self.request(Method::POST, "/auth/sign-in-sol")
+                .json(payload),
+        )
+        .await
+    }
+
+    /// Requests a signing message, signs it with `signer` and keeps the returned token
+    pub async fn sign_in_evm(&mut self, signer: &PrivateKeySigner) -> ClientResult<String> {
+        let address = signer.address().to_string();
+        let SigningMsgResponse { msg } = self.request_signing_msg(&address).await?;
+
+        let signature = signer.sign_message(msg.as_bytes()).await?;
+
+        let SignInResponse { token } = self
+            .submit_sign_in_evm(&SignInEvmPayload {
+                address,
+                message: msg,
+                signature: signature.to_string(),
+            })
+            .await?;
+
+        self.token = Some(token.clone());
+
+        Ok(token)
+    }
+
+    /// Requests a signing message, signs it with `keypair` and keeps the returned token
+    pub async fn sign_in_sol(&mut self, keypair: &Keypair) -> ClientResult<String> {
+        let address = keypair.pubkey().to_string();
+        let SigningMsgResponse { msg } = self.request_signing_msg(&address).await?;
+
+        let signature = keypair.sign_message(msg.as_bytes());
+
+        let SignInResponse { token } = self
+            .submit_sign_in_sol(&SignInSolPayload {
+                address,
+                message: msg,
+                signature: signature.to_string(),
+            })
+            .await?;
+
+        self.token = Some(token.clone());
+
+        Ok(token)
+    }
+
+    /// `GET /users/me`
+    pub async fn me(&self) -> ClientResult<MeResponse> {
+        self.send(self.authed(Method::GET, "/users/me")?).await
+    }
+
+    /// `GET /admin/settings`
+    pub async fn settings(&self) -> ClientResult<SettingsResponse> {
+        self.send(self.authed(Method::GET, "/admin/settings")?)
+            .await
+    }
+
+    /// `GET /admin/settings/changes`, optionally only the changes of `key`
+    ///
+    /// Pass the previous page's `next_cursor` as `cursor` to continue
+    pub async fn setting_changes(
+        &self,
+        key: Option<&str>,
+        cursor: Option<&str>,
+        limit: Option<u64>,
+    ) -> ClientResult<SettingChangesResponse> {
+        let limit = limit.map(|limit| limit.to_string());
+        let query = query_of(&[
+            ("key", key),
+            ("cursor", cursor),
+            ("limit", limit.as_deref()),
+        ]);
+
+        self.send(
+            self.authed(Method::GET, "/admin/settings/changes")?
+                .query(&query),
+        )
+        .await
+    }
+
+    /// `GET /admin/cursors/evm/{chain_id}`
+    pub async fn evm_cursor(&self, chain_id: u64) -> ClientResult<EvmCursor> {
+        let path = format!("/admin/cursors/evm/{}", chain_id);
+
+        self.send(self.authed(Method::GET, &path)?).await
+    }
+
+    /// `PUT /admin/cursors/evm/{chain_id}`
+    pub async fn set_evm_cursor(&self, chain_id: u64, next_block: u64) -> ClientResult<EvmCursor> {
+        let path = format!("/admin/cursors/evm/{}", chain_id);
+        let payload = SetEvmCursorPayload { next_block };
+
+        self.send(self.authed(Method::PUT, &path)?.json(&payload))
+            .await
+    }
+
+    /// `GET /admin/cursors/solana`
+    pub async fn solana_cursor(&self) -> ClientResult<SolanaCursor> {
+        self.send(self.authed(Method::GET, "/admin/cursors/solana")?)
+            .await
+    }
+
+    /// `PUT /admin/cursors/solana`
+    pub async fn set_solana_cursor(&self, signature: &str) -> ClientResult<SolanaCursor> {
+        let payload = SetSolanaCursorPayload {
+            signature: signature.to_owned(),
+        };
+
+        self.send(
+            self.authed(Method::PUT, "/admin/cursors/solana")?
+                .json(&payload),
+        )
+        .await
+    }
+
+    /// Opens the `/random-u64` WebSocket feed
+    pub async fn subscribe_random_u64(&self) -> ClientResult<RandomU64Feed> {
+        let url = match self.base_url.split_once("://") {
+            Some(("https", rest)) => format!("wss://{}/random-u64", rest),
+            Some(("http", rest)) => format!("ws://{}/random-u64", rest),
+            _ => return Err(ClientError::InvalidUrl(self.base_url.clone())),
+        };
+
+        let uri = Uri::from_str(&url)
+            .ok()
+            .filter(|uri| uri.host().is_some_and(|host| !host.is_empty()))
+            .ok_or_else(|| ClientError::InvalidUrl(self.base_url.clone()))?;
+
+        Ok(RandomU64Feed::new(ws_client::connect(&uri).await?))
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http
+            .request(method, format!("{}{}", self.base_url, path))
+    }
+
+    /// `POST /webhooks`; keep the returned secret to verify deliveries
+    pub async fn create_webhook(&self, payload: &WebhookPayload) -> ClientResult<CreatedWebhook> {
+        self.send(self.authed(Method::POST, "/webhooks")?.json(payload))
+            .await
+    }
+
+    /// `GET /webhooks`
+    pub async fn webhooks(&self) -> ClientResult<WebhooksResponse> {
+        self.send(self.authed(Method::GET, "/webhooks")?).await
+    }
+
+    /// `GET /webhooks/{id}`
+    pub async fn webhook(&self, id: i32) -> ClientResult<Webhook> {
+        let path = format!("/webhooks/{}", id);
+
+        self.send(self.authed(Method::GET, &path)?).await
+    }
+
+    /// `PUT /webhooks/{id}`
+    pub async fn update_webhook(&self, id: i32, payload: &WebhookPayload) -> ClientResult<Webhook> {
+        let path = format!("/webhooks/{}", id);
+
+        self.send(self.authed(Method::PUT, &path)?.json(payload))
+            .await
+    }
+
+    /// `DELETE /webhooks/{id}`
+    pub async fn delete_webhook(&self, id: i32) -> ClientResult<()> {
+        let path = format!("/webhooks/{}", id);
+        let response = self.authed(Method::DELETE, &path)?.send().await?;
+
+        if !response.status().is_success() {
+            return Err(error_of(response).await);
+        }
+
+        Ok(())
+    }
+
+    /// `GET /webhooks/{id}/deliveries`, optionally only those in `status`
+    ///
+    /// Pass the previous page's `next_cursor` as `cursor` to continue
+    pub async fn webhook_deliveries(
+        &self,
+        id: i32,
+        status: Option<&str>,
+        cursor: Option<&str>,
+        limit: Option<u64>,
+    ) -> ClientResult<WebhookDeliveriesResponse> {
+        let path = format!("/webhooks/{}/deliveries", id);
+        let limit = limit.map(|limit| limit.to_string());
+        let query = query_of(&[
+            ("status", status),
+            ("cursor", cursor),
+            ("limit", limit.as_deref()),
+        ]);
+
+        self.send(self.authed(Method::GET, &path)?.query(&query))
+            .await
+    }
+
+    /// A request carrying the bearer token, failing if not signed in
+    fn authed(&self, method: Method, path: &str) -> ClientResult<RequestBuilder> {
+        let token = self.token.as_deref().ok_or(ClientError::NotSignedIn)?;
+
+        Ok(self.request(method, path).bearer_auth(token))
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> ClientResult<T> {
+        let response = request.send().await?;
+
+        if !response.status().is_success() {
+            return Err(error_of(response).await);
+        }
+
+        Ok(response.json().await?)
+    }
+}
+
+/// Query params whose value is set
+fn query_of<'a>(params: &[(&'a str, Option<&'a str>)]) -> Vec<(&'a str, &'a str)> {
+    params
+        .iter()
+        .filter_map(|(name, value)| value.map(|value| (*name, value)))
+        .collect()
+}
+
+/// Decodes an error response, keeping the raw body when it isn't an [`ErrorBody`]
+async fn error_of(response: reqwest::Response) -> ClientError {
+    let status = response.status().as_u16();
+
+    let body = match response.text().await {
+        Ok(body) => body,
+        Err(error) => return error.into(),
+    };
+
+    match serde_json::from_str::<ErrorBody>(&body) {
+        Ok(body) => ClientError::Api { status, body },
+        Err(_) => ClientError::UnexpectedResponse { status, body },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use alloy::signers::Signature;
+    use api_types::error::ErrorCode;
+    use axum::{
+        Json, Router,
+        http::{HeaderMap, StatusCode as Status, header},
+        routing::{get, post},
+    };
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    const TOKEN: &str = "token";
+
+    fn error_body(code: ErrorCode, status: Status, msg: &str) -> (Status, Json<Value>) {
+        (
+            status,
+            Json(json!({
+                "code": code,
+                "status": status.as_u16(),
+                "msg": msg,
+                "request_id": null,
+            })),
+        )
+    }
+
+    /// Serves the handful of endpoints the tests call, like http-server would
+    async fn server() -> SocketAddr {
+        let router = Router::new()
+            .route(
+                "/health/ready",
+                get(async || {
+                    (
+                        Status::SERVICE_UNAVAILABLE,
+                        Json(
+                            json!({ "status": "unavailable", "error": "database is unreachable" }),
+                        ),
+                    )
+                }),
+            )
+            .route(
+                "/health/indexers",
+                get(async || (Status::BAD_GATEWAY, "upstream is down")),
+            )
+            .route(
+                "/auth/signing-msg",
+                post(async |Json(payload): Json<SigningMsgPayload>| {
+                    Json(json!({ "msg": format!("sign in as {}", payload.address) }))
+                }),
+            )
+            .route(
+                "/auth/sign-in-evm",
+                post(async |Json(payload): Json<SignInEvmPayload>| {
+                    let signer =
+                        payload
+                            .signature
+                            .parse::<Signature>()
+                            .ok()
+                            .and_then(|signature| {
+                                signature.recover_address_from_msg(&payload.message).ok()
+                            });
+
+                    match signer {
+                        Some(signer) if signer.to_string() == payload.address => {
+                            (Status::OK, Json(json!({ "token": TOKEN })))
+                        }
+                        _ => error_body(
+                            ErrorCode::AuthInvalidSignature,
+                            Status::UNAUTHORIZED,
+                            "invalid signature",
+                        ),
+                    }
+                }),
+            )
+            .route(
+                "/users/me",
+                get(async |headers: HeaderMap| {
+                    let expected = format!("Bearer {}", TOKEN);
+
+                    match headers.get(header::AUTHORIZATION) {
+                        Some(value) if value == expected.as_str() => {
+                            (Status::OK, Json(json!({ "address": "0xme" })))
+                        }
+                        _ => error_body(
+                            ErrorCode::AuthInvalidToken,
+                            Status::UNAUTHORIZED,
+                            "invalid token",
+                        ),
+                    }
+                }),
+            );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        addr
+    }
+
+    async fn client() -> ApiClient {
+        // The trailing slash is trimmed
+        ApiClient::new(format!("http://{}/", server().await))
+    }
+
+    #[tokio::test]
+    async fn sign_in_keeps_the_token_for_later_requests() {
+        let mut client = client().await;
+
+        let token = client
+            .sign_in_evm(&PrivateKeySigner::random())
+            .await
+            .unwrap();
+        let me = client.me().await.unwrap();
+
+        assert_eq!(token, TOKEN);
+        assert_eq!(client.token(), Some(TOKEN));
+        assert_eq!(me.address, "0xme");
+    }
+
+    #[tokio::test]
+    async fn api_errors_are_decoded() {
+        let client = client().await.with_token("stale");
+
+        let error = client.me().await.unwrap_err();
+
+        let ClientError::Api { status, body } = error else {
+            panic!("expected an api error, got {:?}", error);
+        };
+        assert_eq!(status, 401);
+        assert_eq!(body.code, ErrorCode::AuthInvalidToken);
+    }
+
+    #[tokio::test]
+    async fn undocumented_errors_keep_the_raw_body() {
+        let client = client().await;
+
+        let error = client.indexers().await.unwrap_err();
+
+        let ClientError::UnexpectedResponse { status, body } = error else {
+            panic!("expected an unexpected response, got {:?}", error);
+        };
+        assert_eq!(status, 502);
+        assert_eq!(body, "upstream is down");
+    }
+
+    #[tokio::test]
+    async fn unavailable_server_is_reported_in_readiness() {
+        let client = client().await;
+
+        let readiness = client.readiness().await.unwrap();
+
+        assert_eq!(readiness.status, "unavailable");
+        assert_eq!(readiness.error.as_deref(), Some("database is unreachable"));
+    }
+
+    #[tokio::test]
+    async fn authed_requests_need_a_token() {
+        let client = ApiClient::new("http://localhost:1");
+
+        assert!(matches!(client.me().await, Err(ClientError::NotSignedIn)));
+        assert!(matches!(
+            client.delete_webhook(1).await,
+            Err(ClientError::NotSignedIn)
+        ));
+    }
+
+    #[tokio::test]
+    async fn websocket_needs_a_valid_base_url() {
+        for base_url in [
+            "localhost:8080",
+            "ftp://localhost",
+            "http://",
+            "http://bad host",
+        ] {
+            let result = ApiClient::new(base_url).subscribe_random_u64().await;
+
+            assert!(
+                matches!(result, Err(ClientError::InvalidUrl(_))),
+                "{}",
+                base_url
+            );
+        }
+    }
+
+    #[test]
+    fn only_set_query_params_are_sent() {
+        let query = query_of(&[("key", Some("a")), ("cursor", None), ("limit", Some("5"))]);
+
+        assert_eq!(query, [("key", "a"), ("limit", "5")]);
+    }
+}
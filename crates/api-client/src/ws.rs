@@ -0,0 +1,39 @@
+use fastwebsockets::OpCode;
+use ws_client::FrameCollector;
+
+use crate::error::{ClientError, ClientResult};
+
+/// Subscription to `/random-u64`
+///
+/// Pings are answered automatically.
+pub struct RandomU64Feed {
+    ws: FrameCollector,
+}
+
+impl RandomU64Feed {
+    pub(crate) fn new(ws: FrameCollector) -> Self {
+        Self { ws }
+    }
+
+    /// Waits for the next value, or `None` once the server closes the connection
+    pub async fn next(&mut self) -> ClientResult<Option<u64>> {
+        loop {
+            let frame = self.ws.read_frame().await?;
+
+            match frame.opcode {
+                OpCode::Binary | OpCode::Text => {
+                    let value = std::str::from_utf8(&frame.payload)
+                        .ok()
+                        .and_then(|text| text.parse().ok())
+                        .ok_or_else(|| {
+                            ClientError::InvalidFrame(format!("not a u64: {:?}", frame.payload))
+                        })?;
+
+                    return Ok(Some(value));
+                }
+                OpCode::Close => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+}
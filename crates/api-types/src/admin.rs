@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct SettingsResponse {
+    pub settings: Vec<SettingEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct SettingEntry {
+    #[cfg_attr(feature = "server", schema(example = "evm_scanned_block_chain_1"))]
+    pub key: String,
+    pub value: String,
+    pub updated_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct EvmCursor {
+    pub chain_id: u64,
+    /// Next block the scanner will read, absent before the scanner's first run
+    pub next_block: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct SetEvmCursorPayload {
+    /// Next block the scanner should read; must not be past the chain head
+    pub next_block: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct SolanaCursor {
+    /// Last scanned signature, absent before the scanner's first run
+    pub signature: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema, validator::Validate))]
+pub struct SetSolanaCursorPayload {
+    /// Signature to resume after; must be a known transaction
+    #[cfg_attr(
+        feature = "server",
+        validate(custom(function = "shared::validators::validate_solana_signature")),
+        schema(pattern = r"^[1-9A-HJ-NP-Za-km-z]{64,88}$")
+    )]
+    pub signature: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct SettingChangesResponse {
+    pub items: Vec<SettingChange>,
+    /// Pass as `cursor` to fetch the next page, absent on the last page
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct SettingChange {
+    pub id: i32,
+    pub key: String,
+    /// Absent when the change created the setting
+    pub old_value: Option<String>,
+    pub new_value: String,
+    /// Address of the admin who made the change
+    pub changed_by: String,
+    /// RFC 3339 timestamp
+    pub changed_at: String,
+}
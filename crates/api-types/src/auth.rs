@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema, validator::Validate))]
+pub struct SigningMsgPayload {
+    /// EVM or Solana wallet address
+    #[cfg_attr(
+        feature = "server",
+        validate(custom(function = "shared::validators::validate_union_address")),
+        schema(pattern = r"^(0x[0-9a-fA-F]{40}|[1-9A-HJ-NP-Za-km-z]{32,44})$")
+    )]
+    pub address: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct SigningMsgResponse {
+    /// The message to be signed by the wallet
+    pub msg: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema, validator::Validate))]
+pub struct SignInEvmPayload {
+    /// EVM wallet address (0x-prefixed hex, any case)
+    #[cfg_attr(
+        feature = "server",
+        validate(custom(function = "shared::validators::validate_evm_address")),
+        schema(pattern = r"^0x[0-9a-fA-F]{40}$")
+    )]
+    pub address: String,
+    /// The signing message that was signed
+    pub message: String,
+    /// Hex-encoded EVM signature
+    #[cfg_attr(
+        feature = "server",
+        validate(custom(function = "shared::validators::validate_evm_signature")),
+        schema(pattern = r"^(0x)?[0-9a-fA-F]{130}$")
+    )]
+    pub signature: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema, validator::Validate))]
+pub struct SignInSolPayload {
+    /// Solana wallet public key (base58)
+    #[cfg_attr(
+        feature = "server",
+        validate(custom(function = "shared::validators::validate_solana_pubkey")),
+        schema(pattern = r"^[1-9A-HJ-NP-Za-km-z]{32,44}$")
+    )]
+    pub address: String,
+    /// The signing message that was signed
+    pub message: String,
+    /// Base58-encoded Solana signature
+    #[cfg_attr(
+        feature = "server",
+        validate(custom(function = "shared::validators::validate_solana_signature")),
+        schema(pattern = r"^[1-9A-HJ-NP-Za-km-z]{64,88}$")
+    )]
+    pub signature: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct SignInResponse {
+    /// JWT bearer token
+    pub token: String,
+}
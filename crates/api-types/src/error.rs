@@ -0,0 +1,55 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Stable, machine-readable error codes returned in the `code` field of error bodies
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    BadRequest,
+    ValidationFailed,
+    InvalidInput,
+    Unauthorized,
+    AuthMissingToken,
+    AuthInvalidToken,
+    AuthTokenExpired,
+    AuthNonceExpired,
+    AuthInvalidMessage,
+    AuthInvalidSignature,
+    Forbidden,
+    NotFound,
+    Conflict,
+    IdempotencyKeyInProgress,
+    IdempotencyKeyReused,
+    TooManyRequests,
+    InternalError,
+}
+
+/// Body of every error response
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct ErrorBody {
+    /// Stable error code, safe to match on
+    pub code: ErrorCode,
+    /// HTTP status code
+    #[cfg_attr(feature = "server", schema(example = 400))]
+    pub status: u16,
+    /// Human-readable description of the error
+    pub msg: String,
+    /// Per-field validation failures, present when `code` is `VALIDATION_FAILED`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<BTreeMap<String, Vec<FieldError>>>,
+    /// Id of the failed request, echoed in the `X-Request-Id` response header
+    pub request_id: Option<String>,
+}
+
+/// A single failed validation rule on a payload field
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct FieldError {
+    /// Validator code, e.g. `length` or `invalid_evm_address`
+    pub code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
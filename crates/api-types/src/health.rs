@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct Liveness {
+    #[cfg_attr(feature = "server", schema(example = "ok"))]
+    pub status: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct Readiness {
+    /// `ready` or `unavailable`
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct IndexersResponse {
+    pub indexers: Vec<Indexer>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct Indexer {
+    #[cfg_attr(feature = "server", schema(example = "evm_scanner_chain_1"))]
+    pub name: String,
+    /// Next block to scan or last scanned signature
+    pub cursor: String,
+    pub head: Option<u64>,
+    pub lag: Option<u64>,
+    pub lagging: bool,
+    /// Present when the head could not be fetched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
@@ -0,0 +1,182 @@
+//! Request and response bodies of the HTTP API
+//!
+//! Shared by http-server and api-client so the two can't drift apart. The `server`
+//! feature adds the OpenAPI schema and validation derives the handlers rely on.
+
+pub mod admin;
+pub mod auth;
+pub mod error;
+pub mod health;
+pub mod users;
+pub mod webhooks;
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use regex::Regex;
+    use shared::validators::{
+        validate_evm_address, validate_evm_signature, validate_solana_pubkey,
+        validate_solana_signature, validate_union_address,
+    };
+    use utoipa::{
+        PartialSchema,
+        openapi::{RefOr, Schema},
+    };
+    use validator::ValidationError;
+
+    use crate::{admin, auth};
+
+    type Validate = fn(&str) -> Result<(), ValidationError>;
+
+    const EVM_ADDRESS: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+    const SOLANA_PUBKEY: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
+
+    fn pattern<T: PartialSchema>(field: &str) -> Regex {
+        let RefOr::T(Schema::Object(object)) = T::schema() else {
+            panic!("not an object schema");
+        };
+
+        match object.properties.get(field) {
+            Some(RefOr::T(Schema::Object(property))) => {
+                Regex::new(property.pattern.as_deref().expect("no pattern")).unwrap()
+            }
+            _ => panic!("no property {}", field),
+        }
+    }
+
+    fn evm_signature(prefix: &str, hex_len: usize) -> String {
+        // r and s of 0x11.. then v = 27
+        format!("{}{}1b", prefix, "1".repeat(hex_len - 2))
+    }
+
+    /// Samples matching the advertised pattern must pass the validator, others must fail
+    fn check(field: &str, pattern: Regex, validate: Validate, samples: &[(String, bool)]) {
+        for (sample, valid) in samples {
+            assert_eq!(
+                pattern.is_match(sample),
+                *valid,
+                "{} pattern on {:?}",
+                field,
+                sample
+            );
+            assert_eq!(
+                validate(sample).is_ok(),
+                *valid,
+                "{} validator on {:?}",
+                field,
+                sample
+            );
+        }
+    }
+
+    fn samples(samples: &[(&str, bool)]) -> Vec<(String, bool)> {
+        samples
+            .iter()
+            .map(|(sample, valid)| (sample.to_string(), *valid))
+            .collect()
+    }
+
+    fn evm_address_samples() -> Vec<(String, bool)> {
+        samples(&[
+            (EVM_ADDRESS, true),
+            (&EVM_ADDRESS.to_lowercase(), true),
+            (&EVM_ADDRESS[2..], false),
+            (&EVM_ADDRESS[..41], false),
+            (&format!("{}0", EVM_ADDRESS), false),
+            (&format!("0xg{}", &EVM_ADDRESS[3..]), false),
+            ("", false),
+        ])
+    }
+
+    fn solana_pubkey_samples() -> Vec<(String, bool)> {
+        samples(&[
+            (SOLANA_PUBKEY, true),
+            ("11111111111111111111111111111111", true),
+            ("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA", true),
+            (&"1".repeat(31), false),
+            (&format!("{}11", SOLANA_PUBKEY), false),
+            (&SOLANA_PUBKEY.replace('8', "0"), false),
+            (&SOLANA_PUBKEY.replace('8', "l"), false),
+            ("", false),
+        ])
+    }
+
+    fn solana_signature_samples() -> Vec<(String, bool)> {
+        let signature = "5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnbJLgp8uirBgmQpjKhoR4tjF3ZpRzrFmBV6UjKdiSZkQUW";
+
+        samples(&[
+            (signature, true),
+            (&"1".repeat(64), true),
+            (&"1".repeat(63), false),
+            (&format!("{}1", signature), false),
+            (&signature.replace('8', "0"), false),
+            ("", false),
+        ])
+    }
+
+    #[test]
+    fn union_address_pattern_matches_validator() {
+        let mut samples = evm_address_samples();
+        samples.extend(solana_pubkey_samples());
+
+        check(
+            "address",
+            pattern::<auth::SigningMsgPayload>("address"),
+            validate_union_address,
+            &samples,
+        );
+    }
+
+    #[test]
+    fn evm_address_pattern_matches_validator() {
+        check(
+            "address",
+            pattern::<auth::SignInEvmPayload>("address"),
+            validate_evm_address,
+            &evm_address_samples(),
+        );
+    }
+
+    #[test]
+    fn evm_signature_pattern_matches_validator() {
+        let samples = vec![
+            (evm_signature("0x", 130), true),
+            (evm_signature("", 130), true),
+            (evm_signature("0x", 128), false),
+            (evm_signature("0x", 132), false),
+            (evm_signature("0x", 130).replace('1', "z"), false),
+        ];
+
+        check(
+            "signature",
+            pattern::<auth::SignInEvmPayload>("signature"),
+            validate_evm_signature,
+            &samples,
+        );
+    }
+
+    #[test]
+    fn solana_pubkey_pattern_matches_validator() {
+        check(
+            "address",
+            pattern::<auth::SignInSolPayload>("address"),
+            validate_solana_pubkey,
+            &solana_pubkey_samples(),
+        );
+    }
+
+    #[test]
+    fn solana_signature_patterns_match_validator() {
+        check(
+            "signature",
+            pattern::<auth::SignInSolPayload>("signature"),
+            validate_solana_signature,
+            &solana_signature_samples(),
+        );
+        check(
+            "signature",
+            pattern::<admin::SetSolanaCursorPayload>("signature"),
+            validate_solana_signature,
+            &solana_signature_samples(),
+        );
+    }
+}
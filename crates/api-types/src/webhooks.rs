@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema, validator::Validate))]
+pub struct WebhookPayload {
+    /// Endpoint receiving `POST`ed events, on a public host. Redirects are not followed
+    #[cfg_attr(
+        feature = "server",
+        validate(
+            length(max = 2048),
+            custom(function = "shared::validators::validate_http_url")
+        ),
+        schema(example = "https://example.com/hooks/indexed")
+    )]
+    pub url: String,
+    /// Only deliver events of this chain, e.g. `1` or `solana`; any chain when absent
+    #[cfg_attr(feature = "server", validate(length(min = 1, max = 32)))]
+    #[serde(default)]
+    pub chain: Option<String>,
+    /// Only deliver events of this protocol, e.g. `uniswap_v3`; any protocol when absent
+    #[cfg_attr(feature = "server", validate(length(min = 1, max = 32)))]
+    #[serde(default)]
+    pub protocol: Option<String>,
+    /// Disabled webhooks receive nothing; re-enabling clears the failure count.
+    /// Ignored on creation
+    #[serde(default = "enabled_by_default")]
+    #[cfg_attr(feature = "server", schema(default = true))]
+    pub enabled: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct Webhook {
+    pub id: i32,
+    pub url: String,
+    pub chain: Option<String>,
+    pub protocol: Option<String>,
+    pub enabled: bool,
+    /// Failed deliveries in a row; the webhook is disabled when this gets too high
+    pub consecutive_failures: i32,
+    /// RFC 3339 timestamp
+    pub created_at: String,
+    /// RFC 3339 timestamp of automatic disabling
+    pub disabled_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct CreatedWebhook {
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    /// Key of the `X-Webhook-Signature` HMAC, only returned on creation
+    pub secret: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct WebhooksResponse {
+    pub webhooks: Vec<Webhook>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct WebhookDelivery {
+    pub id: i32,
+    /// `pending`, `delivered` or `failed`
+    pub status: String,
+    pub attempts: i32,
+    /// Body sent to the webhook, a JSON [`WebhookEvent`]
+    pub payload: String,
+    /// RFC 3339 timestamp of the next attempt while `pending`
+    pub next_attempt_at: String,
+    pub last_status_code: Option<u16>,
+    pub last_error: Option<String>,
+    /// RFC 3339 timestamp
+    pub created_at: String,
+    /// RFC 3339 timestamp
+    pub delivered_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct WebhookDeliveriesResponse {
+    pub items: Vec<WebhookDelivery>,
+    /// Pass as `cursor` to fetch the next page, absent on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Body `POST`ed to webhooks
+///
+/// Requests carry `X-Webhook-Timestamp` (unix seconds) and `X-Webhook-Signature:
+/// sha256=<hex>`, the HMAC-SHA256 of `<timestamp>.<body>` keyed with the webhook secret
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct WebhookEvent {
+    pub webhook_id: i32,
+    /// Chain id for EVM chains, `solana` for Solana
+    pub chain: String,
+    pub protocol: String,
+    pub tx_hash: String,
+    pub log_ix: i32,
+}
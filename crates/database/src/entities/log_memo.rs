@@ -11,6 +11,7 @@ pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub log_ix: i32,
     pub timestamp: i64,
+    pub chain: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -1,4 +1,10 @@
+pub mod failed_event;
+pub mod idempotency_key;
 pub mod log_memo;
+pub mod outbox;
 pub mod setting;
+pub mod setting_audit;
 pub mod signing_message;
 pub mod user;
+pub mod webhook;
+pub mod webhook_delivery;
@@ -1,20 +1,180 @@
 mod entities;
+pub mod migrations;
+pub mod notify;
+pub mod pagination;
+pub mod pool;
+pub mod repos;
 pub mod repositories;
+#[cfg(test)]
+mod testing;
 pub use sea_orm;
 use sea_orm::{ConnectOptions, Database, DatabaseConnection, DbErr};
+use shared::{
+    env::{self, Env},
+    result::Rs,
+};
+
+use crate::pool::PoolConfig;
 
 /// Establishes a connection to the database with optimized settings
 ///
+/// The backend is picked from the url: Postgres, or SQLite for `sqlite:` urls when the
+/// `sqlite` feature is enabled, e.g. `sqlite::memory:` or `sqlite://dev.db?mode=rwc`.
+/// The pool is sized from [`PoolConfig::from_env`], and a failing first connection is
+/// retried with exponential backoff.
+///
 /// # Arguments
 /// * `db_url` - Database connection URL
 ///
 /// # Returns
 /// A database connection or an error if connection fails
-pub async fn establish_connection(db_url: &str) -> Result<DatabaseConnection, DbErr> {
+pub async fn establish_connection(db_url: &str) -> Rs<DatabaseConnection> {
+    connect(db_url, "primary", &PoolConfig::from_env()?).await
+}
+
+/// Connects to the read replica at `DATABASE_READ_URL`, if one is configured
+///
+/// Only read-only queries that tolerate replication lag should be sent to it.
+pub async fn establish_replica_connection() -> Rs<Option<DatabaseConnection>> {
+    match env::read(Env::DatabaseReadUrl) {
+        Ok(db_url) => Ok(Some(
+            connect(&db_url, "replica", &PoolConfig::from_env()?).await?,
+        )),
+        Err(_) => Ok(None),
+    }
+}
+
+/// `role` names the connection in logs, `primary` or `replica`
+async fn connect(db_url: &str, role: &'static str, config: &PoolConfig) -> Rs<DatabaseConnection> {
+    let opt = connect_options(db_url, config)?;
+
+    tracing::info!(
+        role,
+        max_connections = config.max_connections,
+        "Connecting to the database"
+    );
+
+    let mut attempt = 0;
+
+    let mut db = loop {
+        match Database::connect(opt.clone()).await {
+            Ok(db) => break db,
+            Err(error) if attempt < config.connect_retries => {
+                attempt += 1;
+                let backoff = config.backoff(attempt);
+                tracing::warn!(
+                    ?error,
+                    role,
+                    attempt,
+                    ?backoff,
+                    "Database connection failed, retrying"
+                );
+                tokio::time::sleep(backoff).await;
+            }
+            Err(error) => return Err(error.into()),
+        }
+    };
+
+    db.set_metric_callback(|info| shared::metrics::record_db_query(info.elapsed, info.failed));
+
+    Ok(db)
+}
+
+fn connect_options(db_url: &str, config: &PoolConfig) -> Result<ConnectOptions, DbErr> {
     let mut opt = ConnectOptions::new(db_url);
-    opt.sqlx_logging(false);
+    opt.sqlx_logging(false)
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(config.acquire_timeout)
+        .idle_timeout(config.idle_timeout);
+
+    if let Some(timeout) = config.statement_timeout {
+        let timeout = timeout.as_millis().to_string();
+        opt.map_sqlx_postgres_opts(move |pg| pg.options([("statement_timeout", &timeout)]));
+    }
+
+    if db_url.starts_with("sqlite:") {
+        if !cfg!(feature = "sqlite") {
+            return Err(DbErr::Custom(
+                "SQLite urls need the `sqlite` feature of the database crate".to_owned(),
+            ));
+        }
+
+        // Each connection to an in-memory database gets its own, so keep a single one forever
+        if db_url.contains(":memory:") || db_url.contains("mode=memory") {
+            opt.max_connections(1)
+                .min_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None);
+        }
+    }
+
+    Ok(opt)
+}
+
+// Connecting to SQLite through the pool needs the feature, not only the dev-dependency
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    fn config(connect_retries: u32) -> PoolConfig {
+        PoolConfig {
+            connect_retries,
+            ..PoolConfig::from_lookup(&|_| None).unwrap()
+        }
+    }
+
+    /// A SQLite url in a directory that doesn't exist yet, so connecting fails until it does
+    fn missing_dir(name: &str) -> (std::path::PathBuf, String) {
+        let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let url = format!("sqlite://{}/db.sqlite?mode=rwc", dir.display());
+
+        (dir, url)
+    }
+
+    #[tokio::test]
+    async fn connect_retries_until_the_database_is_reachable() {
+        let (dir, url) = missing_dir("connect-retries");
+
+        let created = dir.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            std::fs::create_dir_all(created).unwrap();
+        });
+
+        let started = Instant::now();
+        let db = connect(&url, "primary", &config(3)).await.unwrap();
+
+        // The first attempt failed, the retry after the initial backoff succeeded
+        assert!(started.elapsed() >= config(3).backoff(1));
+        db.ping().await.unwrap();
+
+        db.close().await.unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn connect_gives_up_after_its_retries() {
+        let (_, url) = missing_dir("connect-gives-up");
+
+        let started = Instant::now();
+        let result = connect(&url, "primary", &config(1)).await;
+
+        assert!(result.is_err());
+        assert!(started.elapsed() >= config(1).backoff(1));
+        assert!(started.elapsed() < config(1).backoff(1) + config(1).backoff(2));
+    }
+
+    #[tokio::test]
+    async fn connect_fails_at_once_without_retries() {
+        let (_, url) = missing_dir("connect-no-retries");
 
-    println!("Connecting to db...");
+        let started = Instant::now();
 
-    Database::connect(opt).await
+        assert!(connect(&url, "primary", &config(0)).await.is_err());
+        assert!(started.elapsed() < config(0).backoff(1));
+    }
 }
@@ -0,0 +1,118 @@
+use sea_orm_migration::prelude::*;
+
+/// Tables previously managed by `prisma db push`, created only if missing so existing
+/// databases adopt the migration history as is
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(User::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(User::WalletAddress)
+                            .string_len(44)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Setting::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Setting::Key)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Setting::Value).string().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(LogMemo::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(LogMemo::Hash).string_len(88).not_null())
+                    .col(ColumnDef::new(LogMemo::LogIx).integer().not_null())
+                    .col(ColumnDef::new(LogMemo::Timestamp).big_integer().not_null())
+                    .primary_key(Index::create().col(LogMemo::Hash).col(LogMemo::LogIx))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(SigningMessage::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SigningMessage::Address)
+                            .string_len(44)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(SigningMessage::Message)
+                            .string_len(98)
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in [
+            SigningMessage::Table.into_iden(),
+            LogMemo::Table.into_iden(),
+            Setting::Table.into_iden(),
+            User::Table.into_iden(),
+        ] {
+            manager
+                .drop_table(Table::drop().table(table).if_exists().to_owned())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    WalletAddress,
+}
+
+#[derive(DeriveIden)]
+enum Setting {
+    Table,
+    Key,
+    Value,
+}
+
+#[derive(DeriveIden)]
+enum LogMemo {
+    Table,
+    Hash,
+    LogIx,
+    Timestamp,
+}
+
+#[derive(DeriveIden)]
+enum SigningMessage {
+    Table,
+    Address,
+    Message,
+}
@@ -0,0 +1,72 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SettingAudit::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SettingAudit::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(SettingAudit::Key).string().not_null())
+                    .col(ColumnDef::new(SettingAudit::OldValue).string())
+                    .col(ColumnDef::new(SettingAudit::NewValue).string().not_null())
+                    .col(
+                        ColumnDef::new(SettingAudit::ChangedBy)
+                            .string_len(44)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(SettingAudit::ChangedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("setting_audit_key_idx")
+                    .table(SettingAudit::Table)
+                    .col(SettingAudit::Key)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(SettingAudit::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SettingAudit {
+    Table,
+    Id,
+    Key,
+    OldValue,
+    NewValue,
+    ChangedBy,
+    ChangedAt,
+}
@@ -0,0 +1,65 @@
+use sea_orm_migration::{prelude::*, sea_orm::DbBackend};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DbBackend::Sqlite {
+            return up_sqlite(manager).await;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Setting::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Setting::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Setting::Table)
+                    .drop_column(Setting::UpdatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// SQLite has no `ADD COLUMN IF NOT EXISTS` and only adds columns with constant defaults
+async fn up_sqlite(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    if manager.has_column("setting", "updated_at").await? {
+        return Ok(());
+    }
+
+    manager
+        .alter_table(
+            Table::alter()
+                .table(Setting::Table)
+                .add_column(
+                    ColumnDef::new(Setting::UpdatedAt)
+                        .timestamp_with_time_zone()
+                        .not_null()
+                        .default("1970-01-01 00:00:00"),
+                )
+                .to_owned(),
+        )
+        .await
+}
+
+#[derive(DeriveIden)]
+enum Setting {
+    Table,
+    UpdatedAt,
+}
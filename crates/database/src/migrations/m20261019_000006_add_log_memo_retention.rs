@@ -0,0 +1,70 @@
+use sea_orm_migration::prelude::*;
+
+/// Tags memos with the chain they were indexed from, so each chain can be pruned on its
+/// own horizon, and indexes `timestamp` for the pruning job
+///
+/// Memos recorded before have an empty chain.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Checked up front since SQLite has no `ADD COLUMN IF NOT EXISTS`
+        if !manager.has_column("log_memo", "chain").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(LogMemo::Table)
+                        .add_column(
+                            ColumnDef::new(LogMemo::Chain)
+                                .string_len(16)
+                                .not_null()
+                                .default(""),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("log_memo_chain_timestamp_idx")
+                    .table(LogMemo::Table)
+                    .col(LogMemo::Chain)
+                    .col(LogMemo::Timestamp)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("log_memo_chain_timestamp_idx")
+                    .table(LogMemo::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(LogMemo::Table)
+                    .drop_column(LogMemo::Chain)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum LogMemo {
+    Table,
+    Chain,
+    Timestamp,
+}
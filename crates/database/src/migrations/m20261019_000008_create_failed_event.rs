@@ -0,0 +1,105 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(FailedEvent::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(FailedEvent::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(FailedEvent::Source)
+                            .string_len(32)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(FailedEvent::Chain).string_len(16).not_null())
+                    .col(ColumnDef::new(FailedEvent::EventKey).string().not_null())
+                    .col(ColumnDef::new(FailedEvent::Payload).text().not_null())
+                    .col(ColumnDef::new(FailedEvent::Error).text().not_null())
+                    .col(
+                        ColumnDef::new(FailedEvent::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(FailedEvent::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(FailedEvent::LastFailedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(FailedEvent::ResolvedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("failed_event_source_event_key_key")
+                    .table(FailedEvent::Table)
+                    .col(FailedEvent::Source)
+                    .col(FailedEvent::EventKey)
+                    .unique()
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("failed_event_source_chain_resolved_at_idx")
+                    .table(FailedEvent::Table)
+                    .col(FailedEvent::Source)
+                    .col(FailedEvent::Chain)
+                    .col(FailedEvent::ResolvedAt)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(FailedEvent::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum FailedEvent {
+    Table,
+    Id,
+    Source,
+    Chain,
+    EventKey,
+    Payload,
+    Error,
+    Attempts,
+    CreatedAt,
+    LastFailedAt,
+    ResolvedAt,
+}
@@ -0,0 +1,157 @@
+use std::str::FromStr;
+
+use sea_orm::{DatabaseConnection, DbBackend};
+use sea_orm_migration::{MigrationTrait, MigratorTrait};
+use shared::{
+    env::{self, Env},
+    result::{AppErr, Rs},
+};
+
+mod m20261019_000001_create_core_tables;
+mod m20261019_000002_create_idempotency_key;
+mod m20261019_000003_create_setting_audit;
+mod m20261019_000004_create_webhook;
+mod m20261019_000005_add_setting_updated_at;
+mod m20261019_000006_add_log_memo_retention;
+mod m20261019_000007_create_outbox;
+mod m20261019_000008_create_failed_event;
+
+/// Versioned schema of the database, applied in order
+pub struct Migrator;
+
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![
+            Box::new(m20261019_000001_create_core_tables::Migration),
+            Box::new(m20261019_000002_create_idempotency_key::Migration),
+            Box::new(m20261019_000003_create_setting_audit::Migration),
+            Box::new(m20261019_000004_create_webhook::Migration),
+            Box::new(m20261019_000005_add_setting_updated_at::Migration),
+            Box::new(m20261019_000006_add_log_memo_retention::Migration),
+            Box::new(m20261019_000007_create_outbox::Migration),
+            Box::new(m20261019_000008_create_failed_event::Migration),
+        ]
+    }
+}
+
+const USAGE: &str = "usage: migrate [up [<n>] | down [<n>] | status]";
+
+/// Runs the `migrate` subcommand with the arguments following it
+///
+/// `up` applies pending migrations, all of them unless a count is given, `down` rolls
+/// back the last one or `n`, `status` lists applied and pending migrations
+pub async fn run_command(db: &DatabaseConnection, args: &[String]) -> Rs<()> {
+    let steps = args
+        .get(1)
+        .map(|steps| steps.parse::<u32>())
+        .transpose()
+        .map_err(|_| AppErr::invalid_input(USAGE))?;
+
+    match args.first().map(String::as_str).unwrap_or("up") {
+        "up" => Migrator::up(db, steps).await?,
+        "down" => Migrator::down(db, Some(steps.unwrap_or(1))).await?,
+        "status" => {
+            for migration in Migrator::get_applied_migrations(db).await? {
+                println!("applied  {}", migration.name());
+            }
+
+            for migration in Migrator::get_pending_migrations(db).await? {
+                println!("pending  {}", migration.name());
+            }
+        }
+        _ => return Err(AppErr::invalid_input(USAGE)),
+    }
+
+    Ok(())
+}
+
+/// What to do about pending migrations on startup
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CheckMode {
+    Refuse,
+    Warn,
+    Apply,
+    Off,
+}
+
+impl FromStr for CheckMode {
+    type Err = AppErr;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "refuse" => Ok(Self::Refuse),
+            "warn" => Ok(Self::Warn),
+            "apply" => Ok(Self::Apply),
+            "off" => Ok(Self::Off),
+            mode => Err(AppErr::invalid_input(format!(
+                "invalid MIGRATION_CHECK: {}, expected refuse, warn, apply or off",
+                mode
+            ))),
+        }
+    }
+}
+
+/// Checks for pending migrations on startup, as configured by `MIGRATION_CHECK`
+///
+/// `refuse` fails when any are pending, `warn` logs them, `apply` applies them and `off`
+/// skips the check; any other value is an error. Unset, it is `warn`, or `apply` on
+/// SQLite, since an in-memory database can't be migrated from another process.
+pub async fn check(db: &DatabaseConnection) -> Rs<()> {
+    let mode = match env::read(Env::MigrationCheck) {
+        Ok(mode) => mode.parse()?,
+        Err(_) => match db.get_database_backend() {
+            DbBackend::Sqlite => CheckMode::Apply,
+            _ => CheckMode::Warn,
+        },
+    };
+
+    match mode {
+        CheckMode::Off => return Ok(()),
+        CheckMode::Apply => {
+            Migrator::up(db, None).await?;
+            return Ok(());
+        }
+        CheckMode::Refuse | CheckMode::Warn => {}
+    }
+
+    let pending = Migrator::get_pending_migrations(db)
+        .await?
+        .iter()
+        .map(|migration| migration.name().to_owned())
+        .collect::<Vec<_>>();
+
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let msg = format!(
+        "{} pending migrations: {}, run `http-server migrate`",
+        pending.len(),
+        pending.join(", ")
+    );
+
+    if mode == CheckMode::Refuse {
+        return Err(AppErr::custom(msg));
+    }
+
+    tracing::warn!("{}", msg);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_mode_accepts_only_known_values() {
+        assert_eq!("refuse".parse::<CheckMode>().unwrap(), CheckMode::Refuse);
+        assert_eq!("warn".parse::<CheckMode>().unwrap(), CheckMode::Warn);
+        assert_eq!("apply".parse::<CheckMode>().unwrap(), CheckMode::Apply);
+        assert_eq!("off".parse::<CheckMode>().unwrap(), CheckMode::Off);
+
+        for mode in ["", "Apply", "refuse ", "yes"] {
+            assert!(mode.parse::<CheckMode>().is_err(), "{:?}", mode);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::sync::{Arc, LazyLock};
+
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DbBackend, DbErr, RuntimeErr, Statement,
+    sqlx::postgres::PgListener,
+};
+use serde::{Deserialize, Serialize};
+use shared::result::{AppErr, Rs};
+use tokio::sync::broadcast;
+
+/// Postgres channel the indexers announce newly persisted events on
+const INDEXED_EVENT_CHANNEL: &str = "indexed_event";
+
+/// Payloads sent per statement by [`notify`]
+const PUBLISH_CHUNK_SIZE: usize = 500;
+
+/// Stands in for NOTIFY on backends without it, reaching listeners of this process only
+static LOCAL_EVENTS: LazyLock<broadcast::Sender<IndexedEvent>> =
+    LazyLock::new(|| broadcast::channel(1024).0);
+
+/// An event an indexer has just persisted
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IndexedEvent {
+    /// Chain id for EVM chains, `solana` for Solana
+    pub chain: String,
+    /// Protocol the event belongs to, e.g. `uniswap_v3` or `pumpfun`
+    pub protocol: String,
+    pub tx_hash: String,
+    pub log_ix: i32,
+}
+
+/// Announces `event` to every [`Listener`], across processes
+pub async fn publish(db: &DatabaseConnection, event: &IndexedEvent) -> Rs<()> {
+    publish_many(db, std::slice::from_ref(event)).await
+}
+
+/// Announces `events` to every [`Listener`], one statement per [`PUBLISH_CHUNK_SIZE`] events
+#[tracing::instrument(name = "db.notify.publish", skip_all, fields(events = events.len()))]
+pub async fn publish_many(db: &DatabaseConnection, events: &[IndexedEvent]) -> Rs<()> {
+    if db.get_database_backend() != DbBackend::Postgres {
+        for event in events {
+            // No listeners is fine, the event is simply dropped
+            let _ = LOCAL_EVENTS.send(event.clone());
+        }
+
+        return Ok(());
+    }
+
+    let payloads = events
+        .iter()
+        .map(serde_json::to_string)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| AppErr::custom(error.to_string()))?;
+
+    notify(db, INDEXED_EVENT_CHANNEL, &payloads).await
+}
+
+/// Sends `payloads` on the Postgres `channel`, one statement per [`PUBLISH_CHUNK_SIZE`]
+///
+/// Fails on other backends, which have no NOTIFY.
+pub async fn notify(db: &DatabaseConnection, channel: &str, payloads: &[String]) -> Rs<()> {
+    if db.get_database_backend() != DbBackend::Postgres {
+        return Err(AppErr::custom("NOTIFY needs a Postgres database"));
+    }
+
+    for chunk in payloads.chunks(PUBLISH_CHUNK_SIZE) {
+        let rows = (0..chunk.len())
+            .map(|ix| format!("(${})", ix + 2))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let values = std::iter::once(channel.into())
+            .chain(chunk.iter().map(|payload| payload.as_str().into()));
+
+        db.execute_raw(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            format!(
+                "SELECT pg_notify($1, payload) FROM (VALUES {}) AS event(payload)",
+                rows
+            ),
+            values,
+        ))
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Receives [`IndexedEvent`]s published by any process sharing the database
+///
+/// Without Postgres only events published by this process are received
+pub struct Listener(Source);
+
+enum Source {
+    Postgres(PgListener),
+    Local(broadcast::Receiver<IndexedEvent>),
+}
+
+impl Listener {
+    pub async fn connect(db: &DatabaseConnection) -> Rs<Self> {
+        if db.get_database_backend() != DbBackend::Postgres {
+            return Ok(Self(Source::Local(LOCAL_EVENTS.subscribe())));
+        }
+
+        let mut listener = PgListener::connect_with(db.get_postgres_connection_pool())
+            .await
+            .map_err(conn_err)?;
+
+        listener
+            .listen(INDEXED_EVENT_CHANNEL)
+            .await
+            .map_err(conn_err)?;
+
+        Ok(Self(Source::Postgres(listener)))
+    }
+
+    /// Waits for the next event, reconnecting transparently if the connection drops
+    ///
+    /// Events published while reconnecting are lost
+    pub async fn recv(&mut self) -> Rs<IndexedEvent> {
+        let listener = match &mut self.0 {
+            Source::Postgres(listener) => listener,
+            Source::Local(receiver) => return recv_local(receiver).await,
+        };
+
+        let notification = listener.recv().await.map_err(conn_err)?;
+
+        serde_json::from_str(notification.payload())
+            .map_err(|error| AppErr::custom(format!("invalid indexed event: {}", error)))
+    }
+}
+
+async fn recv_local(receiver: &mut broadcast::Receiver<IndexedEvent>) -> Rs<IndexedEvent> {
+    loop {
+        match receiver.recv().await {
+            Ok(event) => return Ok(event),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!(skipped, "Listener lagged behind, events were lost");
+            }
+            Err(broadcast::error::RecvError::Closed) => {
+                return Err(AppErr::custom("local event channel closed"));
+            }
+        }
+    }
+}
+
+fn conn_err(error: sea_orm::sqlx::Error) -> DbErr {
+    DbErr::Conn(RuntimeErr::SqlxError(Arc::new(error)))
+}
@@ -0,0 +1,288 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, EntityTrait, IdenStatic, Order, QueryFilter,
+    QueryOrder, QuerySelect, Select, Value,
+    prelude::{BigDecimal, DateTimeWithTimeZone},
+    sea_query::ColumnType,
+};
+use serde::{Deserialize, Serialize};
+use shared::result::{AppErr, Rs};
+
+/// Sort direction of a paginated query
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Opaque keyset cursor: the sort column and direction it was issued for and the
+/// last row's `(sort, tie_breaker)` values
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Cursor {
+    sort: String,
+    direction: Direction,
+    values: [serde_json::Value; 2],
+}
+
+/// A page of results and the cursor to request the following one
+#[derive(Serialize, Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PageRequest {
+    pub cursor: Option<Cursor>,
+    pub limit: u64,
+    pub direction: Direction,
+}
+
+impl Cursor {
+    /// Decodes a cursor previously returned in [`Page::next_cursor`]
+    pub fn decode(raw: &str) -> Option<Self> {
+        URL_SAFE_NO_PAD
+            .decode(raw)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+    }
+
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).expect("cursor is serializable"))
+    }
+}
+
+/// Applies keyset pagination to `select`, ordering by `sort` then `tie_breaker`
+///
+/// `tie_breaker` must be unique (usually the primary key) so pages never overlap.
+/// The returned cursor only remains valid for the same sort column and direction.
+pub async fn paginate<E, C>(
+    db: &C,
+    select: Select<E>,
+    sort: E::Column,
+    tie_breaker: E::Column,
+    request: &PageRequest,
+) -> Rs<Page<E::Model>>
+where
+    E: EntityTrait,
+    E::Model: Serialize,
+    C: ConnectionTrait,
+{
+    let order = match request.direction {
+        Direction::Asc => Order::Asc,
+        Direction::Desc => Order::Desc,
+    };
+
+    let mut select = select
+        .order_by(sort, order.clone())
+        .order_by(tie_breaker, order)
+        .limit(request.limit + 1);
+
+    if let Some(cursor) = &request.cursor {
+        if cursor.sort != sort.as_str() {
+            return Err(AppErr::invalid_input(
+                "cursor was issued for a different sort field",
+            ));
+        }
+
+        if cursor.direction != request.direction {
+            return Err(AppErr::invalid_input(
+                "cursor was issued for a different sort order",
+            ));
+        }
+
+        let after_sort = to_value(sort, &cursor.values[0])?;
+        let after_tie = to_value(tie_breaker, &cursor.values[1])?;
+
+        let condition = match request.direction {
+            Direction::Asc => Condition::any().add(sort.gt(after_sort.clone())).add(
+                Condition::all()
+                    .add(sort.eq(after_sort))
+                    .add(tie_breaker.gt(after_tie)),
+            ),
+            Direction::Desc => Condition::any().add(sort.lt(after_sort.clone())).add(
+                Condition::all()
+                    .add(sort.eq(after_sort))
+                    .add(tie_breaker.lt(after_tie)),
+            ),
+        };
+
+        select = select.filter(condition);
+    }
+
+    let mut items = select.all(db).await?;
+
+    let next_cursor = if items.len() as u64 > request.limit {
+        items.truncate(request.limit as usize);
+        items
+            .last()
+            .map(|last| cursor_of(last, sort, tie_breaker, request.direction))
+            .transpose()?
+    } else {
+        None
+    };
+
+    Ok(Page { items, next_cursor })
+}
+
+fn cursor_of<M: Serialize, Col: ColumnTrait>(
+    model: &M,
+    sort: Col,
+    tie_breaker: Col,
+    direction: Direction,
+) -> Rs<String> {
+    let row = serde_json::to_value(model).map_err(|error| AppErr::custom(error.to_string()))?;
+
+    let field = |column: Col| {
+        row.get(column.as_str())
+            .cloned()
+            .ok_or_else(|| AppErr::custom(format!("model has no field {}", column.as_str())))
+    };
+
+    let cursor = Cursor {
+        sort: sort.as_str().to_owned(),
+        direction,
+        values: [field(sort)?, field(tie_breaker)?],
+    };
+
+    Ok(cursor.encode())
+}
+
+/// Converts a cursor value back into a bind value of the column's SQL type
+fn to_value<Col: ColumnTrait>(column: Col, value: &serde_json::Value) -> Rs<Value> {
+    use serde_json::Value as Json;
+
+    let invalid = || AppErr::invalid_input(format!("invalid cursor value for {}", column.as_str()));
+
+    let value = match (column.def().get_column_type(), value) {
+        (ColumnType::TimestampWithTimeZone, Json::String(s)) => {
+            DateTimeWithTimeZone::parse_from_rfc3339(s)
+                .map_err(|_| invalid())?
+                .into()
+        }
+        // Decimals are parsed from their text so they round-trip exactly
+        (ColumnType::Decimal(_) | ColumnType::Money(_), Json::String(s)) => {
+            s.parse::<BigDecimal>().map_err(|_| invalid())?.into()
+        }
+        (ColumnType::Decimal(_) | ColumnType::Money(_), Json::Number(n)) => n
+            .to_string()
+            .parse::<BigDecimal>()
+            .map_err(|_| invalid())?
+            .into(),
+        (ColumnType::Float | ColumnType::Double, Json::Number(n)) => {
+            n.as_f64().ok_or_else(invalid)?.into()
+        }
+        (_, Json::String(s)) => s.clone().into(),
+        (_, Json::Bool(b)) => (*b).into(),
+        (_, Json::Number(n)) => n
+            .as_i64()
+            .map(Value::from)
+            .or_else(|| n.as_u64().map(Value::from))
+            .ok_or_else(invalid)?,
+        _ => return Err(invalid()),
+    };
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::Value;
+
+    use super::*;
+
+    mod item {
+        use sea_orm::entity::prelude::*;
+        use serde::Serialize;
+
+        #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+        #[sea_orm(table_name = "item")]
+        pub struct Model {
+            #[sea_orm(primary_key)]
+            pub id: i64,
+            #[sea_orm(column_type = "Decimal(Some((78, 0)))")]
+            pub amount: BigDecimal,
+            pub created_at: DateTimeWithTimeZone,
+        }
+
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {}
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+
+    fn model() -> item::Model {
+        item::Model {
+            id: 42,
+            amount:
+                "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+                    .parse()
+                    .unwrap(),
+            created_at: DateTimeWithTimeZone::parse_from_rfc3339(
+                "2026-10-19T08:00:00.123456+00:00",
+            )
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn cursor_round_trips_through_encoding() {
+        let cursor = Cursor {
+            sort: "created_at".to_owned(),
+            direction: Direction::Desc,
+            values: [
+                serde_json::json!("2026-10-19T08:00:00+00:00"),
+                serde_json::json!(7),
+            ],
+        };
+
+        assert_eq!(Cursor::decode(&cursor.encode()), Some(cursor));
+    }
+
+    #[test]
+    fn invalid_cursors_are_rejected() {
+        assert_eq!(Cursor::decode("not a cursor"), None);
+        assert_eq!(Cursor::decode(&URL_SAFE_NO_PAD.encode(b"{}")), None);
+    }
+
+    #[test]
+    fn cursor_values_keep_their_precision() {
+        let model = model();
+
+        let raw = cursor_of(
+            &model,
+            item::Column::Amount,
+            item::Column::Id,
+            Direction::Asc,
+        )
+        .unwrap();
+        let cursor = Cursor::decode(&raw).unwrap();
+
+        assert_eq!(cursor.sort, "amount");
+        assert_eq!(cursor.direction, Direction::Asc);
+        assert_eq!(
+            to_value(item::Column::Amount, &cursor.values[0]).unwrap(),
+            Value::from(model.amount.clone())
+        );
+        assert_eq!(
+            to_value(item::Column::Id, &cursor.values[1]).unwrap(),
+            Value::from(42i64)
+        );
+
+        let raw = cursor_of(
+            &model,
+            item::Column::CreatedAt,
+            item::Column::Id,
+            Direction::Asc,
+        )
+        .unwrap();
+        let cursor = Cursor::decode(&raw).unwrap();
+
+        assert_eq!(
+            to_value(item::Column::CreatedAt, &cursor.values[0]).unwrap(),
+            Value::from(model.created_at)
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use shared::{
+    env::{self, Env, parse_or},
+    result::Rs,
+};
+
+const DEFAULT_MAX_CONNECTIONS: u32 = 10;
+const DEFAULT_MIN_CONNECTIONS: u32 = 1;
+const DEFAULT_ACQUIRE_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 600;
+const DEFAULT_CONNECT_RETRIES: u32 = 5;
+
+/// First wait between connection attempts, doubled after each failure
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Connection pool settings, read from the environment
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// `DB_MAX_CONNECTIONS`
+    pub max_connections: u32,
+    /// `DB_MIN_CONNECTIONS`, kept open even when idle
+    pub min_connections: u32,
+    /// `DB_ACQUIRE_TIMEOUT_SECS`, how long a query waits for a free connection
+    pub acquire_timeout: Duration,
+    /// `DB_IDLE_TIMEOUT_SECS`, idle connections past `min_connections` are closed after it
+    pub idle_timeout: Duration,
+    /// `DB_STATEMENT_TIMEOUT_MS`, Postgres cancels statements running longer, `0` disables it
+    pub statement_timeout: Option<Duration>,
+    /// `DB_CONNECT_RETRIES`, attempts after the first failed connection on startup
+    pub connect_retries: u32,
+}
+
+impl PoolConfig {
+    pub fn from_env() -> Rs<Self> {
+        Self::from_lookup(&env::var)
+    }
+
+    pub(crate) fn from_lookup(lookup: &impl Fn(&Env) -> Option<String>) -> Rs<Self> {
+        Ok(Self {
+            max_connections: parse_or(lookup, Env::DbMaxConnections, DEFAULT_MAX_CONNECTIONS)?,
+            min_connections: parse_or(lookup, Env::DbMinConnections, DEFAULT_MIN_CONNECTIONS)?,
+            acquire_timeout: Duration::from_secs(parse_or(
+                lookup,
+                Env::DbAcquireTimeoutSecs,
+                DEFAULT_ACQUIRE_TIMEOUT_SECS,
+            )?),
+            idle_timeout: Duration::from_secs(parse_or(
+                lookup,
+                Env::DbIdleTimeoutSecs,
+                DEFAULT_IDLE_TIMEOUT_SECS,
+            )?),
+            statement_timeout: Some(parse_or(lookup, Env::DbStatementTimeoutMs, 0)?)
+                .filter(|ms| *ms > 0)
+                .map(Duration::from_millis),
+            connect_retries: parse_or(lookup, Env::DbConnectRetries, DEFAULT_CONNECT_RETRIES)?,
+        })
+    }
+
+    /// Wait before connection attempt `attempt`, counting from 1 for the first retry
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        shared::retry::backoff(INITIAL_BACKOFF, MAX_BACKOFF, attempt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn from_vars(vars: &[(&str, &str)]) -> Rs<PoolConfig> {
+        let vars = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>();
+
+        PoolConfig::from_lookup(&|key: &Env| vars.get(key.key().as_ref()).cloned())
+    }
+
+    #[test]
+    fn defaults_apply_when_unset() {
+        let config = from_vars(&[]).unwrap();
+
+        assert_eq!(config.max_connections, DEFAULT_MAX_CONNECTIONS);
+        assert_eq!(config.min_connections, DEFAULT_MIN_CONNECTIONS);
+        assert_eq!(
+            config.acquire_timeout,
+            Duration::from_secs(DEFAULT_ACQUIRE_TIMEOUT_SECS)
+        );
+        assert_eq!(
+            config.idle_timeout,
+            Duration::from_secs(DEFAULT_IDLE_TIMEOUT_SECS)
+        );
+        assert_eq!(config.statement_timeout, None);
+        assert_eq!(config.connect_retries, DEFAULT_CONNECT_RETRIES);
+    }
+
+    #[test]
+    fn variables_override_the_defaults() {
+        let config = from_vars(&[
+            ("DB_MAX_CONNECTIONS", "50"),
+            ("DB_MIN_CONNECTIONS", " 5 "),
+            ("DB_ACQUIRE_TIMEOUT_SECS", "3"),
+            ("DB_IDLE_TIMEOUT_SECS", "60"),
+            ("DB_STATEMENT_TIMEOUT_MS", "1500"),
+            ("DB_CONNECT_RETRIES", "0"),
+        ])
+        .unwrap();
+
+        assert_eq!(config.max_connections, 50);
+        assert_eq!(config.min_connections, 5);
+        assert_eq!(config.acquire_timeout, Duration::from_secs(3));
+        assert_eq!(config.idle_timeout, Duration::from_secs(60));
+        assert_eq!(config.statement_timeout, Some(Duration::from_millis(1500)));
+        assert_eq!(config.connect_retries, 0);
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        for (key, value) in [
+            ("DB_MAX_CONNECTIONS", "many"),
+            ("DB_MIN_CONNECTIONS", "-1"),
+            ("DB_STATEMENT_TIMEOUT_MS", "1.5"),
+        ] {
+            let error = from_vars(&[(key, value)]).unwrap_err();
+
+            assert!(format!("{:?}", error).contains(key), "{}", key);
+        }
+    }
+
+    #[test]
+    fn backoff_starts_at_the_initial_wait() {
+        let config = from_vars(&[]).unwrap();
+
+        assert_eq!(config.backoff(1), INITIAL_BACKOFF);
+        assert_eq!(config.backoff(u32::MAX), MAX_BACKOFF);
+    }
+}
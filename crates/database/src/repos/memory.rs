@@ -0,0 +1,168 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, hash_map::Entry},
+    sync::Mutex,
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use shared::{UnionAddress, UnionTxHash, result::Rs};
+
+use super::{LogMemoRepo, SettingRepo, SigningMessageRepo, UserRepo};
+use crate::{
+    entities::{setting, user},
+    repositories::{
+        log_memos::NewLogMemo,
+        settings::{Setting, StoredSetting},
+        signing_messages,
+        users::User,
+    },
+};
+
+/// Every repository kept in process memory, for tests and tooling without a database
+///
+/// Values are stored the way the SQL implementations store them, so both decode alike.
+#[derive(Default)]
+pub struct MemoryRepos {
+    users: Mutex<HashSet<String>>,
+    settings: Mutex<BTreeMap<String, StoredSetting>>,
+    log_memos: Mutex<HashMap<(String, i32), i64>>,
+    signing_messages: Mutex<HashMap<String, String>>,
+}
+
+#[async_trait]
+impl UserRepo for MemoryRepos {
+    async fn find_by_wallet_address(&self, address: UnionAddress) -> Rs<Option<User>> {
+        let address = address.to_string();
+
+        let user = self
+            .users
+            .lock()
+            .unwrap()
+            .contains(&address)
+            .then_some(user::Model {
+                wallet_address: address,
+            });
+
+        Ok(user)
+    }
+
+    async fn find_by_wallet_addresses(&self, addresses: Vec<UnionAddress>) -> Rs<Vec<User>> {
+        let users = self.users.lock().unwrap();
+
+        let found = addresses
+            .into_iter()
+            .map(|address| address.to_string())
+            .filter(|address| users.contains(address))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .map(|wallet_address| user::Model { wallet_address })
+            .collect();
+
+        Ok(found)
+    }
+
+    async fn save(&self, address: UnionAddress) -> Rs<()> {
+        self.users.lock().unwrap().insert(address.to_string());
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SettingRepo for MemoryRepos {
+    async fn get(&self, key: Setting) -> Rs<Option<String>> {
+        let settings = self.settings.lock().unwrap();
+
+        Ok(settings
+            .get(key.to_str_key().as_ref())
+            .map(|record| record.value.clone()))
+    }
+
+    async fn list(&self) -> Rs<Vec<StoredSetting>> {
+        Ok(self.settings.lock().unwrap().values().cloned().collect())
+    }
+
+    async fn change(
+        &self,
+        key: Setting,
+        value: String,
+        _changed_by: UnionAddress,
+    ) -> Rs<Option<String>> {
+        let key = key.to_str_key().into_owned();
+
+        let previous = self.settings.lock().unwrap().insert(
+            key.clone(),
+            setting::Model {
+                key,
+                value,
+                updated_at: Utc::now().fixed_offset(),
+            },
+        );
+
+        Ok(previous.map(|record| record.value))
+    }
+}
+
+#[async_trait]
+impl LogMemoRepo for MemoryRepos {
+    async fn is_existed(&self, hash: UnionTxHash, log_ix: i32) -> Rs<bool> {
+        let log_memos = self.log_memos.lock().unwrap();
+
+        Ok(log_memos.contains_key(&(hash.to_string(), log_ix)))
+    }
+
+    async fn save(&self, memo: NewLogMemo) -> Rs<bool> {
+        let mut log_memos = self.log_memos.lock().unwrap();
+
+        match log_memos.entry((memo.hash.to_string(), memo.log_ix)) {
+            Entry::Occupied(_) => Ok(false),
+            Entry::Vacant(entry) => {
+                entry.insert(memo.timestamp);
+                Ok(true)
+            }
+        }
+    }
+
+    async fn save_many(&self, memos: Vec<NewLogMemo>) -> Rs<u64> {
+        let mut log_memos = self.log_memos.lock().unwrap();
+        let mut inserted = 0;
+
+        for memo in memos {
+            if let Entry::Vacant(entry) = log_memos.entry((memo.hash.to_string(), memo.log_ix)) {
+                entry.insert(memo.timestamp);
+                inserted += 1;
+            }
+        }
+
+        Ok(inserted)
+    }
+}
+
+#[async_trait]
+impl SigningMessageRepo for MemoryRepos {
+    async fn allocate(&self, address: UnionAddress, message: String) -> Rs<()> {
+        signing_messages::check_message(&message)?;
+
+        self.signing_messages
+            .lock()
+            .unwrap()
+            .insert(address.to_string(), message);
+
+        Ok(())
+    }
+
+    async fn revoke(&self, address: UnionAddress) -> Rs<()> {
+        self.signing_messages
+            .lock()
+            .unwrap()
+            .remove(&address.to_string());
+
+        Ok(())
+    }
+
+    async fn get(&self, address: UnionAddress) -> Rs<Option<String>> {
+        let signing_messages = self.signing_messages.lock().unwrap();
+
+        Ok(signing_messages.get(&address.to_string()).cloned())
+    }
+}
@@ -0,0 +1,144 @@
+//! Repositories behind traits, so callers can swap the database for [`MemoryRepos`]
+//!
+//! The SQL implementations delegate to the free functions in [`crate::repositories`],
+//! which stay the way to run queries inside a caller's transaction. Reads that tolerate
+//! replication lag, like user lookups and listings, go to the read replica if any.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sea_orm::DatabaseConnection;
+use shared::{UnionAddress, UnionTxHash, result::Rs};
+
+use crate::repositories::{
+    log_memos::NewLogMemo,
+    settings::{self, Setting, StoredSetting, TypedSetting},
+    users::User,
+};
+
+pub use memory::MemoryRepos;
+pub use sql::SqlRepos;
+
+mod memory;
+mod sql;
+
+#[async_trait]
+pub trait UserRepo: Send + Sync {
+    async fn find_by_wallet_address(&self, address: UnionAddress) -> Rs<Option<User>>;
+
+    async fn find_by_wallet_addresses(&self, addresses: Vec<UnionAddress>) -> Rs<Vec<User>>;
+
+    /// Registers the wallet, doing nothing if it already is
+    async fn save(&self, address: UnionAddress) -> Rs<()>;
+}
+
+/// Settings as stored, decoded by the helpers on `dyn SettingRepo`
+#[async_trait]
+pub trait SettingRepo: Send + Sync {
+    async fn get(&self, key: Setting) -> Rs<Option<String>>;
+
+    /// Every stored row, including keys this build doesn't recognize
+    async fn list(&self) -> Rs<Vec<StoredSetting>>;
+
+    /// Sets `key` on behalf of `changed_by`, returning the previous value
+    async fn change(
+        &self,
+        key: Setting,
+        value: String,
+        changed_by: UnionAddress,
+    ) -> Rs<Option<String>>;
+}
+
+#[async_trait]
+pub trait LogMemoRepo: Send + Sync {
+    async fn is_existed(&self, hash: UnionTxHash, log_ix: i32) -> Rs<bool>;
+
+    /// Records the log as handled, returning `false` for a log seen before
+    async fn save(&self, memo: NewLogMemo) -> Rs<bool>;
+
+    /// Records many handled logs, returning how many weren't recorded yet
+    async fn save_many(&self, memos: Vec<NewLogMemo>) -> Rs<u64>;
+}
+
+#[async_trait]
+pub trait SigningMessageRepo: Send + Sync {
+    /// Sets the message `address` has to sign, replacing any previous one
+    async fn allocate(&self, address: UnionAddress, message: String) -> Rs<()>;
+
+    async fn revoke(&self, address: UnionAddress) -> Rs<()>;
+
+    async fn get(&self, address: UnionAddress) -> Rs<Option<String>>;
+}
+
+impl dyn SettingRepo {
+    pub async fn get_typed<K: TypedSetting>(&self, key: K) -> Rs<Option<K::Value>> {
+        let str_key = key.setting().to_str_key();
+
+        self.get(key.setting())
+            .await?
+            .map(|value| settings::decode(&str_key, &value))
+            .transpose()
+    }
+
+    /// Every recognized setting as `(key, value)`
+    pub async fn all(&self) -> Rs<Vec<(Setting, String)>> {
+        let settings = self
+            .list()
+            .await?
+            .into_iter()
+            .filter_map(|record| Setting::from_str_key(&record.key).map(|key| (key, record.value)))
+            .collect();
+
+        Ok(settings)
+    }
+
+    pub async fn change_typed<K, A>(
+        &self,
+        key: K,
+        value: &K::Value,
+        changed_by: A,
+    ) -> Rs<Option<String>>
+    where
+        K: TypedSetting,
+        A: Into<UnionAddress>,
+    {
+        let value = settings::encode(&key.setting().to_str_key(), value)?;
+
+        self.change(key.setting(), value, changed_by.into()).await
+    }
+}
+
+/// Every repository, backed by the same store
+#[derive(Clone)]
+pub struct Repos {
+    pub users: Arc<dyn UserRepo>,
+    pub settings: Arc<dyn SettingRepo>,
+    pub log_memos: Arc<dyn LogMemoRepo>,
+    pub signing_messages: Arc<dyn SigningMessageRepo>,
+}
+
+impl Repos {
+    /// Repositories querying `db`, with lag-tolerant reads sent to `replica` when given
+    pub fn sql(db: DatabaseConnection, replica: Option<DatabaseConnection>) -> Self {
+        let repos = Arc::new(SqlRepos::new(db, replica));
+
+        Self {
+            users: repos.clone(),
+            settings: repos.clone(),
+            log_memos: repos.clone(),
+            signing_messages: repos,
+        }
+    }
+
+    /// Repositories kept in process memory, starting empty
+    pub fn memory() -> Self {
+        let store = Arc::new(MemoryRepos::default());
+
+        Self {
+            users: store.clone(),
+            settings: store.clone(),
+            log_memos: store.clone(),
+            signing_messages: store,
+        }
+    }
+}
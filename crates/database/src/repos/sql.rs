@@ -0,0 +1,215 @@
+use async_trait::async_trait;
+use sea_orm::DatabaseConnection;
+use shared::{UnionAddress, UnionTxHash, result::Rs};
+
+use super::{LogMemoRepo, SettingRepo, SigningMessageRepo, UserRepo};
+use crate::repositories::{
+    log_memos::{self, NewLogMemo},
+    settings::{self, Setting, StoredSetting},
+    signing_messages,
+    users::{self, User},
+};
+
+/// Repositories querying the database, sending lag-tolerant reads to `reader`
+pub struct SqlRepos {
+    db: DatabaseConnection,
+    reader: DatabaseConnection,
+}
+
+impl SqlRepos {
+    /// Reads go to `replica` when given, to `db` otherwise
+    pub fn new(db: DatabaseConnection, replica: Option<DatabaseConnection>) -> Self {
+        let reader = replica.unwrap_or_else(|| db.clone());
+
+        Self { db, reader }
+    }
+}
+
+#[async_trait]
+impl UserRepo for SqlRepos {
+    async fn find_by_wallet_address(&self, address: UnionAddress) -> Rs<Option<User>> {
+        users::find_by_wallet_address(&self.reader, address).await
+    }
+
+    async fn find_by_wallet_addresses(&self, addresses: Vec<UnionAddress>) -> Rs<Vec<User>> {
+        users::find_by_wallet_addresses(&self.reader, addresses).await
+    }
+
+    async fn save(&self, address: UnionAddress) -> Rs<()> {
+        users::save(&self.db, address).await
+    }
+}
+
+#[async_trait]
+impl SettingRepo for SqlRepos {
+    async fn get(&self, key: Setting) -> Rs<Option<String>> {
+        settings::get_encoded(&self.db, key).await
+    }
+
+    async fn list(&self) -> Rs<Vec<StoredSetting>> {
+        settings::list(&self.reader).await
+    }
+
+    async fn change(
+        &self,
+        key: Setting,
+        value: String,
+        changed_by: UnionAddress,
+    ) -> Rs<Option<String>> {
+        settings::change_encoded(&self.db, key, value, changed_by).await
+    }
+}
+
+#[async_trait]
+impl LogMemoRepo for SqlRepos {
+    async fn is_existed(&self, hash: UnionTxHash, log_ix: i32) -> Rs<bool> {
+        log_memos::is_existed(&self.db, hash, log_ix).await
+    }
+
+    async fn save(&self, memo: NewLogMemo) -> Rs<bool> {
+        log_memos::save(
+            &self.db,
+            &memo.chain,
+            memo.hash,
+            memo.log_ix,
+            memo.timestamp,
+        )
+        .await
+    }
+
+    async fn save_many(&self, memos: Vec<NewLogMemo>) -> Rs<u64> {
+        log_memos::save_many(&self.db, memos)
+            .await
+            .map(|inserted| inserted.len() as u64)
+    }
+}
+
+#[async_trait]
+impl SigningMessageRepo for SqlRepos {
+    async fn allocate(&self, address: UnionAddress, message: String) -> Rs<()> {
+        signing_messages::allocate(&self.db, address, message).await
+    }
+
+    async fn revoke(&self, address: UnionAddress) -> Rs<()> {
+        signing_messages::revoke(&self.db, address).await
+    }
+
+    async fn get(&self, address: UnionAddress) -> Rs<Option<String>> {
+        signing_messages::get(&self.db, address).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{repositories::settings::EvmScannedBlock, testing};
+
+    const ADDRESS: &str = "0x00000000000000000000000000000000000000aa";
+
+    fn address() -> UnionAddress {
+        ADDRESS.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn lag_tolerant_reads_go_to_the_replica() {
+        let (primary, replica) = (testing::db().await, testing::db().await);
+        let repos = SqlRepos::new(primary.clone(), Some(replica.clone()));
+        let users: &dyn UserRepo = &repos;
+
+        users.save(address()).await.unwrap();
+
+        // Written to the primary, which the replica hasn't caught up with
+        assert!(
+            users::find_by_wallet_address(&primary, address())
+                .await
+                .unwrap()
+                .is_some()
+        );
+        assert!(
+            users
+                .find_by_wallet_address(address())
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            users
+                .find_by_wallet_addresses(vec![address()])
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        users::save(&replica, address()).await.unwrap();
+
+        assert!(
+            users
+                .find_by_wallet_address(address())
+                .await
+                .unwrap()
+                .is_some()
+        );
+        assert_eq!(
+            users
+                .find_by_wallet_addresses(vec![address()])
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        settings::set(&primary, EvmScannedBlock(56), &10)
+            .await
+            .unwrap();
+        let settings: &dyn SettingRepo = &repos;
+
+        assert!(settings.list().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn reads_that_must_be_current_go_to_the_primary() {
+        let (primary, replica) = (testing::db().await, testing::db().await);
+        let repos = SqlRepos::new(primary.clone(), Some(replica));
+
+        settings::set(&primary, EvmScannedBlock(56), &10)
+            .await
+            .unwrap();
+        let settings: &dyn SettingRepo = &repos;
+
+        assert_eq!(
+            settings.get_typed(EvmScannedBlock(56)).await.unwrap(),
+            Some(10)
+        );
+
+        let log_memos: &dyn LogMemoRepo = &repos;
+        let hash = UnionTxHash::from(solana_sdk::signature::Signature::from([7; 64]));
+        let memo = NewLogMemo {
+            chain: "solana".to_owned(),
+            hash,
+            log_ix: 0,
+            timestamp: 1,
+        };
+
+        assert!(log_memos.save(memo).await.unwrap());
+        assert!(log_memos.is_existed(hash, 0).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn reads_go_to_the_primary_without_a_replica() {
+        let db = testing::db().await;
+        let repos = SqlRepos::new(db.clone(), None);
+        let users: &dyn UserRepo = &repos;
+
+        users.save(address()).await.unwrap();
+        settings::set(&db, EvmScannedBlock(56), &10).await.unwrap();
+
+        assert!(
+            users
+                .find_by_wallet_address(address())
+                .await
+                .unwrap()
+                .is_some()
+        );
+        assert_eq!((&repos as &dyn SettingRepo).list().await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,230 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveValue::Set,
+    ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect,
+    sea_query::{Expr, ExprTrait, OnConflict},
+};
+use shared::result::Rs;
+
+use crate::entities::failed_event;
+
+pub use crate::entities::failed_event::Model as FailedEvent;
+
+/// An event its handler gave up on, kept raw so it can be replayed
+pub struct NewFailedEvent {
+    /// The process that failed it, e.g. `evm_stream`
+    pub source: String,
+    pub chain: String,
+    /// Identifies the event within its source
+    pub key: String,
+    /// What the handler was given, serialized so it can be handed back on replay
+    pub payload: String,
+    pub error: String,
+    pub attempts: u32,
+}
+
+/// Dead-letters `event`
+///
+/// An event dead-lettered again, e.g. after a failed replay, reopens its row and adds
+/// to its attempts rather than being recorded twice.
+#[tracing::instrument(name = "db.failed_events.record", skip_all, fields(source = event.source, key = event.key))]
+pub async fn record<C: ConnectionTrait>(db: &C, event: NewFailedEvent) -> Rs<()> {
+    failed_event::Entity::insert(failed_event::ActiveModel {
+        source: Set(event.source),
+        chain: Set(event.chain),
+        event_key: Set(event.key),
+        payload: Set(event.payload),
+        error: Set(event.error),
+        attempts: Set(event.attempts as i32),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::columns([failed_event::Column::Source, failed_event::Column::EventKey])
+            .update_columns([failed_event::Column::Payload, failed_event::Column::Error])
+            .value(
+                failed_event::Column::Attempts,
+                Expr::cust("failed_event.attempts + excluded.attempts"),
+            )
+            .value(
+                failed_event::Column::LastFailedAt,
+                Expr::current_timestamp(),
+            )
+            .value(
+                failed_event::Column::ResolvedAt,
+                Expr::value(Option::<DateTime<Utc>>::None),
+            )
+            .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Unresolved events of `source` on `chain` with an id above `after`, oldest first
+pub async fn list_unresolved(
+    db: &DatabaseConnection,
+    source: &str,
+    chain: &str,
+    after: i64,
+    limit: u64,
+) -> Rs<Vec<FailedEvent>> {
+    let events = failed_event::Entity::find()
+        .filter(failed_event::Column::Source.eq(source))
+        .filter(failed_event::Column::Chain.eq(chain))
+        .filter(failed_event::Column::ResolvedAt.is_null())
+        .filter(failed_event::Column::Id.gt(after))
+        .order_by_asc(failed_event::Column::Id)
+        .limit(limit)
+        .all(db)
+        .await?;
+
+    Ok(events)
+}
+
+/// Marks event `id` as replayed successfully
+pub async fn resolve(db: &DatabaseConnection, id: i64) -> Rs<()> {
+    failed_event::Entity::update_many()
+        .col_expr(failed_event::Column::ResolvedAt, Expr::current_timestamp())
+        .filter(failed_event::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Records a failed replay of event `id`, which stays dead-lettered
+pub async fn record_replay_failure(db: &DatabaseConnection, id: i64, error: String) -> Rs<()> {
+    failed_event::Entity::update_many()
+        .col_expr(
+            failed_event::Column::Attempts,
+            Expr::col(failed_event::Column::Attempts).add(1),
+        )
+        .col_expr(failed_event::Column::Error, Expr::value(error))
+        .col_expr(
+            failed_event::Column::LastFailedAt,
+            Expr::current_timestamp(),
+        )
+        .filter(failed_event::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::PaginatorTrait;
+
+    use super::*;
+    use crate::testing;
+
+    fn event(source: &str, key: &str, attempts: u32) -> NewFailedEvent {
+        NewFailedEvent {
+            source: source.to_owned(),
+            chain: "56".to_owned(),
+            key: key.to_owned(),
+            payload: format!(r#"{{"attempts":{}}}"#, attempts),
+            error: format!("failed {} times", attempts),
+            attempts,
+        }
+    }
+
+    async fn find(db: &DatabaseConnection, key: &str) -> FailedEvent {
+        failed_event::Entity::find()
+            .filter(failed_event::Column::EventKey.eq(key))
+            .one(db)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn recording_again_reopens_the_row_and_adds_attempts() {
+        let db = testing::db().await;
+
+        record(&db, event("evm_stream", "0xa:0", 3)).await.unwrap();
+        let first = find(&db, "0xa:0").await;
+        resolve(&db, first.id).await.unwrap();
+        assert!(find(&db, "0xa:0").await.resolved_at.is_some());
+
+        record(&db, event("evm_stream", "0xa:0", 2)).await.unwrap();
+        let second = find(&db, "0xa:0").await;
+
+        assert_eq!(second.id, first.id);
+        assert_eq!(second.attempts, 5);
+        assert_eq!(second.payload, r#"{"attempts":2}"#);
+        assert_eq!(second.error, "failed 2 times");
+        assert_eq!(second.resolved_at, None);
+        assert_eq!(failed_event::Entity::find().count(&db).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn the_same_key_from_another_source_is_its_own_event() {
+        let db = testing::db().await;
+
+        record(&db, event("evm_stream", "key", 1)).await.unwrap();
+        record(&db, event("solana_scanner", "key", 1))
+            .await
+            .unwrap();
+
+        assert_eq!(failed_event::Entity::find().count(&db).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn unresolved_events_are_listed_in_order_after_the_given_id() {
+        let db = testing::db().await;
+
+        for key in ["a", "b", "c"] {
+            record(&db, event("evm_stream", key, 1)).await.unwrap();
+        }
+        record(&db, event("solana_scanner", "d", 1)).await.unwrap();
+        resolve(&db, find(&db, "b").await.id).await.unwrap();
+
+        let keys = |events: Vec<FailedEvent>| {
+            events
+                .into_iter()
+                .map(|event| event.event_key)
+                .collect::<Vec<_>>()
+        };
+
+        let all = list_unresolved(&db, "evm_stream", "56", 0, 10)
+            .await
+            .unwrap();
+        let after = all[0].id;
+        assert_eq!(keys(all), ["a", "c"]);
+
+        let rest = list_unresolved(&db, "evm_stream", "56", after, 10)
+            .await
+            .unwrap();
+        assert_eq!(keys(rest), ["c"]);
+
+        let page = list_unresolved(&db, "evm_stream", "56", 0, 1)
+            .await
+            .unwrap();
+        assert_eq!(keys(page), ["a"]);
+
+        let other_chain = list_unresolved(&db, "evm_stream", "1", 0, 10)
+            .await
+            .unwrap();
+        assert!(other_chain.is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_failed_replay_bumps_the_attempts_and_keeps_the_event_open() {
+        let db = testing::db().await;
+
+        record(&db, event("evm_stream", "0xa:0", 3)).await.unwrap();
+        let id = find(&db, "0xa:0").await.id;
+
+        record_replay_failure(&db, id, "still failing".to_owned())
+            .await
+            .unwrap();
+        let event = find(&db, "0xa:0").await;
+
+        assert_eq!(event.attempts, 4);
+        assert_eq!(event.error, "still failing");
+        assert_eq!(event.resolved_at, None);
+    }
+}
@@ -0,0 +1,161 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter,
+    sea_query::OnConflict,
+};
+use shared::result::{AppErr, Rs};
+
+use crate::entities::idempotency_key;
+
+/// How long a request may hold a key before it's considered abandoned, e.g. after a crash
+const LOCK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A response recorded for replay
+pub struct StoredResponse {
+    pub status_code: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// Outcome of trying to claim an idempotency key
+pub enum Claim {
+    /// The caller owns the key and must [`complete`] or [`release`] it
+    Acquired,
+    /// Another request with this key is still running
+    InProgress,
+    /// The key was already used for a request with a different fingerprint
+    Mismatch,
+    /// A request with this key already finished; its response should be replayed
+    Completed(StoredResponse),
+}
+
+/// Claims `key` within `scope`, usually the caller's address, or reports why it can't
+/// be claimed
+///
+/// Expired keys and keys held longer than the lock timeout are taken over
+#[tracing::instrument(name = "db.idempotency_keys.claim", skip_all)]
+pub async fn claim(
+    db: &DatabaseConnection,
+    key: &str,
+    scope: &str,
+    fingerprint: &str,
+    ttl: Duration,
+) -> Rs<Claim> {
+    let now = Utc::now();
+    let lock_expired_at = now - to_chrono(LOCK_TIMEOUT)?;
+
+    idempotency_key::Entity::delete_many()
+        .filter(idempotency_key::Column::Key.eq(key))
+        .filter(idempotency_key::Column::Address.eq(scope))
+        .filter(
+            Condition::any()
+                .add(idempotency_key::Column::ExpiresAt.lt(now))
+                .add(
+                    Condition::all()
+                        .add(idempotency_key::Column::StatusCode.is_null())
+                        .add(idempotency_key::Column::CreatedAt.lt(lock_expired_at)),
+                ),
+        )
+        .exec(db)
+        .await?;
+
+    let inserted = idempotency_key::Entity::insert(idempotency_key::ActiveModel {
+        key: Set(key.to_owned()),
+        address: Set(scope.to_owned()),
+        fingerprint: Set(fingerprint.to_owned()),
+        status_code: Set(None),
+        content_type: Set(None),
+        response_body: Set(None),
+        created_at: Set(now.into()),
+        expires_at: Set((now + to_chrono(ttl)?).into()),
+    })
+    .on_conflict(
+        OnConflict::columns([
+            idempotency_key::Column::Key,
+            idempotency_key::Column::Address,
+        ])
+        .do_nothing()
+        .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await?;
+
+    if inserted == 1 {
+        return Ok(Claim::Acquired);
+    }
+
+    let Some(existing) = idempotency_key::Entity::find_by_id((key.to_owned(), scope.to_owned()))
+        .one(db)
+        .await?
+    else {
+        // Released between the insert and the lookup, the client can retry right away
+        return Ok(Claim::InProgress);
+    };
+
+    if existing.fingerprint != fingerprint {
+        return Ok(Claim::Mismatch);
+    }
+
+    let claim = match existing.status_code {
+        Some(status_code) => Claim::Completed(StoredResponse {
+            status_code: status_code as u16,
+            content_type: existing.content_type,
+            body: existing.response_body.unwrap_or_default(),
+        }),
+        None => Claim::InProgress,
+    };
+
+    Ok(claim)
+}
+
+/// Stores the response of a claimed key so retries replay it
+#[tracing::instrument(name = "db.idempotency_keys.complete", skip_all)]
+pub async fn complete(
+    db: &DatabaseConnection,
+    key: &str,
+    scope: &str,
+    response: StoredResponse,
+) -> Rs<()> {
+    idempotency_key::Entity::update_many()
+        .set(idempotency_key::ActiveModel {
+            status_code: Set(Some(response.status_code as i16)),
+            content_type: Set(response.content_type),
+            response_body: Set(Some(response.body)),
+            ..Default::default()
+        })
+        .filter(idempotency_key::Column::Key.eq(key))
+        .filter(idempotency_key::Column::Address.eq(scope))
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Gives up a claimed key without storing a response, so it can be retried
+#[tracing::instrument(name = "db.idempotency_keys.release", skip_all)]
+pub async fn release(db: &DatabaseConnection, key: &str, scope: &str) -> Rs<()> {
+    idempotency_key::Entity::delete_many()
+        .filter(idempotency_key::Column::Key.eq(key))
+        .filter(idempotency_key::Column::Address.eq(scope))
+        .filter(idempotency_key::Column::StatusCode.is_null())
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Deletes expired keys, returning how many were removed
+pub async fn purge_expired(db: &DatabaseConnection) -> Rs<u64> {
+    let result = idempotency_key::Entity::delete_many()
+        .filter(idempotency_key::Column::ExpiresAt.lt(Utc::now()))
+        .exec(db)
+        .await?;
+
+    Ok(result.rows_affected)
+}
+
+fn to_chrono(duration: Duration) -> Rs<chrono::Duration> {
+    chrono::Duration::from_std(duration).map_err(|error| AppErr::custom(error.to_string()))
+}
@@ -1,25 +1,52 @@
-use sea_orm::{ActiveValue::Set, DatabaseConnection, EntityTrait};
+use std::collections::HashSet;
+
+use sea_orm::{
+    ActiveValue::Set,
+    ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect,
+    QueryTrait, TryInsertResult,
+    sea_query::{Expr, ExprTrait, OnConflict},
+};
 use shared::{UnionTxHash, result::Rs};
 
 use crate::entities::log_memo;
 
-pub async fn save<H: Into<UnionTxHash>>(
-    db: &DatabaseConnection,
-    hash: H,
-    log_ix: i32,
-    timestamp: i64,
-) -> Rs<()> {
-    log_memo::Entity::insert(log_memo::ActiveModel {
+/// Rows per statement in bulk operations, well below Postgres' bind parameter limit
+const CHUNK_SIZE: usize = 1_000;
+
+/// A handled log to record
+pub struct NewLogMemo {
+    /// Chain the log was indexed from, an EVM chain id or `solana`
+    pub chain: String,
+    pub hash: UnionTxHash,
+    pub log_ix: i32,
+    /// Block time of the log, or a later one; 0 when unknown, which is never pruned
+    pub timestamp: i64,
+}
+
+/// Records the log as handled unless it already is
+///
+/// Returns `false` for a log seen before. Run it in the transaction holding the log's
+/// side effects, so they commit together and concurrent workers can't both claim it.
+#[tracing::instrument(name = "db.log_memos.save", skip_all, fields(log_ix = log_ix))]
+pub async fn save<C, H>(db: &C, chain: &str, hash: H, log_ix: i32, timestamp: i64) -> Rs<bool>
+where
+    C: ConnectionTrait,
+    H: Into<UnionTxHash>,
+{
+    let inserted = log_memo::Entity::insert(log_memo::ActiveModel {
         hash: Set(hash.into().to_string()),
         log_ix: Set(log_ix),
         timestamp: Set(timestamp),
+        chain: Set(chain.to_owned()),
     })
-    .exec(db)
+    .on_conflict_do_nothing()
+    .exec_without_returning(db)
     .await?;
 
-    Ok(())
+    Ok(matches!(inserted, TryInsertResult::Inserted(1)))
 }
 
+#[tracing::instrument(name = "db.log_memos.is_existed", skip_all, fields(log_ix = log_ix))]
 pub async fn is_existed<H: Into<UnionTxHash>>(
     db: &DatabaseConnection,
     hash: H,
@@ -32,3 +59,125 @@ pub async fn is_existed<H: Into<UnionTxHash>>(
 
     Ok(is_existed)
 }
+
+/// Records many handled logs, in chunks of [`CHUNK_SIZE`], skipping those already recorded
+///
+/// Returns the `(hash, log_ix)` keys inserted, the logs this call claimed: run it in the
+/// transaction holding their side effects and only apply those of the returned keys, so
+/// concurrent workers never both handle a log.
+#[tracing::instrument(name = "db.log_memos.save_many", skip_all)]
+pub async fn save_many<C: ConnectionTrait>(
+    db: &C,
+    memos: Vec<NewLogMemo>,
+) -> Rs<HashSet<(String, i32)>> {
+    let mut inserted = HashSet::new();
+
+    for chunk in memos.chunks(CHUNK_SIZE) {
+        let records = chunk.iter().map(|memo| log_memo::ActiveModel {
+            hash: Set(memo.hash.to_string()),
+            log_ix: Set(memo.log_ix),
+            timestamp: Set(memo.timestamp),
+            chain: Set(memo.chain.clone()),
+        });
+
+        let keys = log_memo::Entity::insert_many(records)
+            .on_conflict(
+                OnConflict::columns([log_memo::Column::Hash, log_memo::Column::LogIx])
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_with_returning_keys(db)
+            .await?;
+
+        inserted.extend(keys);
+    }
+
+    Ok(inserted)
+}
+
+/// Deletes up to `limit` memos of `chain` older than `before`, a unix timestamp
+///
+/// Memos without a time are kept, there is no telling whether a scanner still needs
+/// them. Returns how many were deleted, fewer than `limit` once nothing older is left.
+#[tracing::instrument(name = "db.log_memos.prune", skip(db))]
+pub async fn prune<C: ConnectionTrait>(db: &C, chain: &str, before: i64, limit: u64) -> Rs<u64> {
+    let batch = log_memo::Entity::find()
+        .select_only()
+        .column(log_memo::Column::Hash)
+        .column(log_memo::Column::LogIx)
+        .filter(log_memo::Column::Chain.eq(chain))
+        .filter(log_memo::Column::Timestamp.gt(0))
+        .filter(log_memo::Column::Timestamp.lt(before))
+        .limit(limit)
+        .into_query();
+
+    let deleted = log_memo::Entity::delete_many()
+        .filter(
+            Expr::tuple([
+                Expr::col(log_memo::Column::Hash),
+                Expr::col(log_memo::Column::LogIx),
+            ])
+            .in_subquery(batch),
+        )
+        .exec(db)
+        .await?;
+
+    Ok(deleted.rows_affected)
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::UnionTxHash;
+    use solana_sdk::signature::Signature;
+
+    use super::*;
+    use crate::testing;
+
+    fn memo(hash: UnionTxHash, log_ix: i32) -> NewLogMemo {
+        NewLogMemo {
+            chain: "solana".to_owned(),
+            hash,
+            log_ix,
+            timestamp: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn prune_keeps_recent_and_untimed_memos() {
+        let db = testing::db().await;
+        let hash = UnionTxHash::from(Signature::from([7; 64]));
+
+        let memos = [(0, 0), (1, 100), (2, 200), (3, 300)]
+            .into_iter()
+            .map(|(log_ix, timestamp)| NewLogMemo {
+                timestamp,
+                ..memo(hash, log_ix)
+            })
+            .collect();
+        save_many(&db, memos).await.unwrap();
+
+        assert_eq!(prune(&db, "solana", 250, 10).await.unwrap(), 2);
+        assert_eq!(prune(&db, "solana", 250, 10).await.unwrap(), 0);
+
+        let left = log_memo::Entity::find().all(&db).await.unwrap();
+        let mut left = left.iter().map(|memo| memo.log_ix).collect::<Vec<_>>();
+        left.sort();
+        assert_eq!(left, [0, 3]);
+    }
+
+    #[tokio::test]
+    async fn save_many_returns_only_the_keys_it_inserted() {
+        let db = testing::db().await;
+        let hash = UnionTxHash::from(Signature::from([7; 64]));
+
+        let first = save_many(&db, vec![memo(hash, 0), memo(hash, 1)])
+            .await
+            .unwrap();
+        assert_eq!(first.len(), 2);
+
+        let second = save_many(&db, vec![memo(hash, 1), memo(hash, 2), memo(hash, 2)])
+            .await
+            .unwrap();
+        assert_eq!(second, HashSet::from([(hash.to_string(), 2)]));
+    }
+}
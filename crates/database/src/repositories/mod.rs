@@ -1,4 +1,10 @@
+pub mod failed_events;
+pub mod idempotency_keys;
 pub mod log_memos;
+pub mod outbox;
+pub mod setting_audits;
 pub mod settings;
 pub mod signing_messages;
 pub mod users;
+pub mod webhook_deliveries;
+pub mod webhooks;
@@ -0,0 +1,251 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveValue::Set,
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait, FromQueryResult,
+    QueryFilter, QueryOrder, QuerySelect, QueryTrait, Statement, TransactionTrait,
+    sea_query::{Expr, ExprTrait, OnConflict},
+};
+use shared::result::{AppErr, Rs};
+
+use crate::{entities::outbox, notify::IndexedEvent};
+
+pub use crate::entities::outbox::Model as OutboxMessage;
+
+/// Topic of the [`IndexedEvent`]s the indexers persist
+pub const INDEXED_EVENT_TOPIC: &str = "indexed_event";
+
+/// Rows per insert statement, well below Postgres' bind parameter limit
+const CHUNK_SIZE: usize = 1_000;
+
+/// A message to publish once the transaction writing it commits
+pub struct NewOutboxMessage {
+    pub topic: String,
+    /// Identifies the message within its topic; a key is only ever enqueued once
+    pub key: String,
+    pub payload: String,
+}
+
+impl NewOutboxMessage {
+    pub fn indexed_event(event: &IndexedEvent) -> Rs<Self> {
+        Ok(Self {
+            topic: INDEXED_EVENT_TOPIC.to_owned(),
+            key: format!("{}:{}:{}", event.chain, event.tx_hash, event.log_ix),
+            payload: serde_json::to_string(event)
+                .map_err(|error| AppErr::custom(error.to_string()))?,
+        })
+    }
+}
+
+/// Enqueues `messages`, skipping keys already enqueued, in chunks of [`CHUNK_SIZE`]
+///
+/// Run it in the transaction persisting what the messages describe, so either both
+/// commit or neither does. Returns how many were enqueued.
+#[tracing::instrument(name = "db.outbox.enqueue", skip_all, fields(messages = messages.len()))]
+pub async fn enqueue<C: ConnectionTrait>(db: &C, messages: Vec<NewOutboxMessage>) -> Rs<u64> {
+    let mut inserted = 0;
+
+    for chunk in messages.chunks(CHUNK_SIZE) {
+        let records = chunk.iter().map(|message| outbox::ActiveModel {
+            topic: Set(message.topic.clone()),
+            message_key: Set(message.key.clone()),
+            payload: Set(message.payload.clone()),
+            ..Default::default()
+        });
+
+        inserted += outbox::Entity::insert_many(records)
+            .on_conflict(
+                OnConflict::columns([outbox::Column::Topic, outbox::Column::MessageKey])
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await?;
+    }
+
+    Ok(inserted)
+}
+
+/// Claims up to `limit` undelivered messages that are due, oldest first
+///
+/// Claimed messages aren't due again until `lease` elapses, so concurrent relays
+/// don't publish them twice and a crashed relay's claims are picked up later
+#[tracing::instrument(name = "db.outbox.claim_due", skip_all)]
+pub async fn claim_due(
+    db: &DatabaseConnection,
+    limit: u64,
+    lease: Duration,
+) -> Rs<Vec<OutboxMessage>> {
+    if db.get_database_backend() != DbBackend::Postgres {
+        return claim_due_portable(db, limit, lease).await;
+    }
+
+    let mut messages = OutboxMessage::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"UPDATE outbox
+        SET next_attempt_at = now() + make_interval(secs => $1)
+        WHERE id IN (
+            SELECT id FROM outbox
+            WHERE delivered_at IS NULL AND next_attempt_at <= now()
+            ORDER BY id
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING *"#,
+        [lease.as_secs_f64().into(), limit.into()],
+    ))
+    .all(db)
+    .await?;
+
+    // RETURNING keeps no order, and relays publish in enqueue order
+    messages.sort_by_key(|message| message.id);
+
+    Ok(messages)
+}
+
+/// [`claim_due`] without `SKIP LOCKED`, for backends like SQLite that serialize writers
+async fn claim_due_portable(
+    db: &DatabaseConnection,
+    limit: u64,
+    lease: Duration,
+) -> Rs<Vec<OutboxMessage>> {
+    let now = Utc::now();
+    let lease =
+        chrono::Duration::from_std(lease).map_err(|error| AppErr::custom(error.to_string()))?;
+
+    let txn = db.begin().await?;
+
+    let ids = outbox::Entity::find()
+        .select_only()
+        .column(outbox::Column::Id)
+        .filter(outbox::Column::DeliveredAt.is_null())
+        .filter(outbox::Column::NextAttemptAt.lte(now))
+        .order_by_asc(outbox::Column::Id)
+        .limit(limit)
+        .into_tuple::<i64>()
+        .all(&txn)
+        .await?;
+
+    outbox::Entity::update_many()
+        .col_expr(outbox::Column::NextAttemptAt, Expr::value(now + lease))
+        .filter(outbox::Column::Id.is_in(ids.clone()))
+        .exec(&txn)
+        .await?;
+
+    let messages = outbox::Entity::find()
+        .filter(outbox::Column::Id.is_in(ids))
+        .order_by_asc(outbox::Column::Id)
+        .all(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    Ok(messages)
+}
+
+/// Marks message `id` as published
+pub async fn mark_delivered(db: &DatabaseConnection, id: i64) -> Rs<()> {
+    outbox::Entity::update_many()
+        .col_expr(
+            outbox::Column::Attempts,
+            Expr::col(outbox::Column::Attempts).add(1),
+        )
+        // Bound rather than `CURRENT_TIMESTAMP`, which SQLite stores in another format
+        // than the bound times `prune_delivered` compares it with
+        .col_expr(outbox::Column::DeliveredAt, Expr::value(Utc::now()))
+        .col_expr(
+            outbox::Column::LastError,
+            Expr::value(Option::<String>::None),
+        )
+        .filter(outbox::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Deletes up to `limit` messages delivered before `before`
+///
+/// A deleted message's key can be enqueued again, so the horizon must outlast the
+/// deduplication of what the messages describe. Returns how many were deleted, fewer
+/// than `limit` once nothing older is left.
+#[tracing::instrument(name = "db.outbox.prune_delivered", skip(db))]
+pub async fn prune_delivered(
+    db: &DatabaseConnection,
+    before: DateTime<Utc>,
+    limit: u64,
+) -> Rs<u64> {
+    let batch = outbox::Entity::find()
+        .select_only()
+        .column(outbox::Column::Id)
+        .filter(outbox::Column::DeliveredAt.lt(before))
+        .limit(limit)
+        .into_query();
+
+    let deleted = outbox::Entity::delete_many()
+        .filter(outbox::Column::Id.in_subquery(batch))
+        .exec(db)
+        .await?;
+
+    Ok(deleted.rows_affected)
+}
+
+/// Records a failed attempt to publish message `id`, which is retried at `retry_at`
+pub async fn record_failure(
+    db: &DatabaseConnection,
+    id: i64,
+    error: String,
+    retry_at: DateTime<Utc>,
+) -> Rs<()> {
+    outbox::Entity::update_many()
+        .col_expr(
+            outbox::Column::Attempts,
+            Expr::col(outbox::Column::Attempts).add(1),
+        )
+        .col_expr(outbox::Column::LastError, Expr::value(error))
+        .col_expr(outbox::Column::NextAttemptAt, Expr::value(retry_at))
+        .filter(outbox::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn message(key: &str) -> NewOutboxMessage {
+        NewOutboxMessage {
+            topic: "test".to_owned(),
+            key: key.to_owned(),
+            payload: "{}".to_owned(),
+        }
+    }
+
+    #[tokio::test]
+    async fn only_delivered_messages_past_the_horizon_are_pruned() {
+        let db = testing::db().await;
+
+        let keys = ["a", "b", "c"];
+        enqueue(&db, keys.map(message).into()).await.unwrap();
+
+        let claimed = claim_due(&db, 10, Duration::from_secs(60)).await.unwrap();
+        mark_delivered(&db, claimed[0].id).await.unwrap();
+        mark_delivered(&db, claimed[1].id).await.unwrap();
+
+        let past = Utc::now() - chrono::Duration::hours(1);
+        assert_eq!(prune_delivered(&db, past, 10).await.unwrap(), 0);
+
+        let future = Utc::now() + chrono::Duration::hours(1);
+        assert_eq!(prune_delivered(&db, future, 1).await.unwrap(), 1);
+        assert_eq!(prune_delivered(&db, future, 10).await.unwrap(), 1);
+
+        let left = outbox::Entity::find().all(&db).await.unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].message_key, "c");
+        assert!(left[0].delivered_at.is_none());
+    }
+}
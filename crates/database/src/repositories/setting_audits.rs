@@ -0,0 +1,70 @@
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+};
+use serde::Deserialize;
+use shared::{UnionAddress, result::Rs};
+
+use crate::{
+    entities::setting_audit,
+    pagination::{self, Page, PageRequest},
+};
+
+/// Fields the change log can be sorted by
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingChangeSort {
+    #[default]
+    ChangedAt,
+    Key,
+}
+
+impl SettingChangeSort {
+    fn column(self) -> setting_audit::Column {
+        match self {
+            Self::ChangedAt => setting_audit::Column::ChangedAt,
+            Self::Key => setting_audit::Column::Key,
+        }
+    }
+}
+
+/// Records that `changed_by` moved `key` from `old_value` to `new_value`
+pub async fn record<C, A>(
+    db: &C,
+    key: &str,
+    old_value: Option<String>,
+    new_value: String,
+    changed_by: A,
+) -> Rs<()>
+where
+    C: ConnectionTrait,
+    A: Into<UnionAddress>,
+{
+    setting_audit::Entity::insert(setting_audit::ActiveModel {
+        key: Set(key.to_owned()),
+        old_value: Set(old_value),
+        new_value: Set(new_value),
+        changed_by: Set(changed_by.into().to_string()),
+        ..Default::default()
+    })
+    .exec_without_returning(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Lists recorded changes by `sort`, optionally only those of `key`
+#[tracing::instrument(name = "db.setting_audits.list", skip_all)]
+pub async fn list(
+    db: &DatabaseConnection,
+    key: Option<&str>,
+    sort: SettingChangeSort,
+    page: &PageRequest,
+) -> Rs<Page<setting_audit::Model>> {
+    let mut select = setting_audit::Entity::find();
+
+    if let Some(key) = key {
+        select = select.filter(setting_audit::Column::Key.eq(key));
+    }
+
+    pagination::paginate(db, select, sort.column(), setting_audit::Column::Id, page).await
+}
@@ -1,11 +1,21 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, fmt::Display, ops::Deref, str::FromStr};
 
 use sea_orm::{
-    ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, sea_query::Expr,
+    ActiveValue::Set,
+    ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect,
+    TransactionTrait,
+    sea_query::{Expr, OnConflict},
 };
-use shared::result::Rs;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::DeserializeOwned};
+use shared::{
+    UnionAddress,
+    result::{AppErr, Rs},
+};
+use solana_sdk::signature::Signature;
+
+use crate::{entities::setting, repositories::setting_audits};
 
-use crate::entities::setting;
+pub use crate::entities::setting::Model as StoredSetting;
 
 #[derive(Clone, Copy, Debug)]
 pub enum Setting {
@@ -13,41 +23,370 @@ pub enum Setting {
     SolCurrentScannedSignature,
 }
 
-pub async fn get(db: &DatabaseConnection, key: Setting) -> Rs<Option<String>> {
-    let val = setting::Entity::find_by_id(key.to_str_key())
+/// A setting key together with the type its value is stored as
+pub trait TypedSetting: Copy {
+    type Value: Serialize + DeserializeOwned;
+
+    fn setting(self) -> Setting;
+}
+
+/// Next block the EVM scanner of the chain will scan
+#[derive(Clone, Copy, Debug)]
+pub struct EvmScannedBlock(pub u64);
+
+impl TypedSetting for EvmScannedBlock {
+    type Value = u64;
+
+    fn setting(self) -> Setting {
+        Setting::EvmScannedBlock(self.0)
+    }
+}
+
+/// Newest signature the Solana scanner has processed
+#[derive(Clone, Copy, Debug)]
+pub struct SolCurrentScannedSignature;
+
+impl TypedSetting for SolCurrentScannedSignature {
+    type Value = Text<Signature>;
+
+    fn setting(self) -> Setting {
+        Setting::SolCurrentScannedSignature
+    }
+}
+
+/// Serializes `T` through its `Display` and `FromStr` impls, for values whose own serde
+/// form isn't the string users know them by
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Text<T>(pub T);
+
+pub async fn get<K: TypedSetting>(db: &DatabaseConnection, key: K) -> Rs<Option<K::Value>> {
+    let str_key = key.setting().to_str_key();
+
+    get_encoded(db, key.setting())
+        .await?
+        .map(|value| decode(&str_key, &value))
+        .transpose()
+}
+
+/// Value of `key` as stored, without decoding it
+pub async fn get_encoded(db: &DatabaseConnection, key: Setting) -> Rs<Option<String>> {
+    let value = setting::Entity::find_by_id(key.to_str_key().as_ref())
         .one(db)
         .await?
         .map(|record| record.value);
 
-    Ok(val)
+    Ok(value)
+}
+
+/// Every stored row, including keys this build doesn't recognize
+pub async fn list(db: &DatabaseConnection) -> Rs<Vec<StoredSetting>> {
+    Ok(setting::Entity::find().all(db).await?)
+}
+
+/// Sets `key` to `value`, creating the row if missing
+pub async fn set<K: TypedSetting>(db: &DatabaseConnection, key: K, value: &K::Value) -> Rs<()> {
+    let str_key = key.setting().to_str_key();
+
+    upsert(db, &str_key, encode(&str_key, value)?).await
 }
 
-pub async fn set(db: &DatabaseConnection, key: Setting, value: String) -> Rs<()> {
-    setting::Entity::update_many()
-        .col_expr(setting::Column::Value, Expr::value(value))
-        .filter(setting::Column::Key.eq(key.to_str_key()))
+/// Stores `value` unless `key` is already set, and returns whichever value is stored
+///
+/// Safe to race, e.g. when two scanners start on an empty table.
+pub async fn get_or_insert<K: TypedSetting>(
+    db: &DatabaseConnection,
+    key: K,
+    value: K::Value,
+) -> Rs<K::Value> {
+    let str_key = key.setting().to_str_key();
+
+    setting::Entity::insert(setting::ActiveModel {
+        key: Set(str_key.to_string()),
+        value: Set(encode(&str_key, &value)?),
+        ..Default::default()
+    })
+    .on_conflict_do_nothing()
+    .exec_without_returning(db)
+    .await?;
+
+    get(db, key)
+        .await?
+        .ok_or_else(|| AppErr::custom(format!("setting {} vanished after insert", str_key)))
+}
+
+/// Sets `key` to `value` only if it still holds `expected`
+///
+/// Returns `false` when the value was changed concurrently, e.g. by another scanner or
+/// through the admin API, so a stale writer can't move a cursor backwards. Run it in the
+/// transaction holding the writes the new value covers, so they commit together.
+pub async fn compare_and_swap<C, K>(
+    db: &C,
+    key: K,
+    expected: &K::Value,
+    value: &K::Value,
+) -> Rs<bool>
+where
+    C: ConnectionTrait,
+    K: TypedSetting,
+{
+    let str_key = key.setting().to_str_key();
+
+    let result = setting::Entity::update_many()
+        .col_expr(
+            setting::Column::Value,
+            Expr::value(encode(&str_key, value)?),
+        )
+        .col_expr(setting::Column::UpdatedAt, Expr::current_timestamp())
+        .filter(setting::Column::Key.eq(str_key.as_ref()))
+        .filter(setting::Column::Value.eq(encode(&str_key, expected)?))
         .exec(db)
         .await?;
 
-    Ok(())
+    Ok(result.rows_affected == 1)
+}
+
+/// Sets `key` to `value` on behalf of `changed_by`, creating the row if missing, and
+/// records the change in the audit trail
+///
+/// Returns the previous value as stored, which isn't decoded so a malformed value can
+/// still be overwritten
+#[tracing::instrument(name = "db.settings.change", skip_all)]
+pub async fn change<K, A>(
+    db: &DatabaseConnection,
+    key: K,
+    value: &K::Value,
+    changed_by: A,
+) -> Rs<Option<String>>
+where
+    K: TypedSetting,
+    A: Into<UnionAddress>,
+{
+    let value = encode(&key.setting().to_str_key(), value)?;
+
+    change_encoded(db, key.setting(), value, changed_by.into()).await
 }
 
-pub async fn insert(db: &DatabaseConnection, key: Setting, value: String) -> Rs<()> {
+/// [`change`] with a value already encoded as stored
+pub async fn change_encoded(
+    db: &DatabaseConnection,
+    key: Setting,
+    value: String,
+    changed_by: UnionAddress,
+) -> Rs<Option<String>> {
+    let str_key = key.to_str_key();
+
+    let txn = db.begin().await?;
+
+    let previous = setting::Entity::find_by_id(str_key.as_ref())
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .map(|record| record.value);
+
+    upsert(&txn, &str_key, value.clone()).await?;
+
+    setting_audits::record(&txn, &str_key, previous.clone(), value, changed_by).await?;
+
+    txn.commit().await?;
+
+    Ok(previous)
+}
+
+async fn upsert<C: ConnectionTrait>(db: &C, key: &str, value: String) -> Rs<()> {
     setting::Entity::insert(setting::ActiveModel {
-        key: Set(key.to_str_key().to_string()),
+        key: Set(key.to_owned()),
         value: Set(value),
+        ..Default::default()
     })
-    .exec(db)
+    .on_conflict(
+        OnConflict::column(setting::Column::Key)
+            .update_column(setting::Column::Value)
+            .value(setting::Column::UpdatedAt, Expr::current_timestamp())
+            .to_owned(),
+    )
+    .exec_without_returning(db)
     .await?;
 
     Ok(())
 }
 
+/// Strings are stored bare rather than as JSON strings, so rows stay readable and
+/// match what was written before values were typed
+pub(crate) fn encode<V: Serialize>(key: &str, value: &V) -> Rs<String> {
+    let encoded = serde_json::to_value(value).map_err(|error| {
+        AppErr::custom(format!("setting {} could not be encoded: {}", key, error))
+    })?;
+
+    Ok(match encoded {
+        serde_json::Value::String(text) => text,
+        other => other.to_string(),
+    })
+}
+
+pub(crate) fn decode<V: DeserializeOwned>(key: &str, raw: &str) -> Rs<V> {
+    serde_json::from_str(raw)
+        .or_else(|_| V::deserialize(serde_json::Value::String(raw.to_owned())))
+        .map_err(|error| AppErr::custom(format!("setting {} holds {:?}: {}", key, raw, error)))
+}
+
 impl Setting {
-    fn to_str_key(self) -> Cow<'static, str> {
+    /// Row key the setting is stored under
+    pub fn to_str_key(self) -> Cow<'static, str> {
         match self {
             Self::EvmScannedBlock(chain) => format!("evm_scanned_block_chain_{}", chain).into(),
             Self::SolCurrentScannedSignature => "solana_current_scanned_signature".into(),
         }
     }
+
+    pub(crate) fn from_str_key(key: &str) -> Option<Self> {
+        if key == "solana_current_scanned_signature" {
+            return Some(Self::SolCurrentScannedSignature);
+        }
+
+        key.strip_prefix("evm_scanned_block_chain_")
+            .and_then(|chain| chain.parse().ok())
+            .map(Self::EvmScannedBlock)
+    }
+}
+
+impl<T> Deref for Text<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Display> Serialize for Text<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.0)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Text<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = Cow::<str>::deserialize(deserializer)?;
+
+        text.parse().map(Text).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    const CHAIN: EvmScannedBlock = EvmScannedBlock(56);
+
+    /// Stores `value` as is, the way rows were written before values were typed
+    async fn store_raw(db: &DatabaseConnection, key: Setting, value: &str) {
+        upsert(db, &key.to_str_key(), value.to_owned())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn compare_and_swap_only_replaces_the_expected_value() {
+        let db = testing::db().await;
+
+        // A missing row has nothing to compare against
+        assert!(!compare_and_swap(&db, CHAIN, &10, &20).await.unwrap());
+        assert_eq!(get(&db, CHAIN).await.unwrap(), None);
+
+        set(&db, CHAIN, &10).await.unwrap();
+
+        assert!(compare_and_swap(&db, CHAIN, &10, &20).await.unwrap());
+        assert_eq!(get(&db, CHAIN).await.unwrap(), Some(20));
+
+        // A stale writer can't move the cursor back
+        assert!(!compare_and_swap(&db, CHAIN, &10, &15).await.unwrap());
+        assert_eq!(get(&db, CHAIN).await.unwrap(), Some(20));
+    }
+
+    #[tokio::test]
+    async fn compare_and_swap_rolls_back_with_its_transaction() {
+        let db = testing::db().await;
+        set(&db, CHAIN, &10).await.unwrap();
+
+        let txn = db.begin().await.unwrap();
+        assert!(compare_and_swap(&txn, CHAIN, &10, &20).await.unwrap());
+        txn.rollback().await.unwrap();
+
+        assert_eq!(get(&db, CHAIN).await.unwrap(), Some(10));
+    }
+
+    #[tokio::test]
+    async fn racing_get_or_insert_agree_on_one_value() {
+        let db = testing::db().await;
+
+        let (first, second, third) = tokio::join!(
+            get_or_insert(&db, CHAIN, 1),
+            get_or_insert(&db, CHAIN, 2),
+            get_or_insert(&db, CHAIN, 3),
+        );
+        let first = first.unwrap();
+
+        assert_eq!(second.unwrap(), first);
+        assert_eq!(third.unwrap(), first);
+        assert_eq!(get(&db, CHAIN).await.unwrap(), Some(first));
+
+        // Once set, the stored value wins over the one offered
+        assert_eq!(get_or_insert(&db, CHAIN, 99).await.unwrap(), first);
+    }
+
+    #[tokio::test]
+    async fn decodes_values_stored_before_they_were_typed() {
+        let db = testing::db().await;
+        let signature = Signature::from([7; 64]);
+
+        store_raw(&db, CHAIN.setting(), "12345").await;
+        store_raw(
+            &db,
+            Setting::SolCurrentScannedSignature,
+            &signature.to_string(),
+        )
+        .await;
+
+        assert_eq!(get(&db, CHAIN).await.unwrap(), Some(12345));
+        assert_eq!(
+            get(&db, SolCurrentScannedSignature).await.unwrap(),
+            Some(Text(signature))
+        );
+    }
+
+    #[tokio::test]
+    async fn typed_values_are_stored_bare() {
+        let db = testing::db().await;
+        let signature = Signature::from([7; 64]);
+
+        set(&db, CHAIN, &12345).await.unwrap();
+        set(&db, SolCurrentScannedSignature, &Text(signature))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            get_encoded(&db, CHAIN.setting()).await.unwrap().as_deref(),
+            Some("12345")
+        );
+        assert_eq!(
+            get_encoded(&db, Setting::SolCurrentScannedSignature)
+                .await
+                .unwrap(),
+            Some(signature.to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn malformed_values_fail_to_decode_with_the_key() {
+        let db = testing::db().await;
+        store_raw(&db, CHAIN.setting(), "not a block").await;
+
+        let error = get(&db, CHAIN).await.unwrap_err();
+
+        assert!(format!("{:?}", error).contains("evm_scanned_block_chain_56"));
+    }
 }
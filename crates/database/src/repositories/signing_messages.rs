@@ -13,11 +13,7 @@ pub async fn allocate<A>(db: &DatabaseConnection, address: A, message: String) -
 where
     A: Into<UnionAddress>,
 {
-    if message.len() > 98 {
-        return Err(AppErr::custom(
-            "signing message's lenght can not greater than 98",
-        ));
-    }
+    check_message(&message)?;
 
     signing_message::Entity::insert(signing_message::ActiveModel {
         address: Set(address.into().to_string()),
@@ -58,3 +54,14 @@ where
 
     Ok(message)
 }
+
+/// Rejects messages longer than the column holds
+pub(crate) fn check_message(message: &str) -> Rs<()> {
+    if message.len() > 98 {
+        return Err(AppErr::custom(
+            "signing message's lenght can not greater than 98",
+        ));
+    }
+
+    Ok(())
+}
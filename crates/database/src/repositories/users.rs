@@ -3,6 +3,8 @@ use shared::{UnionAddress, result::Rs};
 
 use crate::entities::user;
 
+pub use crate::entities::user::Model as User;
+
 pub async fn find_by_wallet_address<A: Into<UnionAddress>>(
     db: &DatabaseConnection,
     address: A,
@@ -15,6 +17,23 @@ pub async fn find_by_wallet_address<A: Into<UnionAddress>>(
     Ok(user)
 }
 
+pub async fn find_by_wallet_addresses<I>(
+    db: &DatabaseConnection,
+    addresses: I,
+) -> Rs<Vec<user::Model>>
+where
+    I: IntoIterator<Item = UnionAddress>,
+{
+    let addresses = addresses.into_iter().map(|address| address.to_string());
+
+    let users = user::Entity::find()
+        .filter(user::Column::WalletAddress.is_in(addresses))
+        .all(db)
+        .await?;
+
+    Ok(users)
+}
+
 pub async fn save<A: Into<UnionAddress>>(db: &DatabaseConnection, address: A) -> Rs<()> {
     let user = user::ActiveModel {
         wallet_address: Set(address.into().to_string()),
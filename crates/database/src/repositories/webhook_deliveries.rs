@@ -0,0 +1,322 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveValue::Set,
+    ColumnTrait, DatabaseConnection, DbBackend, EntityTrait, FromQueryResult, QueryFilter,
+    QueryOrder, QuerySelect, Statement, TransactionTrait,
+    sea_query::{Expr, ExprTrait, OnConflict},
+};
+use serde::Deserialize;
+use shared::result::{AppErr, Rs};
+
+use crate::{
+    entities::{webhook, webhook_delivery},
+    pagination::{self, Page, PageRequest},
+};
+
+pub use crate::entities::webhook_delivery::Model as WebhookDelivery;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    /// Retries were exhausted
+    Failed,
+}
+
+/// Fields the delivery log can be sorted by
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliverySort {
+    #[default]
+    CreatedAt,
+    NextAttemptAt,
+    Attempts,
+}
+
+impl DeliverySort {
+    fn column(self) -> webhook_delivery::Column {
+        match self {
+            Self::CreatedAt => webhook_delivery::Column::CreatedAt,
+            Self::NextAttemptAt => webhook_delivery::Column::NextAttemptAt,
+            Self::Attempts => webhook_delivery::Column::Attempts,
+        }
+    }
+}
+
+impl DeliveryStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Delivered => "delivered",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// A delivery to enqueue for one webhook
+pub struct NewDelivery {
+    pub webhook_id: i32,
+    /// Identifies the event; a webhook gets at most one delivery per key
+    pub event_key: String,
+    pub payload: String,
+}
+
+/// Result of one delivery attempt
+pub enum Attempt {
+    Delivered {
+        status_code: u16,
+    },
+    Failed {
+        status_code: Option<u16>,
+        error: String,
+        /// When to try again, `None` to give up
+        retry_at: Option<DateTime<Utc>>,
+    },
+}
+
+/// Enqueues `deliveries`, skipping events a webhook already has a delivery for
+///
+/// Returns how many were enqueued
+pub async fn enqueue<I>(db: &DatabaseConnection, deliveries: I) -> Rs<u64>
+where
+    I: IntoIterator<Item = NewDelivery>,
+{
+    let deliveries = deliveries
+        .into_iter()
+        .map(|delivery| webhook_delivery::ActiveModel {
+            webhook_id: Set(delivery.webhook_id),
+            event_key: Set(delivery.event_key),
+            payload: Set(delivery.payload),
+            status: Set(DeliveryStatus::Pending.as_str().to_owned()),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+
+    if deliveries.is_empty() {
+        return Ok(0);
+    }
+
+    let inserted = webhook_delivery::Entity::insert_many(deliveries)
+        .on_conflict(
+            OnConflict::columns([
+                webhook_delivery::Column::WebhookId,
+                webhook_delivery::Column::EventKey,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+
+    Ok(inserted)
+}
+
+/// Claims up to `limit` due deliveries of enabled webhooks
+///
+/// Claimed deliveries aren't due again until `lease` elapses, so concurrent workers
+/// don't send them twice and a crashed worker's claims are picked up later
+#[tracing::instrument(name = "db.webhook_deliveries.claim_due", skip_all)]
+pub async fn claim_due(
+    db: &DatabaseConnection,
+    limit: u64,
+    lease: Duration,
+) -> Rs<Vec<webhook_delivery::Model>> {
+    if db.get_database_backend() != DbBackend::Postgres {
+        return claim_due_portable(db, limit, lease).await;
+    }
+
+    let deliveries = webhook_delivery::Model::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"UPDATE webhook_delivery
+        SET next_attempt_at = now() + make_interval(secs => $1)
+        WHERE id IN (
+            SELECT d.id FROM webhook_delivery d
+            JOIN webhook w ON w.id = d.webhook_id
+            WHERE d.status = $2 AND d.next_attempt_at <= now() AND w.enabled
+            ORDER BY d.next_attempt_at
+            LIMIT $3
+            FOR UPDATE OF d SKIP LOCKED
+        )
+        RETURNING *"#,
+        [
+            lease.as_secs_f64().into(),
+            DeliveryStatus::Pending.as_str().into(),
+            limit.into(),
+        ],
+    ))
+    .all(db)
+    .await?;
+
+    Ok(deliveries)
+}
+
+/// [`claim_due`] without `SKIP LOCKED`, for backends like SQLite that serialize writers
+async fn claim_due_portable(
+    db: &DatabaseConnection,
+    limit: u64,
+    lease: Duration,
+) -> Rs<Vec<webhook_delivery::Model>> {
+    let now = Utc::now();
+    let lease =
+        chrono::Duration::from_std(lease).map_err(|error| AppErr::custom(error.to_string()))?;
+
+    let txn = db.begin().await?;
+
+    let ids = webhook_delivery::Entity::find()
+        .select_only()
+        .column(webhook_delivery::Column::Id)
+        .inner_join(webhook::Entity)
+        .filter(webhook_delivery::Column::Status.eq(DeliveryStatus::Pending.as_str()))
+        .filter(webhook_delivery::Column::NextAttemptAt.lte(now))
+        .filter(webhook::Column::Enabled.eq(true))
+        .order_by_asc(webhook_delivery::Column::NextAttemptAt)
+        .limit(limit)
+        .into_tuple::<i32>()
+        .all(&txn)
+        .await?;
+
+    webhook_delivery::Entity::update_many()
+        .col_expr(
+            webhook_delivery::Column::NextAttemptAt,
+            Expr::value(now + lease),
+        )
+        .filter(webhook_delivery::Column::Id.is_in(ids.clone()))
+        .exec(&txn)
+        .await?;
+
+    let deliveries = webhook_delivery::Entity::find()
+        .filter(webhook_delivery::Column::Id.is_in(ids))
+        .all(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    Ok(deliveries)
+}
+
+/// Records the outcome of an attempt on delivery `id`
+pub async fn record(db: &DatabaseConnection, id: i32, attempt: Attempt) -> Rs<()> {
+    let attempts = Expr::col(webhook_delivery::Column::Attempts).add(1);
+
+    let mut update = webhook_delivery::Entity::update_many()
+        .col_expr(webhook_delivery::Column::Attempts, attempts)
+        .filter(webhook_delivery::Column::Id.eq(id));
+
+    update = match attempt {
+        Attempt::Delivered { status_code } => update
+            .col_expr(
+                webhook_delivery::Column::Status,
+                Expr::value(DeliveryStatus::Delivered.as_str()),
+            )
+            .col_expr(
+                webhook_delivery::Column::LastStatusCode,
+                Expr::value(status_code as i16),
+            )
+            .col_expr(
+                webhook_delivery::Column::LastError,
+                Expr::value(Option::<String>::None),
+            )
+            .col_expr(
+                webhook_delivery::Column::DeliveredAt,
+                Expr::current_timestamp(),
+            ),
+        Attempt::Failed {
+            status_code,
+            error,
+            retry_at,
+        } => {
+            let update = update
+                .col_expr(
+                    webhook_delivery::Column::LastStatusCode,
+                    Expr::value(status_code.map(|code| code as i16)),
+                )
+                .col_expr(webhook_delivery::Column::LastError, Expr::value(error));
+
+            match retry_at {
+                Some(retry_at) => update.col_expr(
+                    webhook_delivery::Column::NextAttemptAt,
+                    Expr::value(retry_at),
+                ),
+                None => update.col_expr(
+                    webhook_delivery::Column::Status,
+                    Expr::value(DeliveryStatus::Failed.as_str()),
+                ),
+            }
+        }
+    };
+
+    update.exec(db).await?;
+
+    Ok(())
+}
+
+/// Lists the deliveries of `webhook_id` by `sort`, optionally only those in `status`
+#[tracing::instrument(name = "db.webhook_deliveries.list", skip_all)]
+pub async fn list_by_webhook(
+    db: &DatabaseConnection,
+    webhook_id: i32,
+    status: Option<DeliveryStatus>,
+    sort: DeliverySort,
+    page: &PageRequest,
+) -> Rs<Page<webhook_delivery::Model>> {
+    let mut select =
+        webhook_delivery::Entity::find().filter(webhook_delivery::Column::WebhookId.eq(webhook_id));
+
+    if let Some(status) = status {
+        select = select.filter(webhook_delivery::Column::Status.eq(status.as_str()));
+    }
+
+    pagination::paginate(
+        db,
+        select,
+        sort.column(),
+        webhook_delivery::Column::Id,
+        page,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::PaginatorTrait;
+
+    use super::*;
+    use crate::{repositories::webhooks, testing};
+
+    fn delivery(webhook_id: i32, event_key: &str) -> NewDelivery {
+        NewDelivery {
+            webhook_id,
+            event_key: event_key.to_owned(),
+            payload: "{}".to_owned(),
+        }
+    }
+
+    #[tokio::test]
+    async fn enqueue_skips_events_a_webhook_already_has() {
+        let db = testing::db().await;
+        let first = webhooks::tests::webhook(&db).await.id;
+        let second = webhooks::tests::webhook(&db).await.id;
+
+        let enqueued = enqueue(
+            &db,
+            [delivery(first, "1:0xa:0"), delivery(second, "1:0xa:0")],
+        )
+        .await
+        .unwrap();
+        assert_eq!(enqueued, 2);
+
+        let enqueued = enqueue(
+            &db,
+            [delivery(first, "1:0xa:0"), delivery(first, "1:0xb:0")],
+        )
+        .await
+        .unwrap();
+        assert_eq!(enqueued, 1);
+
+        let count = webhook_delivery::Entity::find().count(&db).await.unwrap();
+        assert_eq!(count, 3);
+    }
+}
@@ -0,0 +1,251 @@
+use sea_orm::{
+    ActiveModelTrait,
+    ActiveValue::Set,
+    ColumnTrait, Condition, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder,
+    sea_query::{Expr, ExprTrait},
+};
+use shared::{UnionAddress, result::Rs};
+
+use crate::entities::webhook;
+
+pub use crate::entities::webhook::Model as Webhook;
+
+/// Fields of a webhook its owner may set
+pub struct WebhookInput {
+    pub url: String,
+    pub chain: Option<String>,
+    pub protocol: Option<String>,
+}
+
+pub async fn create<A: Into<UnionAddress>>(
+    db: &DatabaseConnection,
+    owner: A,
+    secret: String,
+    input: WebhookInput,
+) -> Rs<webhook::Model> {
+    let webhook = webhook::ActiveModel {
+        owner: Set(owner.into().to_string()),
+        url: Set(input.url),
+        secret: Set(secret),
+        chain: Set(input.chain),
+        protocol: Set(input.protocol),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(webhook)
+}
+
+pub async fn count_by_owner<A: Into<UnionAddress>>(db: &DatabaseConnection, owner: A) -> Rs<u64> {
+    let count = webhook::Entity::find()
+        .filter(webhook::Column::Owner.eq(owner.into().to_string()))
+        .count(db)
+        .await?;
+
+    Ok(count)
+}
+
+pub async fn list_by_owner<A: Into<UnionAddress>>(
+    db: &DatabaseConnection,
+    owner: A,
+) -> Rs<Vec<webhook::Model>> {
+    let webhooks = webhook::Entity::find()
+        .filter(webhook::Column::Owner.eq(owner.into().to_string()))
+        .order_by_asc(webhook::Column::Id)
+        .all(db)
+        .await?;
+
+    Ok(webhooks)
+}
+
+/// Finds webhook `id` if it belongs to `owner`
+pub async fn find_by_owner<A: Into<UnionAddress>>(
+    db: &DatabaseConnection,
+    id: i32,
+    owner: A,
+) -> Rs<Option<webhook::Model>> {
+    let webhook = webhook::Entity::find_by_id(id)
+        .filter(webhook::Column::Owner.eq(owner.into().to_string()))
+        .one(db)
+        .await?;
+
+    Ok(webhook)
+}
+
+pub async fn find_by_ids<I>(db: &DatabaseConnection, ids: I) -> Rs<Vec<webhook::Model>>
+where
+    I: IntoIterator<Item = i32>,
+{
+    let webhooks = webhook::Entity::find()
+        .filter(webhook::Column::Id.is_in(ids))
+        .all(db)
+        .await?;
+
+    Ok(webhooks)
+}
+
+/// Replaces the settable fields of webhook `id` if it belongs to `owner`
+///
+/// Re-enabling a webhook clears its failure count
+pub async fn update<A: Into<UnionAddress>>(
+    db: &DatabaseConnection,
+    id: i32,
+    owner: A,
+    input: WebhookInput,
+    enabled: bool,
+) -> Rs<Option<webhook::Model>> {
+    let Some(existing) = find_by_owner(db, id, owner).await? else {
+        return Ok(None);
+    };
+
+    let was_enabled = existing.enabled;
+    let mut webhook: webhook::ActiveModel = existing.into();
+
+    webhook.url = Set(input.url);
+    webhook.chain = Set(input.chain);
+    webhook.protocol = Set(input.protocol);
+    webhook.enabled = Set(enabled);
+
+    if enabled && !was_enabled {
+        webhook.consecutive_failures = Set(0);
+        webhook.disabled_at = Set(None);
+    }
+
+    Ok(Some(webhook.update(db).await?))
+}
+
+/// Deletes webhook `id` and its deliveries if it belongs to `owner`
+pub async fn delete<A: Into<UnionAddress>>(db: &DatabaseConnection, id: i32, owner: A) -> Rs<bool> {
+    let result = webhook::Entity::delete_many()
+        .filter(webhook::Column::Id.eq(id))
+        .filter(webhook::Column::Owner.eq(owner.into().to_string()))
+        .exec(db)
+        .await?;
+
+    Ok(result.rows_affected == 1)
+}
+
+/// Enabled webhooks whose filters accept an event of `chain` and `protocol`
+pub async fn find_matching(
+    db: &DatabaseConnection,
+    chain: &str,
+    protocol: &str,
+) -> Rs<Vec<webhook::Model>> {
+    let webhooks = webhook::Entity::find()
+        .filter(webhook::Column::Enabled.eq(true))
+        .filter(
+            Condition::any()
+                .add(webhook::Column::Chain.is_null())
+                .add(webhook::Column::Chain.eq(chain)),
+        )
+        .filter(
+            Condition::any()
+                .add(webhook::Column::Protocol.is_null())
+                .add(webhook::Column::Protocol.eq(protocol)),
+        )
+        .all(db)
+        .await?;
+
+    Ok(webhooks)
+}
+
+/// Resets the failure count after a successful delivery
+pub async fn record_success(db: &DatabaseConnection, id: i32) -> Rs<()> {
+    webhook::Entity::update_many()
+        .col_expr(webhook::Column::ConsecutiveFailures, Expr::value(0))
+        .filter(webhook::Column::Id.eq(id))
+        .filter(webhook::Column::ConsecutiveFailures.ne(0))
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Counts a failed delivery, disabling the webhook once `disable_after` failures
+/// happened in a row
+///
+/// Returns `true` if this failure disabled the webhook
+pub async fn record_failure(db: &DatabaseConnection, id: i32, disable_after: i32) -> Rs<bool> {
+    let failures = Expr::col(webhook::Column::ConsecutiveFailures).add(1);
+
+    let updated = webhook::Entity::update_many()
+        .col_expr(webhook::Column::ConsecutiveFailures, failures.clone())
+        .col_expr(webhook::Column::Enabled, failures.clone().lt(disable_after))
+        .col_expr(
+            webhook::Column::DisabledAt,
+            Expr::case(failures.gte(disable_after), Expr::current_timestamp())
+                .finally(Expr::col(webhook::Column::DisabledAt))
+                .into(),
+        )
+        .filter(webhook::Column::Id.eq(id))
+        .filter(webhook::Column::Enabled.eq(true))
+        .exec_with_returning(db)
+        .await?;
+
+    Ok(updated.iter().any(|webhook| !webhook.enabled))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use shared::UnionAddress;
+
+    use super::*;
+    use crate::testing;
+
+    const OWNER: &str = "0x00000000000000000000000000000000000000aa";
+
+    pub(crate) async fn webhook(db: &DatabaseConnection) -> webhook::Model {
+        let owner = OWNER.parse::<UnionAddress>().unwrap();
+        let input = WebhookInput {
+            url: "https://example.com/hook".to_owned(),
+            chain: None,
+            protocol: None,
+        };
+
+        create(db, owner, "secret".to_owned(), input).await.unwrap()
+    }
+
+    async fn reload(db: &DatabaseConnection, id: i32) -> webhook::Model {
+        webhook::Entity::find_by_id(id)
+            .one(db)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn repeated_failures_disable_the_webhook() {
+        let db = testing::db().await;
+        let webhook = webhook(&db).await;
+
+        assert!(!record_failure(&db, webhook.id, 3).await.unwrap());
+        assert!(!record_failure(&db, webhook.id, 3).await.unwrap());
+        assert!(reload(&db, webhook.id).await.enabled);
+
+        assert!(record_failure(&db, webhook.id, 3).await.unwrap());
+
+        let disabled = reload(&db, webhook.id).await;
+        assert!(!disabled.enabled);
+        assert!(disabled.disabled_at.is_some());
+        assert_eq!(disabled.consecutive_failures, 3);
+
+        // Already disabled, so later failures neither count nor report disabling it
+        assert!(!record_failure(&db, webhook.id, 3).await.unwrap());
+        assert_eq!(reload(&db, webhook.id).await.consecutive_failures, 3);
+    }
+
+    #[tokio::test]
+    async fn success_resets_the_failure_count() {
+        let db = testing::db().await;
+        let webhook = webhook(&db).await;
+
+        record_failure(&db, webhook.id, 3).await.unwrap();
+        record_failure(&db, webhook.id, 3).await.unwrap();
+        record_success(&db, webhook.id).await.unwrap();
+
+        assert!(!record_failure(&db, webhook.id, 3).await.unwrap());
+        assert_eq!(reload(&db, webhook.id).await.consecutive_failures, 1);
+    }
+}
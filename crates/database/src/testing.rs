@@ -0,0 +1,18 @@
+use sea_orm::{ConnectOptions, Database, DatabaseConnection};
+use sea_orm_migration::MigratorTrait;
+
+use crate::migrations::Migrator;
+
+/// A fresh in-memory SQLite database with every migration applied
+pub async fn db() -> DatabaseConnection {
+    let mut opt = ConnectOptions::new("sqlite::memory:");
+    // Each connection to an in-memory database gets its own
+    opt.sqlx_logging(false)
+        .max_connections(1)
+        .min_connections(1);
+
+    let db = Database::connect(opt).await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+
+    db
+}
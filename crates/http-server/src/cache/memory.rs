@@ -0,0 +1,70 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use moka::{Expiry, future::Cache};
+
+use super::CachedResponse;
+
+#[derive(Clone)]
+struct Entry {
+    response: Arc<CachedResponse>,
+    ttl: Duration,
+}
+
+struct EntryTtl;
+
+impl Expiry<String, Entry> for EntryTtl {
+    fn expire_after_create(&self, _key: &String, entry: &Entry, _at: Instant) -> Option<Duration> {
+        Some(entry.ttl)
+    }
+}
+
+/// In-process store, evicting the least recently used entries past `max_entries`
+#[derive(Clone)]
+pub struct MemoryStore {
+    entries: Cache<String, Entry>,
+    generations: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl MemoryStore {
+    pub fn new(max_entries: u64) -> Self {
+        let entries = Cache::builder()
+            .max_capacity(max_entries)
+            .expire_after(EntryTtl)
+            .build();
+
+        Self {
+            entries,
+            generations: Arc::default(),
+        }
+    }
+
+    pub async fn get(&self, key: &str) -> Option<Arc<CachedResponse>> {
+        self.entries.get(key).await.map(|entry| entry.response)
+    }
+
+    pub async fn set(&self, key: String, response: Arc<CachedResponse>, ttl: Duration) {
+        self.entries.insert(key, Entry { response, ttl }).await;
+    }
+
+    pub fn generation(&self, route: &str) -> u64 {
+        let generations = self
+            .generations
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+
+        generations.get(route).copied().unwrap_or_default()
+    }
+
+    pub fn invalidate(&self, route: &str) {
+        let mut generations = self
+            .generations
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+
+        *generations.entry(route.to_owned()).or_default() += 1;
+    }
+}
@@ -0,0 +1,209 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use axum::body::Bytes;
+use shared::{
+    env::{self, Env},
+    result::Rs,
+};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use database::notify::IndexedEvent;
+
+mod memory;
+#[cfg(feature = "redis")]
+mod redis;
+
+const DEFAULT_MAX_ENTRIES: u64 = 10_000;
+
+/// A successful response kept for replay
+pub struct CachedResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    /// Strong validator, quoted as sent in the `ETag` header
+    pub etag: String,
+    pub body: Bytes,
+}
+
+/// Where cached responses live, selected at startup
+#[derive(Clone)]
+pub enum CacheStore {
+    Memory(memory::MemoryStore),
+    #[cfg(feature = "redis")]
+    Redis(redis::RedisStore),
+}
+
+impl CacheStore {
+    /// Redis when `REDIS_URL` is set and the `redis` feature is enabled, in-process otherwise
+    pub async fn from_env() -> Rs<Self> {
+        #[cfg(feature = "redis")]
+        if let Ok(url) = env::read(Env::RedisUrl) {
+            tracing::info!("Caching responses in Redis");
+            return Ok(Self::Redis(redis::RedisStore::connect(&url).await?));
+        }
+
+        #[cfg(not(feature = "redis"))]
+        if env::read(Env::RedisUrl).is_ok() {
+            tracing::warn!("REDIS_URL is ignored, http-server was built without the redis feature");
+        }
+
+        Ok(Self::memory(
+            env::read(Env::CacheMaxEntries)
+                .ok()
+                .and_then(|max| max.parse().ok())
+                .unwrap_or(DEFAULT_MAX_ENTRIES),
+        ))
+    }
+
+    pub fn memory(max_entries: u64) -> Self {
+        Self::Memory(memory::MemoryStore::new(max_entries))
+    }
+
+    /// Looks up `key`, treating store failures as misses
+    pub async fn get(&self, key: &str) -> Option<Arc<CachedResponse>> {
+        match self {
+            Self::Memory(store) => store.get(key).await,
+            #[cfg(feature = "redis")]
+            Self::Redis(store) => store
+                .get(key)
+                .await
+                .inspect_err(|error| error.trace("Cache read failed"))
+                .ok()
+                .flatten(),
+        }
+    }
+
+    /// Current generation of `route`, part of its keys so bumping it drops every entry
+    ///
+    /// Kept in the store, so instances sharing a Redis share entries and a restart
+    /// doesn't orphan them. Store failures read as generation 0.
+    pub async fn generation(&self, route: &str) -> u64 {
+        match self {
+            Self::Memory(store) => store.generation(route),
+            #[cfg(feature = "redis")]
+            Self::Redis(store) => store
+                .generation(route)
+                .await
+                .inspect_err(|error| error.trace("Cache generation read failed"))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Bumps the generation of `route`, invalidating all of its entries
+    pub async fn invalidate(&self, route: &str) {
+        match self {
+            Self::Memory(store) => store.invalidate(route),
+            #[cfg(feature = "redis")]
+            Self::Redis(store) => {
+                if let Err(error) = store.invalidate(route).await {
+                    error.trace("Cache invalidation failed");
+                }
+            }
+        }
+    }
+
+    /// Stores `response` under `key`, logging store failures
+    pub async fn set(&self, key: String, response: Arc<CachedResponse>, ttl: Duration) {
+        match self {
+            Self::Memory(store) => store.set(key, response, ttl).await,
+            #[cfg(feature = "redis")]
+            Self::Redis(store) => {
+                if let Err(error) = store.set(key, response, ttl).await {
+                    error.trace("Cache write failed");
+                }
+            }
+        }
+    }
+}
+
+/// How responses of one route are cached
+///
+/// Only `GET` requests answered with 200 are cached, keyed by path and query, so the
+/// route must not vary its response by caller
+pub struct CachePolicy {
+    ttl: Duration,
+    invalidated_by_events: bool,
+}
+
+impl CachePolicy {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            invalidated_by_events: false,
+        }
+    }
+
+    /// Drops the route's entries whenever an indexer persists a new event
+    pub fn invalidated_by_events(mut self) -> Self {
+        self.invalidated_by_events = true;
+        self
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+}
+
+/// Response cache with per-route policies, keyed by route template
+#[derive(Clone)]
+pub struct Cache {
+    pub store: CacheStore,
+    policies: Arc<HashMap<&'static str, CachePolicy>>,
+}
+
+impl Cache {
+    pub fn new<I>(store: CacheStore, policies: I) -> Self
+    where
+        I: IntoIterator<Item = (&'static str, CachePolicy)>,
+    {
+        Self {
+            store,
+            policies: Arc::new(policies.into_iter().collect()),
+        }
+    }
+
+    pub fn policy(&self, route: &str) -> Option<&CachePolicy> {
+        self.policies.get(route)
+    }
+
+    /// Key of the response to `path_and_query` on `route`, in the route's current generation
+    pub async fn key(&self, route: &str, path_and_query: &str) -> String {
+        let generation = self.store.generation(route).await;
+
+        format!("{}:{}:{}", route, generation, path_and_query)
+    }
+
+    /// Invalidates every entry of `route`
+    pub async fn invalidate(&self, route: &str) {
+        self.store.invalidate(route).await;
+    }
+
+    /// Invalidates event-driven policies as indexed events arrive, until shutdown
+    pub fn spawn_invalidation(&self, mut events: broadcast::Receiver<IndexedEvent>) {
+        let cache = self.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let received = tokio::select! {
+                    received = events.recv() => received,
+                    _ = shared::shutdown::wait() => return,
+                };
+
+                match received {
+                    // Missed events may have touched anything, so lagging invalidates too
+                    Ok(_) | Err(RecvError::Lagged(_)) => {
+                        let routes = cache
+                            .policies
+                            .iter()
+                            .filter(|(_, policy)| policy.invalidated_by_events)
+                            .map(|(route, _)| *route);
+
+                        for route in routes {
+                            cache.invalidate(route).await;
+                        }
+                    }
+                    Err(RecvError::Closed) => return,
+                }
+            }
+        });
+    }
+}
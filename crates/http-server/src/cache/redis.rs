@@ -0,0 +1,100 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use axum::body::Bytes;
+use redis::{AsyncCommands, aio::ConnectionManager};
+use shared::result::{AppErr, Rs};
+
+use super::CachedResponse;
+
+const KEY_PREFIX: &str = "http_cache:";
+const GENERATION_PREFIX: &str = "http_cache_generation:";
+
+/// Store shared by every server instance pointing at the same Redis
+#[derive(Clone)]
+pub struct RedisStore(ConnectionManager);
+
+impl RedisStore {
+    pub async fn connect(url: &str) -> Rs<Self> {
+        let client = redis::Client::open(url).map_err(redis_err)?;
+        let manager = ConnectionManager::new(client).await.map_err(redis_err)?;
+
+        Ok(Self(manager))
+    }
+
+    pub async fn get(&self, key: &str) -> Rs<Option<Arc<CachedResponse>>> {
+        let mut fields: HashMap<String, Vec<u8>> = self
+            .0
+            .clone()
+            .hgetall(format!("{}{}", KEY_PREFIX, key))
+            .await
+            .map_err(redis_err)?;
+
+        let mut text = |field: &str| fields.remove(field).map(String::from_utf8);
+
+        let (Some(Ok(status)), Some(Ok(etag))) = (text("status"), text("etag")) else {
+            return Ok(None);
+        };
+
+        let response = CachedResponse {
+            status: status
+                .parse()
+                .map_err(|_| AppErr::custom("invalid cached status"))?,
+            etag,
+            content_type: text("content_type").and_then(Result::ok),
+            body: fields.remove("body").map(Bytes::from).unwrap_or_default(),
+        };
+
+        Ok(Some(Arc::new(response)))
+    }
+
+    pub async fn set(&self, key: String, response: Arc<CachedResponse>, ttl: Duration) -> Rs<()> {
+        let key = format!("{}{}", KEY_PREFIX, key);
+
+        let mut fields = vec![
+            ("status", response.status.to_string().into_bytes()),
+            ("etag", response.etag.clone().into_bytes()),
+            ("body", response.body.to_vec()),
+        ];
+
+        if let Some(content_type) = &response.content_type {
+            fields.push(("content_type", content_type.clone().into_bytes()));
+        }
+
+        redis::pipe()
+            .atomic()
+            .del(&key)
+            .hset_multiple(&key, &fields)
+            .pexpire(&key, ttl.as_millis() as i64)
+            .exec_async(&mut self.0.clone())
+            .await
+            .map_err(redis_err)?;
+
+        Ok(())
+    }
+
+    pub async fn generation(&self, route: &str) -> Rs<u64> {
+        let generation: Option<u64> = self
+            .0
+            .clone()
+            .get(format!("{}{}", GENERATION_PREFIX, route))
+            .await
+            .map_err(redis_err)?;
+
+        Ok(generation.unwrap_or_default())
+    }
+
+    pub async fn invalidate(&self, route: &str) -> Rs<()> {
+        let _: u64 = self
+            .0
+            .clone()
+            .incr(format!("{}{}", GENERATION_PREFIX, route), 1)
+            .await
+            .map_err(redis_err)?;
+
+        Ok(())
+    }
+}
+
+fn redis_err(error: redis::RedisError) -> AppErr {
+    AppErr::custom(format!("redis: {}", error))
+}
@@ -0,0 +1,238 @@
+use std::{net::SocketAddr, time::Duration};
+
+use axum::http::{HeaderValue, Method, header};
+use shared::{
+    env::{self, Env},
+    result::{AppErr, Rs},
+};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+use crate::middlewares::{
+    idempotency::{IDEMPOTENCY_KEY, IDEMPOTENT_REPLAYED},
+    request_id::X_REQUEST_ID,
+};
+
+const DEFAULT_ADDR: &str = "0.0.0.0:8080";
+const DEFAULT_METHODS: &str = "GET,POST,PUT,PATCH,DELETE,OPTIONS";
+const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+/// Same as axum's default extractor limit
+const DEFAULT_BODY_LIMIT_BYTES: usize = 2 * 1024 * 1024;
+const DEFAULT_IDEMPOTENCY_TTL_SECS: u64 = 24 * 3600;
+
+/// Deployment profile, selected with `APP_PROFILE`
+#[derive(strum::EnumString, strum::Display, Default, Clone, Copy, PartialEq, Eq, Debug)]
+#[strum(serialize_all = "snake_case")]
+pub enum Profile {
+    #[default]
+    Development,
+    Production,
+}
+
+/// Origins allowed to make cross-origin requests
+#[derive(Debug)]
+pub enum Origins {
+    /// Any origin (`*`), only accepted outside production
+    Any,
+    List(Vec<HeaderValue>),
+}
+
+/// Runtime configuration of the HTTP server, read from the environment
+#[derive(Debug)]
+pub struct ServerConfig {
+    pub profile: Profile,
+    /// `SERVER_ADDR`, defaults to `0.0.0.0:8080`
+    pub addr: SocketAddr,
+    /// `CORS_ALLOWED_ORIGINS`, comma separated or `*`
+    pub cors_origins: Origins,
+    /// `CORS_ALLOWED_METHODS`, comma separated
+    pub cors_methods: Vec<Method>,
+    /// `REQUEST_TIMEOUT_SECS`, requests running longer get a 408
+    pub request_timeout: Duration,
+    /// `BODY_LIMIT_BYTES`, larger bodies get a 413
+    pub body_limit: usize,
+    /// `COMPRESSION`, gzip responses when the client accepts it
+    pub compression: bool,
+    /// `IDEMPOTENCY_TTL_SECS`, how long responses are kept for replay
+    pub idempotency_ttl: Duration,
+}
+
+impl ServerConfig {
+    /// Reads the configuration, falling back to development-friendly defaults
+    ///
+    /// Fails in the production profile unless `CORS_ALLOWED_ORIGINS` lists explicit origins
+    pub fn from_env() -> Rs<Self> {
+        Self::from_lookup(&env::var)
+    }
+
+    fn from_lookup(lookup: &impl Fn(&Env) -> Option<String>) -> Rs<Self> {
+        let profile = env::parse_or(lookup, Env::AppProfile, Profile::default())?;
+
+        let cors_origins = match lookup(&Env::CorsAllowedOrigins) {
+            None => Origins::Any,
+            Some(origins) if origins.trim() == "*" => Origins::Any,
+            Some(origins) => Origins::List(
+                split(&origins)
+                    .map(|origin| {
+                        // `AllowOrigin::list` panics on a wildcard among other origins
+                        if origin == "*" {
+                            return Err(AppErr::invalid_input(
+                                "CORS_ALLOWED_ORIGINS can only be `*` on its own",
+                            ));
+                        }
+
+                        HeaderValue::from_str(origin).map_err(|_| {
+                            AppErr::invalid_input(format!("invalid CORS origin: {}", origin))
+                        })
+                    })
+                    .collect::<Rs<_>>()?,
+            ),
+        };
+
+        if profile == Profile::Production && matches!(cors_origins, Origins::Any) {
+            return Err(AppErr::invalid_input(
+                "CORS_ALLOWED_ORIGINS must list explicit origins in the production profile",
+            ));
+        }
+
+        let cors_methods =
+            split(&lookup(&Env::CorsAllowedMethods).unwrap_or_else(|| DEFAULT_METHODS.to_owned()))
+                .map(|method| {
+                    method.to_uppercase().parse().map_err(|_| {
+                        AppErr::invalid_input(format!("invalid CORS method: {}", method))
+                    })
+                })
+                .collect::<Rs<_>>()?;
+
+        Ok(Self {
+            profile,
+            addr: env::parse_or(
+                lookup,
+                Env::ServerAddr,
+                DEFAULT_ADDR.parse().expect("valid address"),
+            )?,
+            cors_origins,
+            cors_methods,
+            request_timeout: Duration::from_secs(env::parse_or(
+                lookup,
+                Env::RequestTimeoutSecs,
+                DEFAULT_REQUEST_TIMEOUT_SECS,
+            )?),
+            body_limit: env::parse_or(lookup, Env::BodyLimitBytes, DEFAULT_BODY_LIMIT_BYTES)?,
+            compression: env::parse_or(lookup, Env::Compression, false)?,
+            idempotency_ttl: Duration::from_secs(env::parse_or(
+                lookup,
+                Env::IdempotencyTtlSecs,
+                DEFAULT_IDEMPOTENCY_TTL_SECS,
+            )?),
+        })
+    }
+
+    pub fn cors_layer(&self) -> CorsLayer {
+        let cors = CorsLayer::new()
+            .allow_methods(self.cors_methods.clone())
+            .expose_headers([X_REQUEST_ID, IDEMPOTENT_REPLAYED])
+            .max_age(Duration::from_secs(3600));
+
+        match &self.cors_origins {
+            Origins::Any => cors.allow_origin(Any).allow_headers(Any),
+            Origins::List(origins) => cors
+                .allow_origin(AllowOrigin::list(origins.clone()))
+                .allow_headers([
+                    header::AUTHORIZATION,
+                    header::CONTENT_TYPE,
+                    X_REQUEST_ID,
+                    IDEMPOTENCY_KEY,
+                ]),
+        }
+    }
+}
+
+fn split(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(vars: &[(Env, &str)]) -> Rs<ServerConfig> {
+        ServerConfig::from_lookup(&|key: &Env| {
+            vars.iter()
+                .find(|(var, _)| var.key() == key.key())
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn defaults_to_development() {
+        let config = config(&[]).unwrap();
+
+        assert_eq!(config.profile, Profile::Development);
+        assert!(matches!(config.cors_origins, Origins::Any));
+        assert_eq!(config.addr, DEFAULT_ADDR.parse().unwrap());
+        assert_eq!(config.cors_methods.len(), 6);
+        assert_eq!(config.request_timeout, Duration::from_secs(30));
+        assert_eq!(config.body_limit, DEFAULT_BODY_LIMIT_BYTES);
+        assert!(!config.compression);
+    }
+
+    #[test]
+    fn production_refuses_any_origin() {
+        for origins in [None, Some("*"), Some(" * ")] {
+            let mut vars = vec![(Env::AppProfile, "production")];
+            vars.extend(origins.map(|origins| (Env::CorsAllowedOrigins, origins)));
+
+            assert!(config(&vars).is_err(), "{:?}", origins);
+        }
+    }
+
+    #[test]
+    fn production_accepts_listed_origins() {
+        let config = config(&[
+            (Env::AppProfile, "production"),
+            (Env::CorsAllowedOrigins, "https://a.com, https://b.com,"),
+        ])
+        .unwrap();
+
+        let Origins::List(origins) = &config.cors_origins else {
+            panic!("expected listed origins");
+        };
+
+        assert_eq!(origins, &["https://a.com", "https://b.com"]);
+
+        // Builds without panicking
+        let _ = config.cors_layer();
+    }
+
+    #[test]
+    fn wildcard_must_be_alone() {
+        for profile in ["development", "production"] {
+            let result = config(&[
+                (Env::AppProfile, profile),
+                (Env::CorsAllowedOrigins, "https://a.com,*"),
+            ]);
+
+            assert!(result.is_err(), "{}", profile);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let cases = [
+            (Env::AppProfile, "staging"),
+            (Env::CorsAllowedOrigins, "https://a.com,https://\u{1}.com"),
+            (Env::CorsAllowedMethods, "GET,FETCH ME"),
+            (Env::ServerAddr, "localhost"),
+            (Env::RequestTimeoutSecs, "-1"),
+            (Env::Compression, "yes"),
+        ];
+
+        for (key, value) in cases {
+            let name = key.key();
+
+            assert!(config(&[(key, value)]).is_err(), "{}={:?}", name, value);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use axum::{
+    Router,
+    body::Bytes,
+    http::header,
+    response::{Html, IntoResponse},
+    routing::get,
+};
+use utoipa::{
+    Modify, OpenApi,
+    openapi::security::{Http, HttpAuthScheme, SecurityScheme},
+};
+
+use crate::extractors::state::AppState;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "API docs",
+        version = "1.0.0",
+        description = "Api document.\n\nEvery response carries an `X-Request-Id` header. \
+            Send one to propagate your own id; it is also included in error bodies."
+    ),
+    tags(
+        (name = "health", description = "Liveness, readiness and indexer lag"),
+        (name = "auth", description = "Wallet sign-in"),
+        (name = "users", description = "Authenticated user"),
+        (name = "ws", description = "WebSocket feeds"),
+        (name = "webhooks", description = "Signed push delivery of indexed events"),
+        (name = "admin", description = "Scanner cursors and settings, restricted to ADMIN_ADDRESSES"),
+    ),
+    // Sent to webhooks rather than returned by a route
+    components(schemas(api_types::webhooks::WebhookEvent)),
+    modifiers(&Finalize)
+)]
+pub struct ApiDoc;
+
+/// Registers the `BearerAuth` scheme and drops the empty license utoipa derives from the manifest
+struct Finalize;
+
+impl Modify for Finalize {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi.info.license = None;
+
+        let components = openapi.components.get_or_insert_with(Default::default);
+
+        let mut scheme = Http::new(HttpAuthScheme::Bearer);
+        scheme.bearer_format = Some("JWT".to_owned());
+        scheme.description = Some("auth token".to_owned());
+
+        components.add_security_scheme("BearerAuth", SecurityScheme::Http(scheme));
+    }
+}
+
+/// Serves the generated spec and the Swagger/Scalar pages that render it
+pub fn routes(openapi: utoipa::openapi::OpenApi) -> Router<AppState> {
+    let spec = Bytes::from(openapi.to_json().expect("OpenAPI spec is serializable"));
+
+    Router::new()
+        .route(
+            "/docs/openapi.json",
+            get(async move || ([(header::CONTENT_TYPE, "application/json")], spec).into_response()),
+        )
+        .route(
+            "/swagger",
+            get(async || Html(include_str!("../docs/swagger.html"))),
+        )
+        .route(
+            "/scalar",
+            get(async || Html(include_str!("../docs/scalar.html"))),
+        )
+}
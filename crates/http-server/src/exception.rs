@@ -1,10 +1,13 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::BTreeMap};
 
+pub use api_types::error::{ErrorBody, ErrorCode, FieldError};
 use axum::{Json, http::StatusCode, response::IntoResponse};
-use serde_json::json;
+use database::sea_orm::{DbErr, SqlErr};
 
 use shared::result::AppErr;
 
+use crate::middlewares::request_id;
+
 type Location = &'static core::panic::Location<'static>;
 
 #[derive(thiserror::Error, Debug)]
@@ -17,12 +20,42 @@ pub enum HttpException {
 
     #[error("BadRequest: {msg}")]
     BadRequest {
+        code: ErrorCode,
         msg: Cow<'static, str>,
         location: Location,
     },
 
     #[error("Unauthorized: {msg}")]
     Unauthorized {
+        code: ErrorCode,
+        msg: Cow<'static, str>,
+        location: Location,
+    },
+
+    #[error("Forbidden: {msg}")]
+    Forbidden {
+        code: ErrorCode,
+        msg: Cow<'static, str>,
+        location: Location,
+    },
+
+    #[error("NotFound: {msg}")]
+    NotFound {
+        code: ErrorCode,
+        msg: Cow<'static, str>,
+        location: Location,
+    },
+
+    #[error("Conflict: {msg}")]
+    Conflict {
+        code: ErrorCode,
+        msg: Cow<'static, str>,
+        location: Location,
+    },
+
+    #[error("TooManyRequests: {msg}")]
+    TooManyRequests {
+        code: ErrorCode,
         msg: Cow<'static, str>,
         location: Location,
     },
@@ -100,6 +133,10 @@ impl HttpException {
             Self::Validation { location, .. } => location,
             Self::BadRequest { location, .. } => location,
             Self::Unauthorized { location, .. } => location,
+            Self::Forbidden { location, .. } => location,
+            Self::NotFound { location, .. } => location,
+            Self::Conflict { location, .. } => location,
+            Self::TooManyRequests { location, .. } => location,
             Self::Internal { location, .. } => location,
             Self::ParseInt { location, .. } => location,
             Self::ParseAddress { location, .. } => location,
@@ -114,6 +151,91 @@ impl HttpException {
         tracing::error!("{}\nTrace: {}", self, self.location());
     }
 
+    /// Resolves the HTTP status and error code this exception is reported with
+    fn classify(&self) -> (StatusCode, ErrorCode) {
+        match self {
+            Self::Validation { .. } => (StatusCode::BAD_REQUEST, ErrorCode::ValidationFailed),
+            Self::BadRequest { code, .. } => (StatusCode::BAD_REQUEST, *code),
+            Self::Unauthorized { code, .. } => (StatusCode::UNAUTHORIZED, *code),
+            Self::Forbidden { code, .. } => (StatusCode::FORBIDDEN, *code),
+            Self::NotFound { code, .. } => (StatusCode::NOT_FOUND, *code),
+            Self::Conflict { code, .. } => (StatusCode::CONFLICT, *code),
+            Self::TooManyRequests { code, .. } => (StatusCode::TOO_MANY_REQUESTS, *code),
+            Self::ParseInt { .. }
+            | Self::ParseAddress { .. }
+            | Self::ParseSignature { .. }
+            | Self::ParseSolanaPubkey { .. }
+            | Self::ParseSolanaSignature { .. }
+            | Self::App(AppErr::InvalidInput { .. }) => {
+                (StatusCode::BAD_REQUEST, ErrorCode::InvalidInput)
+            }
+            Self::App(AppErr::Database { src, .. }) => classify_db_err(src),
+            Self::Internal { .. } | Self::App(_) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, ErrorCode::InternalError)
+            }
+        }
+    }
+
+    /// Replaces the error code of a client-error variant, keeping its status and message
+    pub fn with_code(mut self, new_code: ErrorCode) -> Self {
+        match &mut self {
+            Self::BadRequest { code, .. }
+            | Self::Unauthorized { code, .. }
+            | Self::Forbidden { code, .. }
+            | Self::NotFound { code, .. }
+            | Self::Conflict { code, .. }
+            | Self::TooManyRequests { code, .. } => *code = new_code,
+            _ => {}
+        }
+
+        self
+    }
+
+    /// The message sent to the client
+    ///
+    /// Database errors carry constraint, table and SQL details, so the client gets a
+    /// generic message for them and the full error is only logged
+    fn public_message(&self, status_code: StatusCode) -> String {
+        if !matches!(self, Self::App(AppErr::Database { .. })) {
+            return self.to_string();
+        }
+
+        if status_code.is_client_error() {
+            tracing::warn!("{}\nTrace: {}", self, self.location());
+        }
+
+        match status_code {
+            StatusCode::NOT_FOUND => "resource not found",
+            StatusCode::CONFLICT => "conflicts with an existing resource",
+            _ => "internal server error",
+        }
+        .to_owned()
+    }
+
+    fn details(&self) -> Option<BTreeMap<String, Vec<FieldError>>> {
+        let Self::Validation { src, .. } = self else {
+            return None;
+        };
+
+        let details = src
+            .field_errors()
+            .into_iter()
+            .map(|(field, errors)| {
+                let errors = errors
+                    .iter()
+                    .map(|error| FieldError {
+                        code: error.code.to_string(),
+                        message: error.message.as_ref().map(ToString::to_string),
+                    })
+                    .collect();
+
+                (field.to_string(), errors)
+            })
+            .collect();
+
+        Some(details)
+    }
+
     #[track_caller]
     pub fn internal<E: ToString>(error: E) -> Self {
         Self::Internal {
@@ -123,9 +245,9 @@ impl HttpException {
     }
 
     #[track_caller]
-    #[allow(dead_code)]
     pub fn bad_request<E: Into<Cow<'static, str>>>(error: E) -> Self {
         Self::BadRequest {
+            code: ErrorCode::BadRequest,
             msg: error.into(),
             location: core::panic::Location::caller(),
         }
@@ -134,6 +256,7 @@ impl HttpException {
     #[track_caller]
     pub fn validate<E: Into<Cow<'static, str>>>(error: E) -> Self {
         Self::BadRequest {
+            code: ErrorCode::ValidationFailed,
             msg: error.into(),
             location: core::panic::Location::caller(),
         }
@@ -142,28 +265,160 @@ impl HttpException {
     #[track_caller]
     pub fn unauthorized<E: Into<Cow<'static, str>>>(error: E) -> Self {
         Self::Unauthorized {
+            code: ErrorCode::Unauthorized,
+            msg: error.into(),
+            location: core::panic::Location::caller(),
+        }
+    }
+
+    #[track_caller]
+    pub fn forbidden<E: Into<Cow<'static, str>>>(error: E) -> Self {
+        Self::Forbidden {
+            code: ErrorCode::Forbidden,
+            msg: error.into(),
+            location: core::panic::Location::caller(),
+        }
+    }
+
+    #[track_caller]
+    pub fn not_found<E: Into<Cow<'static, str>>>(error: E) -> Self {
+        Self::NotFound {
+            code: ErrorCode::NotFound,
+            msg: error.into(),
+            location: core::panic::Location::caller(),
+        }
+    }
+
+    #[track_caller]
+    pub fn conflict<E: Into<Cow<'static, str>>>(error: E) -> Self {
+        Self::Conflict {
+            code: ErrorCode::Conflict,
+            msg: error.into(),
+            location: core::panic::Location::caller(),
+        }
+    }
+
+    #[track_caller]
+    #[allow(dead_code)]
+    pub fn too_many_requests<E: Into<Cow<'static, str>>>(error: E) -> Self {
+        Self::TooManyRequests {
+            code: ErrorCode::TooManyRequests,
             msg: error.into(),
             location: core::panic::Location::caller(),
         }
     }
 }
 
+fn classify_db_err(error: &DbErr) -> (StatusCode, ErrorCode) {
+    if let DbErr::RecordNotFound(_) = error {
+        return (StatusCode::NOT_FOUND, ErrorCode::NotFound);
+    }
+
+    match error.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_) | SqlErr::ForeignKeyConstraintViolation(_)) => {
+            (StatusCode::CONFLICT, ErrorCode::Conflict)
+        }
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, ErrorCode::InternalError),
+    }
+}
+
 impl IntoResponse for HttpException {
     fn into_response(self) -> axum::response::Response {
-        let status_code = match &self {
-            Self::BadRequest { .. } | Self::Validation { .. } => StatusCode::BAD_REQUEST,
-            Self::Unauthorized { .. } => StatusCode::UNAUTHORIZED,
-            _ => {
-                self.trace();
-                StatusCode::INTERNAL_SERVER_ERROR
-            }
-        };
+        let (status_code, code) = self.classify();
 
-        let body = Json(json!({
-            "code": status_code.as_u16(),
-            "msg": self.to_string(),
-        }));
+        if status_code.is_server_error() {
+            self.trace();
+        }
+
+        let body = Json(ErrorBody {
+            code,
+            status: status_code.as_u16(),
+            msg: self.public_message(status_code),
+            details: self.details(),
+            request_id: request_id::current(),
+        });
 
         (status_code, body).into_response()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::body::to_bytes;
+
+    use super::*;
+
+    async fn respond(exception: HttpException) -> (StatusCode, ErrorBody) {
+        let response = exception.into_response();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn constructors_map_to_their_status_and_code() {
+        let cases = [
+            (
+                HttpException::bad_request("bad"),
+                StatusCode::BAD_REQUEST,
+                ErrorCode::BadRequest,
+            ),
+            (
+                HttpException::validate("invalid"),
+                StatusCode::BAD_REQUEST,
+                ErrorCode::ValidationFailed,
+            ),
+            (
+                HttpException::unauthorized("who"),
+                StatusCode::UNAUTHORIZED,
+                ErrorCode::Unauthorized,
+            ),
+            (
+                HttpException::forbidden("no"),
+                StatusCode::FORBIDDEN,
+                ErrorCode::Forbidden,
+            ),
+            (
+                HttpException::not_found("gone"),
+                StatusCode::NOT_FOUND,
+                ErrorCode::NotFound,
+            ),
+            (
+                HttpException::conflict("taken"),
+                StatusCode::CONFLICT,
+                ErrorCode::Conflict,
+            ),
+            (
+                HttpException::too_many_requests("slow down"),
+                StatusCode::TOO_MANY_REQUESTS,
+                ErrorCode::TooManyRequests,
+            ),
+            (
+                HttpException::internal("boom"),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ErrorCode::InternalError,
+            ),
+        ];
+
+        for (exception, status, code) in cases {
+            let (actual_status, body) = respond(exception).await;
+
+            assert_eq!(actual_status, status);
+            assert_eq!(body.status, status.as_u16());
+            assert_eq!(body.code, code);
+        }
+    }
+
+    #[tokio::test]
+    async fn with_code_keeps_the_status() {
+        let exception =
+            HttpException::too_many_requests("slow down").with_code(ErrorCode::Conflict);
+
+        let (status, body) = respond(exception).await;
+
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(body.code, ErrorCode::Conflict);
+        assert_eq!(body.msg, "TooManyRequests: slow down");
+    }
+}
@@ -1,5 +1,9 @@
-use crate::exception::{HttpException, HttpResult};
-use axum::{RequestPartsExt, extract::FromRequestParts, http::request::Parts};
+use crate::exception::{ErrorCode, HttpException, HttpResult};
+use axum::{
+    RequestPartsExt,
+    extract::{FromRequestParts, OptionalFromRequestParts},
+    http::{header, request::Parts},
+};
 use axum_extra::{
     TypedHeader,
     headers::{Authorization, authorization::Bearer},
@@ -28,21 +32,73 @@ where
         parts
             .extract::<TypedHeader<Authorization<Bearer>>>()
             .await
-            .map_err(|_| HttpException::unauthorized("Missing Authorization"))
-            .and_then(|bearer| decode_token(bearer.token(), &secret))
+            .map_err(|_| {
+                HttpException::unauthorized("Missing Authorization")
+                    .with_code(ErrorCode::AuthMissingToken)
+            })
+            .and_then(|bearer| decode_token::<Claims>(bearer.token(), &secret))
+            .inspect(|claims| {
+                tracing::Span::current().record("wallet", tracing::field::display(claims.address));
+            })
             .map(Self)
     }
 }
 
-fn decode_token<T: DeserializeOwned>(token: &str, secret: &str) -> HttpResult<T> {
+/// Anonymous when the `Authorization` header is absent, rejected when it's invalid
+impl<S> OptionalFromRequestParts<S> for Auth
+where
+    S: Send + Sync,
+{
+    type Rejection = HttpException;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> HttpResult<Option<Self>> {
+        if !parts.headers.contains_key(header::AUTHORIZATION) {
+            return Ok(None);
+        }
+
+        <Self as FromRequestParts<S>>::from_request_parts(parts, state)
+            .await
+            .map(Some)
+    }
+}
+
+/// An authenticated caller whose address is listed in `ADMIN_ADDRESSES`
+pub struct Admin(pub Claims);
+
+impl<S> FromRequestParts<S> for Admin
+where
+    S: Send + Sync,
+{
+    type Rejection = HttpException;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> HttpResult<Self> {
+        let Auth(claims) = <Auth as FromRequestParts<S>>::from_request_parts(parts, state).await?;
+
+        let is_admin = shared::env::read(Env::AdminAddresses)
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|address| address.trim().parse::<UnionAddress>().ok())
+            .any(|address| address == claims.address);
+
+        if !is_admin {
+            return Err(HttpException::forbidden("Admin only"));
+        }
+
+        Ok(Self(claims))
+    }
+}
+
+pub(crate) fn decode_token<T: DeserializeOwned>(token: &str, secret: &str) -> HttpResult<T> {
     jsonwebtoken::decode::<T>(
         token,
         &DecodingKey::from_secret(secret.as_bytes()),
         &Validation::default(),
     )
     .map_err(|err| match err.kind() {
-        ErrorKind::ExpiredSignature => HttpException::unauthorized("Expired token"),
-        _ => HttpException::unauthorized("Invalid token"),
+        ErrorKind::ExpiredSignature => {
+            HttpException::unauthorized("Expired token").with_code(ErrorCode::AuthTokenExpired)
+        }
+        _ => HttpException::unauthorized("Invalid token").with_code(ErrorCode::AuthInvalidToken),
     })
     .map(|token_data| token_data.claims)
 }
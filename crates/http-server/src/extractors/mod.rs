@@ -1,3 +1,4 @@
 pub mod auth;
+pub mod pagination;
 pub mod state;
 pub mod validator;
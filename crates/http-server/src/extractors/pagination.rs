@@ -0,0 +1,92 @@
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use database::pagination::{Cursor, Direction, PageRequest};
+use serde::{Deserialize, de::DeserializeOwned};
+use utoipa::IntoParams;
+use validator::Validate;
+
+use crate::exception::HttpException;
+
+pub const DEFAULT_LIMIT: u64 = 20;
+pub const MAX_LIMIT: u64 = 100;
+
+/// Typed filters of a list endpoint, read from the same query string as the page params
+///
+/// Implementors must not use `deny_unknown_fields`: `cursor`, `limit`, `sort` and
+/// `order` are parsed separately
+pub trait Filter: DeserializeOwned + Validate + Send {
+    /// Sort fields the endpoint accepts
+    type Sort: DeserializeOwned + Default + Send;
+}
+
+/// Query params shared by every list endpoint
+#[derive(Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageParams {
+    /// Opaque cursor taken from a previous page's `next_cursor`
+    cursor: Option<String>,
+    /// Page size
+    #[validate(range(min = 1, max = MAX_LIMIT))]
+    #[param(minimum = 1, maximum = 100, default = 20)]
+    limit: Option<u64>,
+    /// Sort direction
+    #[param(value_type = Option<String>, pattern = "^(asc|desc)$", default = "asc")]
+    order: Option<Direction>,
+}
+
+#[derive(Deserialize)]
+struct SortParam<S> {
+    sort: Option<S>,
+}
+
+/// Cursor pagination, sorting and validated filters for list endpoints
+pub struct Paginated<F: Filter> {
+    pub page: PageRequest,
+    pub sort: F::Sort,
+    pub filter: F,
+}
+
+impl<S, F> FromRequestParts<S> for Paginated<F>
+where
+    S: Send + Sync,
+    F: Filter,
+{
+    type Rejection = HttpException;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(params) = Query::<PageParams>::from_request_parts(parts, state)
+            .await
+            .map_err(|error| HttpException::validate(error.to_string()))?;
+        params.validate()?;
+
+        let Query(SortParam { sort }) =
+            Query::<SortParam<F::Sort>>::from_request_parts(parts, state)
+                .await
+                .map_err(|error| HttpException::validate(error.to_string()))?;
+
+        let Query(filter) = Query::<F>::from_request_parts(parts, state)
+            .await
+            .map_err(|error| HttpException::validate(error.to_string()))?;
+        filter.validate()?;
+
+        let cursor = params
+            .cursor
+            .as_deref()
+            .map(|raw| Cursor::decode(raw).ok_or_else(|| HttpException::validate("invalid cursor")))
+            .transpose()?;
+
+        let page = PageRequest {
+            cursor,
+            limit: params.limit.unwrap_or(DEFAULT_LIMIT),
+            direction: params.order.unwrap_or_default(),
+        };
+
+        Ok(Paginated {
+            page,
+            sort: sort.unwrap_or_default(),
+            filter,
+        })
+    }
+}
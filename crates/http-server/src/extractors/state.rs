@@ -1,16 +1,71 @@
+use std::sync::Arc;
+
 use axum::extract::FromRef;
-use database::sea_orm::DatabaseConnection;
+use database::{
+    repos::{LogMemoRepo, Repos, SettingRepo, SigningMessageRepo, UserRepo},
+    sea_orm::DatabaseConnection,
+};
 use shared::{env::Env, result::Rs};
 
+use crate::feed::EventFeed;
+
 #[derive(FromRef, Clone)]
 pub struct AppState {
     pub db: DatabaseConnection,
+    pub feed: EventFeed,
+    pub replica: ReadReplica,
+    pub users: Arc<dyn UserRepo>,
+    pub settings: Arc<dyn SettingRepo>,
+    pub log_memos: Arc<dyn LogMemoRepo>,
+    pub signing_messages: Arc<dyn SigningMessageRepo>,
 }
 
 impl AppState {
     pub async fn new() -> Rs<AppState> {
         let db_url = shared::env::read(Env::DatabaseUrl)?;
         let db = database::establish_connection(&db_url).await?;
-        Ok(Self { db })
+        database::migrations::check(&db).await?;
+        let replica = database::establish_replica_connection().await?;
+        let feed = EventFeed::spawn(db.clone());
+        let repos = Repos::sql(db.clone(), replica.clone());
+        let replica = ReadReplica(replica.unwrap_or_else(|| db.clone()));
+        Ok(Self::with_repos(db, replica, feed, repos))
+    }
+
+    /// State whose repositories are `repos` instead of the database, e.g. [`Repos::memory`]
+    pub fn with_repos(
+        db: DatabaseConnection,
+        replica: ReadReplica,
+        feed: EventFeed,
+        repos: Repos,
+    ) -> AppState {
+        Self {
+            db,
+            feed,
+            replica,
+            users: repos.users,
+            settings: repos.settings,
+            log_memos: repos.log_memos,
+            signing_messages: repos.signing_messages,
+        }
+    }
+
+    /// State on [`Repos::memory`], with a disconnected database and an idle feed
+    ///
+    /// Only handlers going through the repositories can be served from it.
+    #[cfg(test)]
+    pub fn memory() -> AppState {
+        Self::with_repos(
+            DatabaseConnection::default(),
+            ReadReplica(DatabaseConnection::default()),
+            EventFeed::idle(),
+            Repos::memory(),
+        )
     }
 }
+
+/// Connection for read-only queries that tolerate replication lag, like listings
+///
+/// The read replica at `DATABASE_READ_URL`, or the primary database when unset
+#[derive(Clone)]
+pub struct ReadReplica(pub DatabaseConnection);
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use database::{
+    notify::{IndexedEvent, Listener},
+    sea_orm::DatabaseConnection,
+};
+use tokio::sync::broadcast;
+
+const CAPACITY: usize = 1024;
+const DELAY_RECONNECT: Duration = Duration::from_millis(1_000);
+
+/// Live feed of events persisted by the indexers, fanned out to in-process subscribers
+#[derive(Clone)]
+pub struct EventFeed(broadcast::Sender<IndexedEvent>);
+
+impl EventFeed {
+    /// Starts listening for indexed events until shutdown
+    pub fn spawn(db: DatabaseConnection) -> Self {
+        let (tx, _) = broadcast::channel(CAPACITY);
+        let feed = Self(tx.clone());
+
+        tokio::spawn(async move {
+            while !shared::shutdown::requested() {
+                if let Err(error) = forward(&db, &tx).await {
+                    error.trace("Indexed event listener failed, reconnecting");
+                }
+
+                tokio::select! {
+                    _ = tokio::time::sleep(DELAY_RECONNECT) => {}
+                    _ = shared::shutdown::wait() => {}
+                }
+            }
+        });
+
+        feed
+    }
+
+    /// A feed nothing is ever sent on, for states without indexers behind them
+    #[cfg(test)]
+    pub fn idle() -> Self {
+        Self(broadcast::channel(1).0)
+    }
+
+    /// Fans `event` out as if an indexer had published it, returns whether anyone got it
+    #[cfg(test)]
+    pub fn send(&self, event: IndexedEvent) -> bool {
+        self.0.send(event).is_ok()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<IndexedEvent> {
+        self.0.subscribe()
+    }
+}
+
+async fn forward(
+    db: &DatabaseConnection,
+    tx: &broadcast::Sender<IndexedEvent>,
+) -> shared::result::Rs<()> {
+    let mut listener = Listener::connect(db).await?;
+
+    loop {
+        tokio::select! {
+            event = listener.recv() => {
+                // No subscribers is fine, the event is simply dropped
+                let _ = tx.send(event?);
+            }
+            _ = shared::shutdown::wait() => return Ok(()),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_graphql::dataloader::Loader;
+use database::repos::UserRepo;
+use shared::{UnionAddress, result::AppErr};
+
+/// Batches user lookups made while resolving a single request
+pub struct UserLoader {
+    users: Arc<dyn UserRepo>,
+}
+
+impl UserLoader {
+    pub fn new(users: Arc<dyn UserRepo>) -> Self {
+        Self { users }
+    }
+}
+
+impl Loader<UnionAddress> for UserLoader {
+    /// Addresses present in the map are registered users
+    type Value = UnionAddress;
+    type Error = Arc<AppErr>;
+
+    async fn load(
+        &self,
+        keys: &[UnionAddress],
+    ) -> Result<HashMap<UnionAddress, UnionAddress>, Self::Error> {
+        let users = self.users.find_by_wallet_addresses(keys.to_vec()).await?;
+
+        let found = users
+            .into_iter()
+            .filter_map(|user| user.wallet_address.parse().ok())
+            .map(|address| (address, address))
+            .collect();
+
+        Ok(found)
+    }
+}
@@ -0,0 +1,342 @@
+use std::sync::Arc;
+
+use async_graphql::{
+    ErrorExtensions, Schema,
+    dataloader::DataLoader,
+    http::{ALL_WEBSOCKET_PROTOCOLS, GraphiQLSource},
+};
+use async_graphql_axum::{GraphQLProtocol, GraphQLRequest, GraphQLResponse, GraphQLWebSocket};
+use axum::{
+    Router,
+    extract::{
+        State, WebSocketUpgrade,
+        ws::{CloseFrame, Message, close_code},
+    },
+    response::{Html, IntoResponse, Response},
+    routing::get,
+};
+use database::repos::UserRepo;
+use futures_util::{SinkExt, StreamExt};
+use shared::result::AppErr;
+
+use crate::{
+    exception::ErrorCode,
+    extractors::{auth::Auth, state::AppState},
+};
+
+mod loaders;
+mod query;
+mod subscription;
+
+pub type AppSchema = Schema<query::Query, async_graphql::EmptyMutation, subscription::Subscription>;
+
+const ENDPOINT: &str = "/graphql";
+const SUBSCRIPTION_ENDPOINT: &str = "/graphql/ws";
+/// Deepest selection a query may nest
+const MAX_DEPTH: usize = 10;
+/// Most fields a query may select, counting each field once
+const MAX_COMPLEXITY: usize = 200;
+
+/// The schema and the repositories each request's loaders batch against
+#[derive(Clone)]
+struct GraphQL {
+    schema: AppSchema,
+    users: Arc<dyn UserRepo>,
+}
+
+/// `POST /graphql` for queries, `GET /graphql` for GraphiQL and `/graphql/ws` for subscriptions
+pub fn routes(state: &AppState) -> Router<AppState> {
+    let graphql = GraphQL {
+        schema: schema(state),
+        users: state.users.clone(),
+    };
+
+    Router::new()
+        .route(ENDPOINT, get(graphiql).post(execute))
+        .route(SUBSCRIPTION_ENDPOINT, get(subscribe))
+        .with_state(graphql)
+}
+
+fn schema(state: &AppState) -> AppSchema {
+    Schema::build(
+        query::Query,
+        async_graphql::EmptyMutation,
+        subscription::Subscription,
+    )
+    .limit_depth(MAX_DEPTH)
+    .limit_complexity(MAX_COMPLEXITY)
+    .data(state.feed.clone())
+    .finish()
+}
+
+/// Runs a query, with the caller's claims in context when a bearer token is sent
+///
+/// Loaders are created per request, so nothing is cached across requests
+async fn execute(
+    State(graphql): State<GraphQL>,
+    auth: Option<Auth>,
+    request: GraphQLRequest,
+) -> GraphQLResponse {
+    let mut request = request.into_inner().data(DataLoader::new(
+        loaders::UserLoader::new(graphql.users),
+        tokio::spawn,
+    ));
+
+    if let Some(Auth(claims)) = auth {
+        request = request.data(claims);
+    }
+
+    graphql.schema.execute(request).await.into()
+}
+
+/// Serves subscriptions over `graphql-transport-ws` or the legacy `graphql-ws` protocol
+///
+/// On shutdown connections are sent a close frame (1001, going away) and dropped
+async fn subscribe(
+    State(GraphQL { schema, .. }): State<GraphQL>,
+    protocol: GraphQLProtocol,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    upgrade
+        .protocols(ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |socket| async move {
+            let _tracked = shared::shutdown::track();
+            let _connection = shared::metrics::ws_connection_opened("graphql");
+
+            let (mut sink, stream) = socket.split();
+            let serve =
+                GraphQLWebSocket::new_with_pair(&mut sink, stream, schema, protocol).serve();
+
+            let shutting_down = tokio::select! {
+                _ = serve => false,
+                _ = shared::shutdown::wait() => true,
+            };
+
+            if shutting_down {
+                let close = Message::Close(Some(CloseFrame {
+                    code: close_code::AWAY,
+                    reason: "server shutting down".into(),
+                }));
+
+                if let Err(error) = sink.send(close).await {
+                    tracing::debug!("Failed to close GraphQL subscription: {}", error);
+                }
+            }
+        })
+}
+
+async fn graphiql() -> impl IntoResponse {
+    Html(
+        GraphiQLSource::build()
+            .endpoint(ENDPOINT)
+            .subscription_endpoint(SUBSCRIPTION_ENDPOINT)
+            .finish(),
+    )
+}
+
+/// Error with the same `code` extension as REST error bodies
+fn error(code: ErrorCode, message: &str) -> async_graphql::Error {
+    async_graphql::Error::new(message).extend_with(|_, extensions| {
+        extensions.set(
+            "code",
+            serde_json::to_value(code)
+                .unwrap_or_default()
+                .as_str()
+                .unwrap_or_default(),
+        )
+    })
+}
+
+/// Traces `error` and hides its details from the client
+fn internal(error: &AppErr) -> async_graphql::Error {
+    error.trace("GraphQL resolver failed");
+    self::error(ErrorCode::InternalError, "internal error")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::http::StatusCode;
+    use database::notify::IndexedEvent;
+    use serde_json::{Value, json};
+    use shared::UnionAddress;
+
+    use super::*;
+    use crate::testing;
+
+    const EVM_ADDRESS: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+    const SOLANA_ADDRESS: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
+    const UNKNOWN_ADDRESS: &str = "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359";
+
+    async fn state() -> AppState {
+        let state = AppState::memory();
+
+        for address in [EVM_ADDRESS, SOLANA_ADDRESS] {
+            state.users.save(address.parse().unwrap()).await.unwrap();
+        }
+
+        state
+    }
+
+    async fn query(state: &AppState, token: Option<&str>, query: &str) -> Value {
+        let router = routes(state).with_state(state.clone());
+
+        let (status, response): (_, Value) = testing::send(
+            &router,
+            "POST",
+            ENDPOINT,
+            token,
+            Some(json!({ "query": query })),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+
+        response
+    }
+
+    fn address(address: &str) -> String {
+        address.parse::<UnionAddress>().unwrap().to_string()
+    }
+
+    fn error_code(response: &Value) -> Value {
+        response["errors"][0]["extensions"]["code"].clone()
+    }
+
+    #[tokio::test]
+    async fn users_are_loaded_in_input_order() {
+        let state = state().await;
+
+        let response = query(
+            &state,
+            None,
+            &format!(
+                r#"{{ users(addresses: ["{}", "{}", "{}"]) {{ address }} }}"#,
+                SOLANA_ADDRESS, UNKNOWN_ADDRESS, EVM_ADDRESS
+            ),
+        )
+        .await;
+
+        assert_eq!(
+            response["data"]["users"],
+            json!([{ "address": address(SOLANA_ADDRESS) }, { "address": address(EVM_ADDRESS) }])
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_user_is_null() {
+        let state = state().await;
+
+        let response = query(
+            &state,
+            None,
+            &format!(
+                r#"{{ user(address: "{}") {{ address }} }}"#,
+                UNKNOWN_ADDRESS
+            ),
+        )
+        .await;
+
+        assert_eq!(response["data"]["user"], Value::Null);
+        assert!(response.get("errors").is_none());
+    }
+
+    #[tokio::test]
+    async fn invalid_address_is_rejected() {
+        let state = state().await;
+
+        let response = query(&state, None, r#"{ user(address: "nope") { address } }"#).await;
+
+        assert_eq!(
+            error_code(&response),
+            serde_json::to_value(ErrorCode::InvalidInput).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn me_is_the_token_address() {
+        let state = state().await;
+        let token = testing::token(EVM_ADDRESS.parse::<UnionAddress>().unwrap());
+
+        let response = query(&state, Some(&token), "{ me { address } }").await;
+
+        assert_eq!(
+            response["data"]["me"],
+            json!({ "address": address(EVM_ADDRESS) })
+        );
+    }
+
+    #[tokio::test]
+    async fn me_requires_a_token() {
+        let state = state().await;
+
+        let response = query(&state, None, "{ me { address } }").await;
+
+        assert_eq!(response["data"]["me"], Value::Null);
+        assert_eq!(
+            error_code(&response),
+            serde_json::to_value(ErrorCode::AuthMissingToken).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn deep_and_complex_queries_are_rejected() {
+        let state = state().await;
+
+        let deep = "{ __schema { types { fields { type { ofType { ofType { ofType { ofType \
+            { ofType { ofType { ofType { name } } } } } } } } } } } }";
+        let complex = format!(
+            "{{ {} }}",
+            (0..=MAX_COMPLEXITY)
+                .map(|i| format!("u{}: me {{ address }}", i))
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+
+        for query_text in [deep, &complex] {
+            let response = query(&state, None, query_text).await;
+
+            assert!(response.get("data").is_none_or(Value::is_null));
+            assert!(response["errors"][0]["message"].is_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn subscription_streams_matching_events() {
+        let state = AppState::memory();
+        let mut stream = schema(&state).execute_stream(
+            r#"subscription { indexedEvents(chain: "1") { chain protocol txHash logIx } }"#,
+        );
+
+        let event = |chain: &str| IndexedEvent {
+            chain: chain.to_owned(),
+            protocol: "uniswap_v3".to_owned(),
+            tx_hash: "0xabc".to_owned(),
+            log_ix: 7,
+        };
+
+        let next = tokio::spawn(async move { stream.next().await });
+
+        // The stream subscribes to the feed once polled, events sent before are dropped
+        while !state.feed.send(event("56")) {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        state.feed.send(event("1"));
+
+        let response = next.await.unwrap().unwrap();
+
+        assert!(response.errors.is_empty());
+        assert_eq!(
+            response.data.into_json().unwrap(),
+            json!({
+                "indexedEvents": {
+                    "chain": "1",
+                    "protocol": "uniswap_v3",
+                    "txHash": "0xabc",
+                    "logIx": 7
+                }
+            })
+        );
+    }
+}
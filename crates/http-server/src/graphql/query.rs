@@ -0,0 +1,87 @@
+use async_graphql::{Context, Object, Result, SimpleObject, dataloader::DataLoader};
+use shared::UnionAddress;
+
+use super::{error, internal, loaders::UserLoader};
+use crate::{exception::ErrorCode, extractors::auth::Claims};
+
+/// Most addresses accepted by `users` in one query
+const MAX_ADDRESSES: usize = 100;
+
+/// A registered user
+#[derive(SimpleObject)]
+pub struct User {
+    /// EVM address or Solana public key
+    address: String,
+}
+
+impl From<UnionAddress> for User {
+    fn from(address: UnionAddress) -> Self {
+        Self {
+            address: address.to_string(),
+        }
+    }
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// The user the bearer token was issued to
+    async fn me(&self, ctx: &Context<'_>) -> Result<Option<User>> {
+        let claims = ctx
+            .data_opt::<Claims>()
+            .ok_or_else(|| error(ErrorCode::AuthMissingToken, "Missing Authorization"))?;
+
+        load_user(ctx, claims.address).await
+    }
+
+    /// A registered user by wallet address
+    async fn user(&self, ctx: &Context<'_>, address: String) -> Result<Option<User>> {
+        load_user(ctx, parse_address(&address)?).await
+    }
+
+    /// The registered users among `addresses`, in input order
+    async fn users(&self, ctx: &Context<'_>, addresses: Vec<String>) -> Result<Vec<User>> {
+        if addresses.len() > MAX_ADDRESSES {
+            return Err(error(
+                ErrorCode::InvalidInput,
+                "too many addresses, at most 100 are allowed",
+            ));
+        }
+
+        let addresses = addresses
+            .iter()
+            .map(|address| parse_address(address))
+            .collect::<Result<Vec<_>>>()?;
+
+        let found = ctx
+            .data_unchecked::<DataLoader<UserLoader>>()
+            .load_many(addresses.iter().copied())
+            .await
+            .map_err(|error| internal(&error))?;
+
+        let users = addresses
+            .into_iter()
+            .filter(|address| found.contains_key(address))
+            .map(User::from)
+            .collect();
+
+        Ok(users)
+    }
+}
+
+async fn load_user(ctx: &Context<'_>, address: UnionAddress) -> Result<Option<User>> {
+    let user = ctx
+        .data_unchecked::<DataLoader<UserLoader>>()
+        .load_one(address)
+        .await
+        .map_err(|error| internal(&error))?;
+
+    Ok(user.map(User::from))
+}
+
+fn parse_address(address: &str) -> Result<UnionAddress> {
+    address
+        .parse()
+        .map_err(|_| error(ErrorCode::InvalidInput, "invalid address"))
+}
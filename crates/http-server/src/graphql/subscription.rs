@@ -0,0 +1,68 @@
+use std::future::ready;
+
+use async_graphql::{Context, SimpleObject, Subscription};
+use futures_util::{Stream, StreamExt, stream};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::feed::EventFeed;
+
+/// An event an indexer has just persisted
+#[derive(SimpleObject, Clone)]
+pub struct IndexedEvent {
+    /// Chain id for EVM chains, `solana` for Solana
+    chain: String,
+    /// e.g. `uniswap_v2`, `uniswap_v3` or `pumpfun`
+    protocol: String,
+    /// Transaction hash or signature
+    tx_hash: String,
+    log_ix: i32,
+}
+
+impl From<database::notify::IndexedEvent> for IndexedEvent {
+    fn from(event: database::notify::IndexedEvent) -> Self {
+        Self {
+            chain: event.chain,
+            protocol: event.protocol,
+            tx_hash: event.tx_hash,
+            log_ix: event.log_ix,
+        }
+    }
+}
+
+pub struct Subscription;
+
+#[Subscription]
+impl Subscription {
+    /// Events persisted from now on, optionally narrowed to a chain and protocol
+    async fn indexed_events(
+        &self,
+        ctx: &Context<'_>,
+        chain: Option<String>,
+        protocol: Option<String>,
+    ) -> impl Stream<Item = IndexedEvent> + use<> {
+        let receiver = ctx.data_unchecked::<EventFeed>().subscribe();
+
+        let events = stream::unfold(receiver, |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => return Some((event, receiver)),
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("GraphQL subscriber lagged, skipped {} events", skipped);
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        });
+
+        events
+            .filter(move |event| {
+                ready(
+                    chain.as_ref().is_none_or(|chain| *chain == event.chain)
+                        && protocol
+                            .as_ref()
+                            .is_none_or(|protocol| *protocol == event.protocol),
+                )
+            })
+            .map(IndexedEvent::from)
+    }
+}
@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use api_types::admin::EvmCursor;
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use database::{repos::SettingRepo, repositories::settings::EvmScannedBlock};
+
+use crate::{
+    exception::{ErrorBody, HttpResult},
+    extractors::auth::Admin,
+};
+
+/// Get EVM scanner cursor
+///
+/// Returns the next block the chain's scanner will read.
+#[utoipa::path(
+    get,
+    path = "/admin/cursors/evm/{chain_id}",
+    operation_id = "get_evm_cursor",
+    tag = "admin",
+    security(("BearerAuth" = [])),
+    params(("chain_id" = u64, Path, description = "EVM chain id")),
+    responses(
+        (status = 200, description = "Scanner cursor", body = EvmCursor),
+        (status = 401, description = "Missing, invalid or expired token", body = ErrorBody),
+        (status = 403, description = "Caller is not an admin", body = ErrorBody),
+    )
+)]
+pub async fn handler(
+    State(settings): State<Arc<dyn SettingRepo>>,
+    _: Admin,
+    Path(chain_id): Path<u64>,
+) -> HttpResult<Json<EvmCursor>> {
+    let next_block = settings.get_typed(EvmScannedBlock(chain_id)).await?;
+
+    Ok(Json(EvmCursor {
+        chain_id,
+        next_block,
+    }))
+}
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use api_types::admin::SolanaCursor;
+use axum::{Json, extract::State};
+use database::{
+    repos::SettingRepo,
+    repositories::settings::{SolCurrentScannedSignature, Text},
+};
+
+use crate::{
+    exception::{ErrorBody, HttpResult},
+    extractors::auth::Admin,
+};
+
+/// Get Solana scanner cursor
+///
+/// Returns the last signature the Solana scanner processed.
+#[utoipa::path(
+    get,
+    path = "/admin/cursors/solana",
+    operation_id = "get_solana_cursor",
+    tag = "admin",
+    security(("BearerAuth" = [])),
+    responses(
+        (status = 200, description = "Scanner cursor", body = SolanaCursor),
+        (status = 401, description = "Missing, invalid or expired token", body = ErrorBody),
+        (status = 403, description = "Caller is not an admin", body = ErrorBody),
+    )
+)]
+pub async fn handler(
+    State(settings): State<Arc<dyn SettingRepo>>,
+    _: Admin,
+) -> HttpResult<Json<SolanaCursor>> {
+    let signature = settings
+        .get_typed(SolCurrentScannedSignature)
+        .await?
+        .map(|Text(signature)| signature.to_string());
+
+    Ok(Json(SolanaCursor { signature }))
+}
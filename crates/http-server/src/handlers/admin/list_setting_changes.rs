@@ -0,0 +1,70 @@
+use api_types::admin::{SettingChange, SettingChangesResponse};
+use axum::{Json, extract::State};
+use database::repositories::{self, setting_audits::SettingChangeSort};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use validator::Validate;
+
+use crate::{
+    exception::{ErrorBody, HttpResult},
+    extractors::{
+        auth::Admin,
+        pagination::{Filter, PageParams, Paginated},
+        state::ReadReplica,
+    },
+};
+
+#[derive(Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SettingChangesFilter {
+    /// Only changes of this setting key
+    key: Option<String>,
+}
+
+impl Filter for SettingChangesFilter {
+    type Sort = SettingChangeSort;
+}
+
+/// List setting changes
+///
+/// Returns the audit trail of changes made through the admin API, oldest first
+/// unless `order=desc`. `sort` is `changed_at` (default) or `key`.
+#[utoipa::path(
+    get,
+    path = "/admin/settings/changes",
+    operation_id = "list_setting_changes",
+    tag = "admin",
+    security(("BearerAuth" = [])),
+    params(PageParams, SettingChangesFilter),
+    responses(
+        (status = 200, description = "A page of setting changes", body = SettingChangesResponse),
+        (status = 400, description = "Invalid cursor or page params", body = ErrorBody),
+        (status = 401, description = "Missing, invalid or expired token", body = ErrorBody),
+        (status = 403, description = "Caller is not an admin", body = ErrorBody),
+    )
+)]
+pub async fn handler(
+    State(ReadReplica(db)): State<ReadReplica>,
+    _: Admin,
+    Paginated { page, sort, filter }: Paginated<SettingChangesFilter>,
+) -> HttpResult<Json<SettingChangesResponse>> {
+    let page = repositories::setting_audits::list(&db, filter.key.as_deref(), sort, &page).await?;
+
+    let items = page
+        .items
+        .into_iter()
+        .map(|change| SettingChange {
+            id: change.id,
+            key: change.key,
+            old_value: change.old_value,
+            new_value: change.new_value,
+            changed_by: change.changed_by,
+            changed_at: change.changed_at.to_rfc3339(),
+        })
+        .collect();
+
+    Ok(Json(SettingChangesResponse {
+        items,
+        next_cursor: page.next_cursor,
+    }))
+}
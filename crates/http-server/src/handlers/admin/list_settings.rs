@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use api_types::admin::{SettingEntry, SettingsResponse};
+use axum::{Json, extract::State};
+use database::repos::SettingRepo;
+
+use crate::{
+    exception::{ErrorBody, HttpResult},
+    extractors::auth::Admin,
+};
+
+/// List settings
+///
+/// Returns every stored setting, including keys the scanners no longer use.
+#[utoipa::path(
+    get,
+    path = "/admin/settings",
+    operation_id = "list_settings",
+    tag = "admin",
+    security(("BearerAuth" = [])),
+    responses(
+        (status = 200, description = "All settings", body = SettingsResponse),
+        (status = 401, description = "Missing, invalid or expired token", body = ErrorBody),
+        (status = 403, description = "Caller is not an admin", body = ErrorBody),
+    )
+)]
+pub async fn handler(
+    State(settings): State<Arc<dyn SettingRepo>>,
+    _: Admin,
+) -> HttpResult<Json<SettingsResponse>> {
+    let settings = settings
+        .list()
+        .await?
+        .into_iter()
+        .map(|setting| SettingEntry {
+            key: setting.key,
+            value: setting.value,
+            updated_at: setting.updated_at.to_rfc3339(),
+        })
+        .collect();
+
+    Ok(Json(SettingsResponse { settings }))
+}
@@ -0,0 +1,220 @@
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::extractors::state::AppState;
+
+mod get_evm_cursor;
+mod get_solana_cursor;
+mod list_setting_changes;
+mod list_settings;
+mod set_evm_cursor;
+mod set_solana_cursor;
+
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(list_settings::handler))
+        .routes(routes!(list_setting_changes::handler))
+        .routes(routes!(get_evm_cursor::handler, set_evm_cursor::handler))
+        .routes(routes!(
+            get_solana_cursor::handler,
+            set_solana_cursor::handler
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Once;
+
+    use api_types::admin::{
+        EvmCursor, SetEvmCursorPayload, SettingChangesResponse, SettingsResponse,
+    };
+    use axum::{Json, Router, http::StatusCode, routing::post};
+    use serde_json::{Value, json};
+    use shared::{UnionAddress, env::Env};
+
+    use super::*;
+    use crate::{
+        exception::{ErrorBody, ErrorCode},
+        testing,
+    };
+
+    const ADMIN: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+    const USER: &str = "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359";
+    /// Chain whose RPCs point at [`mock_chain`]
+    const CHAIN_ID: u64 = 56;
+    const CHAIN_HEAD: u64 = 100;
+
+    /// Lists [`ADMIN`] in `ADMIN_ADDRESSES` and points [`CHAIN_ID`]'s RPCs at a node
+    /// whose head is [`CHAIN_HEAD`]
+    fn setup() {
+        static SET: Once = Once::new();
+
+        SET.call_once(|| {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.set_nonblocking(true).unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+
+            // SAFETY: set once, to the same values, before any test reads them
+            unsafe {
+                std::env::set_var(Env::AdminAddresses.key().as_ref(), ADMIN);
+                std::env::set_var(Env::PubEvmRpc(CHAIN_ID).key().as_ref(), &url);
+                std::env::set_var(Env::PriEvmRpc(CHAIN_ID).key().as_ref(), &url);
+            }
+
+            // On a runtime of its own, so the node outlives the test that started it
+            std::thread::spawn(move || {
+                let node = Router::new().route(
+                    "/",
+                    post(async |Json(request): Json<Value>| {
+                        Json(json!({
+                            "jsonrpc": "2.0",
+                            "id": request["id"],
+                            "result": format!("{:#x}", CHAIN_HEAD),
+                        }))
+                    }),
+                );
+
+                tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap()
+                    .block_on(async move {
+                        let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                        axum::serve(listener, node).await.unwrap();
+                    });
+            });
+        });
+    }
+
+    async fn router() -> Router {
+        setup();
+
+        let (router, _) = routes().split_for_parts();
+
+        router.with_state(testing::sqlite_state().await)
+    }
+
+    fn token(address: &str) -> String {
+        testing::token(address.parse::<UnionAddress>().unwrap())
+    }
+
+    async fn set_evm_cursor(
+        router: &Router,
+        chain_id: u64,
+        next_block: u64,
+    ) -> (StatusCode, Value) {
+        testing::send(
+            router,
+            "PUT",
+            &format!("/admin/cursors/evm/{}", chain_id),
+            Some(&token(ADMIN)),
+            Some(SetEvmCursorPayload { next_block }),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn non_admins_are_forbidden() {
+        let router = router().await;
+
+        let (status, body): (_, ErrorBody) = testing::send(
+            &router,
+            "GET",
+            "/admin/settings",
+            Some(&token(USER)),
+            None::<()>,
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body.code, ErrorCode::Forbidden);
+
+        let (status, _): (_, ErrorBody) =
+            testing::send(&router, "GET", "/admin/settings", None, None::<()>).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn admins_are_let_in() {
+        let router = router().await;
+
+        let (status, _): (_, SettingsResponse) = testing::send(
+            &router,
+            "GET",
+            "/admin/settings",
+            Some(&token(ADMIN)),
+            None::<()>,
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn evm_cursor_cannot_pass_the_chain_head() {
+        let router = router().await;
+
+        let (status, body) = set_evm_cursor(&router, CHAIN_ID, CHAIN_HEAD + 2).await;
+        let body: ErrorBody = serde_json::from_value(body).unwrap();
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body.code, ErrorCode::BadRequest);
+
+        let (status, body) = set_evm_cursor(&router, CHAIN_ID, CHAIN_HEAD + 1).await;
+        let cursor: EvmCursor = serde_json::from_value(body).unwrap();
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(cursor.next_block, Some(CHAIN_HEAD + 1));
+    }
+
+    #[tokio::test]
+    async fn unsupported_chain_is_rejected() {
+        let router = router().await;
+
+        let (status, _) = set_evm_cursor(&router, 999_999, 1).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn cursor_changes_are_audited() {
+        let router = router().await;
+
+        set_evm_cursor(&router, CHAIN_ID, 10).await;
+        set_evm_cursor(&router, CHAIN_ID, 20).await;
+
+        let (status, changes): (_, SettingChangesResponse) = testing::send(
+            &router,
+            "GET",
+            "/admin/settings/changes",
+            Some(&token(ADMIN)),
+            None::<()>,
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+
+        let changes: Vec<_> = changes
+            .items
+            .iter()
+            .map(|change| {
+                (
+                    change.key.as_str(),
+                    change.old_value.as_deref(),
+                    change.new_value.as_str(),
+                    change.changed_by.as_str(),
+                )
+            })
+            .collect();
+        let admin = ADMIN.parse::<UnionAddress>().unwrap().to_string();
+        let key = format!("evm_scanned_block_chain_{}", CHAIN_ID);
+
+        assert_eq!(
+            changes,
+            [
+                (key.as_str(), None, "10", admin.as_str()),
+                (key.as_str(), Some("10"), "20", admin.as_str()),
+            ]
+        );
+    }
+}
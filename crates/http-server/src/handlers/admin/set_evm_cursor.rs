@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use alloy::providers::Provider;
+use api_types::admin::{EvmCursor, SetEvmCursorPayload};
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use database::{repos::SettingRepo, repositories::settings::EvmScannedBlock};
+use evm_lib::{SupportedChain, client::try_create_public_client};
+use shared::result::Rs;
+
+use crate::{
+    exception::{ErrorBody, HttpException, HttpResult},
+    extractors::auth::Admin,
+};
+
+/// Set EVM scanner cursor
+///
+/// Moves the chain's scanner to `next_block`, forwards or backwards. The running
+/// scanner picks the change up before its next scan.
+#[utoipa::path(
+    put,
+    path = "/admin/cursors/evm/{chain_id}",
+    operation_id = "set_evm_cursor",
+    tag = "admin",
+    security(("BearerAuth" = [])),
+    params(("chain_id" = u64, Path, description = "EVM chain id")),
+    request_body = SetEvmCursorPayload,
+    responses(
+        (status = 200, description = "Cursor updated", body = EvmCursor),
+        (status = 400, description = "Unsupported chain or block past the chain head", body = ErrorBody),
+        (status = 401, description = "Missing, invalid or expired token", body = ErrorBody),
+        (status = 403, description = "Caller is not an admin", body = ErrorBody),
+    )
+)]
+pub async fn handler(
+    State(settings): State<Arc<dyn SettingRepo>>,
+    Admin(claims): Admin,
+    Path(chain_id): Path<u64>,
+    Json(SetEvmCursorPayload { next_block }): Json<SetEvmCursorPayload>,
+) -> HttpResult<Json<EvmCursor>> {
+    let chain = SupportedChain::try_from(chain_id)
+        .map_err(|_| HttpException::bad_request(format!("chain {} is not supported", chain_id)))?;
+
+    let head = chain_head(chain).await?;
+
+    if next_block > head + 1 {
+        return Err(HttpException::bad_request(format!(
+            "block {} is past the chain head {}",
+            next_block, head
+        )));
+    }
+
+    let previous = settings
+        .change_typed(EvmScannedBlock(chain_id), &next_block, claims.address)
+        .await?;
+
+    tracing::info!(
+        chain_id,
+        ?previous,
+        next_block,
+        admin = %claims.address,
+        "EVM scanner cursor changed"
+    );
+
+    Ok(Json(EvmCursor {
+        chain_id,
+        next_block: Some(next_block),
+    }))
+}
+
+async fn chain_head(chain: SupportedChain) -> Rs<u64> {
+    let client = try_create_public_client(chain)?;
+
+    Ok(client.get_block_number().await?)
+}
@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use api_types::admin::{SetSolanaCursorPayload, SolanaCursor};
+use axum::{Json, extract::State};
+use database::{
+    repos::SettingRepo,
+    repositories::settings::{SolCurrentScannedSignature, Text},
+};
+use shared::{env::Env, result::Rs};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::signature::Signature;
+
+use crate::{
+    exception::{ErrorBody, HttpException, HttpResult},
+    extractors::{auth::Admin, validator::ValidatedPayload},
+};
+
+/// Set Solana scanner cursor
+///
+/// Moves the Solana scanner so it resumes after `signature`. The running scanner
+/// picks the change up before its next scan.
+#[utoipa::path(
+    put,
+    path = "/admin/cursors/solana",
+    operation_id = "set_solana_cursor",
+    tag = "admin",
+    security(("BearerAuth" = [])),
+    request_body = SetSolanaCursorPayload,
+    responses(
+        (status = 200, description = "Cursor updated", body = SolanaCursor),
+        (status = 400, description = "Malformed or unknown signature", body = ErrorBody),
+        (status = 401, description = "Missing, invalid or expired token", body = ErrorBody),
+        (status = 403, description = "Caller is not an admin", body = ErrorBody),
+    )
+)]
+pub async fn handler(
+    State(settings): State<Arc<dyn SettingRepo>>,
+    Admin(claims): Admin,
+    ValidatedPayload(SetSolanaCursorPayload { signature }): ValidatedPayload<
+        SetSolanaCursorPayload,
+    >,
+) -> HttpResult<Json<SolanaCursor>> {
+    let parsed = signature.parse::<Signature>()?;
+
+    let is_known = is_known_signature(parsed).await?;
+
+    if !is_known {
+        return Err(HttpException::bad_request(format!(
+            "signature {} was not found on chain",
+            signature
+        )));
+    }
+
+    let previous = settings
+        .change_typed(SolCurrentScannedSignature, &Text(parsed), claims.address)
+        .await?;
+
+    tracing::info!(
+        ?previous,
+        %signature,
+        admin = %claims.address,
+        "Solana scanner cursor changed"
+    );
+
+    Ok(Json(SolanaCursor {
+        signature: Some(signature),
+    }))
+}
+
+async fn is_known_signature(signature: Signature) -> Rs<bool> {
+    let client = RpcClient::new(shared::env::read(Env::SolanaRpc)?);
+
+    let is_known = client
+        .get_signature_statuses_with_history(&[signature])
+        .await?
+        .value
+        .into_iter()
+        .flatten()
+        .next()
+        .is_some();
+
+    Ok(is_known)
+}
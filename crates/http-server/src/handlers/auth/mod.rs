@@ -1,4 +1,4 @@
-use axum::{Router, routing};
+use utoipa_axum::{router::OpenApiRouter, routes};
 
 use crate::extractors::state::AppState;
 
@@ -6,9 +6,270 @@ mod req_signing_msg;
 mod sign_in_evm;
 mod sign_in_sol;
 
-pub fn routes() -> Router<AppState> {
-    Router::new()
-        .route("/auth/signing-msg", routing::post(req_signing_msg::handler))
-        .route("/auth/sign-in-sol", routing::post(sign_in_sol::handler))
-        .route("/auth/sign-in-evm", routing::post(sign_in_evm::handler))
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(req_signing_msg::handler))
+        .routes(routes!(sign_in_sol::handler))
+        .routes(routes!(sign_in_evm::handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::signers::{Signer, local::PrivateKeySigner};
+    use api_types::auth::{
+        SignInEvmPayload, SignInResponse, SignInSolPayload, SigningMsgPayload, SigningMsgResponse,
+    };
+    use axum::{Router, http::StatusCode};
+    use serde_json::Value;
+    use solana_sdk::signature::{Keypair, Signer as _};
+
+    use super::*;
+    use crate::{
+        exception::{ErrorBody, ErrorCode},
+        extractors::auth::{Claims, decode_token},
+        testing,
+    };
+
+    fn router(state: &AppState) -> Router {
+        let (router, _) = routes().split_for_parts();
+
+        router.with_state(state.clone())
+    }
+
+    async fn request_msg(router: &Router, address: &str) -> String {
+        let (status, SigningMsgResponse { msg }) = testing::send(
+            router,
+            "POST",
+            "/auth/signing-msg",
+            None,
+            Some(SigningMsgPayload {
+                address: address.to_owned(),
+            }),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+
+        msg
+    }
+
+    async fn sign_in_evm(router: &Router, payload: SignInEvmPayload) -> (StatusCode, Value) {
+        testing::send(router, "POST", "/auth/sign-in-evm", None, Some(payload)).await
+    }
+
+    async fn sign_in_sol(router: &Router, payload: SignInSolPayload) -> (StatusCode, Value) {
+        testing::send(router, "POST", "/auth/sign-in-sol", None, Some(payload)).await
+    }
+
+    fn code(body: Value) -> ErrorCode {
+        serde_json::from_value::<ErrorBody>(body).unwrap().code
+    }
+
+    #[tokio::test]
+    async fn signing_message_is_stored_for_the_address() {
+        let state = AppState::memory();
+        let router = router(&state);
+        let signer = PrivateKeySigner::random();
+        let address = signer.address().to_string();
+
+        let msg = request_msg(&router, &address).await;
+        assert_eq!(msg, format!("Welcome {}", address));
+
+        let stored = state.signing_messages.get(signer.address().into()).await;
+        assert_eq!(stored.unwrap(), Some(msg));
+    }
+
+    #[tokio::test]
+    async fn evm_sign_in_returns_a_token_and_registers_the_user() {
+        testing::set_access_token_key();
+
+        let state = AppState::memory();
+        let router = router(&state);
+        let signer = PrivateKeySigner::random();
+        let address = signer.address().to_string();
+
+        let msg = request_msg(&router, &address).await;
+        let signature = signer.sign_message(msg.as_bytes()).await.unwrap();
+
+        let (status, body) = sign_in_evm(
+            &router,
+            SignInEvmPayload {
+                address,
+                message: msg,
+                signature: signature.to_string(),
+            },
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+
+        let SignInResponse { token } = serde_json::from_value(body).unwrap();
+        let claims = decode_token::<Claims>(&token, "test").unwrap();
+        assert_eq!(claims.address, signer.address().into());
+
+        let user = state
+            .users
+            .find_by_wallet_address(signer.address().into())
+            .await;
+        assert!(user.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn evm_sign_in_rejects_revoked_and_wrong_messages() {
+        let state = AppState::memory();
+        let router = router(&state);
+        let signer = PrivateKeySigner::random();
+        let address = signer.address().to_string();
+
+        let msg = request_msg(&router, &address).await;
+        let wrong = format!("{} again", msg);
+        let signature = signer.sign_message(wrong.as_bytes()).await.unwrap();
+
+        let (status, body) = sign_in_evm(
+            &router,
+            SignInEvmPayload {
+                address: address.clone(),
+                message: wrong.clone(),
+                signature: signature.to_string(),
+            },
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(code(body), ErrorCode::AuthInvalidMessage);
+
+        state
+            .signing_messages
+            .revoke(signer.address().into())
+            .await
+            .unwrap();
+        let signature = signer.sign_message(msg.as_bytes()).await.unwrap();
+
+        let (status, body) = sign_in_evm(
+            &router,
+            SignInEvmPayload {
+                address,
+                message: msg,
+                signature: signature.to_string(),
+            },
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(code(body), ErrorCode::AuthNonceExpired);
+    }
+
+    #[tokio::test]
+    async fn evm_sign_in_rejects_another_signer() {
+        let state = AppState::memory();
+        let router = router(&state);
+        let signer = PrivateKeySigner::random();
+        let address = signer.address().to_string();
+
+        let msg = request_msg(&router, &address).await;
+        let signature = PrivateKeySigner::random()
+            .sign_message(msg.as_bytes())
+            .await
+            .unwrap();
+
+        let (status, body) = sign_in_evm(
+            &router,
+            SignInEvmPayload {
+                address,
+                message: msg,
+                signature: signature.to_string(),
+            },
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(code(body), ErrorCode::AuthInvalidSignature);
+    }
+
+    #[tokio::test]
+    async fn sol_sign_in_returns_a_token() {
+        testing::set_access_token_key();
+
+        let state = AppState::memory();
+        let router = router(&state);
+        let keypair = Keypair::new();
+        let address = keypair.pubkey().to_string();
+
+        let msg = request_msg(&router, &address).await;
+        let signature = keypair.sign_message(msg.as_bytes());
+
+        let (status, body) = sign_in_sol(
+            &router,
+            SignInSolPayload {
+                address,
+                message: msg,
+                signature: signature.to_string(),
+            },
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+
+        let SignInResponse { token } = serde_json::from_value(body).unwrap();
+        let claims = decode_token::<Claims>(&token, "test").unwrap();
+        assert_eq!(claims.address, keypair.pubkey().into());
+    }
+
+    #[tokio::test]
+    async fn sol_sign_in_rejects_revoked_wrong_and_mismatched_signatures() {
+        let state = AppState::memory();
+        let router = router(&state);
+        let keypair = Keypair::new();
+        let address = keypair.pubkey().to_string();
+
+        let msg = request_msg(&router, &address).await;
+        let wrong = format!("{} again", msg);
+
+        let attempts = [
+            (
+                wrong.clone(),
+                keypair.sign_message(wrong.as_bytes()),
+                ErrorCode::AuthInvalidMessage,
+            ),
+            (
+                msg.clone(),
+                Keypair::new().sign_message(msg.as_bytes()),
+                ErrorCode::AuthInvalidSignature,
+            ),
+        ];
+
+        for (message, signature, error) in attempts {
+            let (status, body) = sign_in_sol(
+                &router,
+                SignInSolPayload {
+                    address: address.clone(),
+                    message,
+                    signature: signature.to_string(),
+                },
+            )
+            .await;
+
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+            assert_eq!(code(body), error);
+        }
+
+        state
+            .signing_messages
+            .revoke(keypair.pubkey().into())
+            .await
+            .unwrap();
+
+        let (status, body) = sign_in_sol(
+            &router,
+            SignInSolPayload {
+                address,
+                signature: keypair.sign_message(msg.as_bytes()).to_string(),
+                message: msg,
+            },
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(code(body), ErrorCode::AuthNonceExpired);
+    }
 }
@@ -1,31 +1,39 @@
+use std::sync::Arc;
+
+use api_types::auth::{SigningMsgPayload, SigningMsgResponse};
 use axum::{Json, extract::State};
-use database::{repositories, sea_orm::DatabaseConnection};
-use serde::{Deserialize, Serialize};
+use database::repos::SigningMessageRepo;
 use shared::UnionAddress;
-use validator::Validate;
-
-use crate::{exception::HttpResult, extractors::validator::ValidatedPayload};
 
-#[derive(Deserialize, Validate)]
-pub struct Payload {
-    #[validate(custom(function = "shared::validators::validate_union_address"))]
-    address: String,
-}
-
-#[derive(Serialize)]
-pub struct Response {
-    msg: String,
-}
+use crate::{
+    exception::{ErrorBody, HttpResult},
+    extractors::validator::ValidatedPayload,
+};
 
+/// Request signing message
+///
+/// Generates a signing message for the given wallet address (EVM or Solana).
+/// The message must be signed and submitted to the sign-in endpoint.
+#[utoipa::path(
+    post,
+    path = "/auth/signing-msg",
+    operation_id = "request_signing_msg",
+    tag = "auth",
+    request_body = SigningMsgPayload,
+    responses(
+        (status = 200, description = "Signing message generated", body = SigningMsgResponse),
+        (status = 400, description = "Invalid address", body = ErrorBody),
+    )
+)]
 pub async fn handler(
-    State(db): State<DatabaseConnection>,
-    ValidatedPayload(Payload { address }): ValidatedPayload<Payload>,
-) -> HttpResult<Json<Response>> {
+    State(signing_messages): State<Arc<dyn SigningMessageRepo>>,
+    ValidatedPayload(SigningMsgPayload { address }): ValidatedPayload<SigningMsgPayload>,
+) -> HttpResult<Json<SigningMsgResponse>> {
     let address = address.parse::<UnionAddress>()?;
     let msg = format!("Welcome {}", address);
-    repositories::signing_messages::allocate(&db, address, msg.clone()).await?;
+    signing_messages.allocate(address, msg.clone()).await?;
 
-    let response = Response { msg };
+    let response = SigningMsgResponse { msg };
 
     Ok(Json(response))
 }
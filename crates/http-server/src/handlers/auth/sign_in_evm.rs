@@ -1,61 +1,73 @@
+use std::sync::Arc;
+
 use alloy::{primitives::Address, signers::Signature};
+use api_types::auth::{SignInEvmPayload, SignInResponse};
 use axum::{Json, extract::State};
-use database::{repositories, sea_orm::DatabaseConnection};
-use serde::{Deserialize, Serialize};
-use validator::Validate;
+use database::repos::{SigningMessageRepo, UserRepo};
 
 use crate::{
     common,
-    exception::{HttpException, HttpResult},
+    exception::{ErrorBody, ErrorCode, HttpException, HttpResult},
     extractors::validator::ValidatedPayload,
 };
 
-#[derive(Deserialize, Validate)]
-pub struct Payload {
-    #[validate(custom(function = "shared::validators::validate_evm_address"))]
-    address: String,
-    message: String,
-    #[validate(custom(function = "shared::validators::validate_evm_signature"))]
-    signature: String,
-}
-
-#[derive(Serialize)]
-pub struct Response {
-    token: String,
-}
-
+/// Sign in with EVM wallet
+///
+/// Verifies an EVM signature against the previously requested signing message.
+/// Returns a JWT token on success.
+#[utoipa::path(
+    post,
+    path = "/auth/sign-in-evm",
+    operation_id = "sign_in_evm",
+    tag = "auth",
+    request_body = SignInEvmPayload,
+    responses(
+        (status = 200, description = "Successfully authenticated, JWT token returned", body = SignInResponse),
+        (status = 400, description = "Malformed address or signature", body = ErrorBody),
+        (status = 401, description = "Message revoked or signature mismatch", body = ErrorBody),
+    )
+)]
 pub async fn handler(
-    State(db): State<DatabaseConnection>,
-    ValidatedPayload(Payload {
+    State(signing_messages): State<Arc<dyn SigningMessageRepo>>,
+    State(users): State<Arc<dyn UserRepo>>,
+    ValidatedPayload(SignInEvmPayload {
         address,
         message,
         signature,
-    }): ValidatedPayload<Payload>,
-) -> HttpResult<Json<Response>> {
+    }): ValidatedPayload<SignInEvmPayload>,
+) -> HttpResult<Json<SignInResponse>> {
     let address = address.parse::<Address>()?;
     let signature = signature.parse::<Signature>()?;
 
-    let Some(msg) = repositories::signing_messages::get(&db, address).await? else {
-        return Err(HttpException::unauthorized("msg was revoked"));
+    let Some(msg) = signing_messages.get(address.into()).await? else {
+        return Err(
+            HttpException::unauthorized("msg was revoked").with_code(ErrorCode::AuthNonceExpired)
+        );
     };
 
     if msg != message {
-        return Err(HttpException::unauthorized("invalid message"));
+        return Err(
+            HttpException::unauthorized("invalid message").with_code(ErrorCode::AuthInvalidMessage)
+        );
     }
 
     let recovered_address = signature
         .recover_address_from_msg(message.as_bytes())
-        .map_err(|error| HttpException::unauthorized(error.to_string()))?;
+        .map_err(|error| {
+            HttpException::unauthorized(error.to_string())
+                .with_code(ErrorCode::AuthInvalidSignature)
+        })?;
 
     if recovered_address != address {
-        return Err(HttpException::unauthorized("mismatch signature address"));
+        return Err(HttpException::unauthorized("mismatch signature address")
+            .with_code(ErrorCode::AuthInvalidSignature));
     }
 
-    repositories::users::save(&db, address).await?;
+    users.save(address.into()).await?;
 
     let token = common::jwt::sign(address)?;
 
-    let response = Response { token };
+    let response = SignInResponse { token };
 
     Ok(Json(response))
 }
@@ -1,57 +1,65 @@
+use std::sync::Arc;
+
+use api_types::auth::{SignInResponse, SignInSolPayload};
 use axum::{Json, extract::State};
-use database::{repositories, sea_orm::DatabaseConnection};
-use serde::{Deserialize, Serialize};
+use database::repos::SigningMessageRepo;
 use solana_sdk::{pubkey::Pubkey, signature::Signature};
-use validator::Validate;
 
 use crate::{
     common,
-    exception::{HttpException, HttpResult},
+    exception::{ErrorBody, ErrorCode, HttpException, HttpResult},
     extractors::validator::ValidatedPayload,
 };
 
-#[derive(Deserialize, Validate)]
-pub struct Payload {
-    #[validate(custom(function = "shared::validators::validate_solana_pubkey"))]
-    address: String,
-    message: String,
-    #[validate(custom(function = "shared::validators::validate_solana_signature"))]
-    signature: String,
-}
-
-#[derive(Serialize)]
-pub struct Response {
-    token: String,
-}
-
+/// Sign in with Solana wallet
+///
+/// Verifies a Solana signature against the previously requested signing message.
+/// Returns a JWT token on success.
+#[utoipa::path(
+    post,
+    path = "/auth/sign-in-sol",
+    operation_id = "sign_in_sol",
+    tag = "auth",
+    request_body = SignInSolPayload,
+    responses(
+        (status = 200, description = "Successfully authenticated, JWT token returned", body = SignInResponse),
+        (status = 400, description = "Malformed address or signature", body = ErrorBody),
+        (status = 401, description = "Message revoked or signature mismatch", body = ErrorBody),
+    )
+)]
 pub async fn handler(
-    State(db): State<DatabaseConnection>,
-    ValidatedPayload(Payload {
+    State(signing_messages): State<Arc<dyn SigningMessageRepo>>,
+    ValidatedPayload(SignInSolPayload {
         address,
         message,
         signature,
-    }): ValidatedPayload<Payload>,
-) -> HttpResult<Json<Response>> {
+    }): ValidatedPayload<SignInSolPayload>,
+) -> HttpResult<Json<SignInResponse>> {
     let address = address.parse::<Pubkey>()?;
     let signature = signature.parse::<Signature>()?;
 
-    let Some(msg) = repositories::signing_messages::get(&db, address).await? else {
-        return Err(HttpException::unauthorized("msg was revoked"));
+    let Some(msg) = signing_messages.get(address.into()).await? else {
+        return Err(
+            HttpException::unauthorized("msg was revoked").with_code(ErrorCode::AuthNonceExpired)
+        );
     };
 
     if msg != message {
-        return Err(HttpException::unauthorized("invalid message"));
+        return Err(
+            HttpException::unauthorized("invalid message").with_code(ErrorCode::AuthInvalidMessage)
+        );
     }
 
     let is_valid_sig = signature.verify(address.as_array(), message.as_bytes());
 
     if !is_valid_sig {
-        return Err(HttpException::unauthorized("invalid signature"));
+        return Err(HttpException::unauthorized("invalid signature")
+            .with_code(ErrorCode::AuthInvalidSignature));
     }
 
     let token = common::jwt::sign(address)?;
 
-    let response = Response { token };
+    let response = SignInResponse { token };
 
     Ok(Json(response))
 }
@@ -0,0 +1,136 @@
+use std::sync::Arc;
+
+use alloy::providers::Provider;
+use api_types::health::{Indexer, IndexersResponse};
+use axum::{Json, extract::State};
+use database::{repos::SettingRepo, repositories::settings::Setting};
+use evm_lib::{SupportedChain, client::try_create_public_client};
+use futures_util::future::join_all;
+use shared::{
+    env::Env,
+    result::{AppErr, Rs},
+};
+use sol_lib::pumpfun;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient, rpc_client::GetConfirmedSignaturesForAddress2Config,
+};
+use solana_sdk::signature::Signature;
+
+use crate::exception::{ErrorBody, HttpResult};
+
+/// Blocks an EVM scanner may trail the chain head before being flagged
+const MAX_EVM_LAG: u64 = 100;
+/// Slots the Solana scanner may trail the program's latest transaction before being flagged
+const MAX_SOLANA_LAG: u64 = 300;
+
+/// Indexer lag
+///
+/// Reports each scanner's persisted cursor against the chain head.
+/// EVM lag is measured in blocks, Solana lag in slots.
+#[utoipa::path(
+    get,
+    path = "/health/indexers",
+    operation_id = "indexer_lag",
+    tag = "health",
+    responses(
+        (status = 200, description = "Indexer progress", body = IndexersResponse),
+        (status = 500, description = "Settings could not be read", body = ErrorBody),
+    )
+)]
+pub async fn handler(
+    State(settings): State<Arc<dyn SettingRepo>>,
+) -> HttpResult<Json<IndexersResponse>> {
+    let settings = settings.all().await?;
+
+    let tasks = settings
+        .into_iter()
+        .map(|(setting, cursor)| inspect(setting, cursor));
+
+    let indexers = join_all(tasks).await;
+
+    Ok(Json(IndexersResponse { indexers }))
+}
+
+async fn inspect(setting: Setting, cursor: String) -> Indexer {
+    let (name, max_lag, progress) = match setting {
+        Setting::EvmScannedBlock(chain_id) => (
+            format!("evm_scanner_chain_{}", chain_id),
+            MAX_EVM_LAG,
+            evm_progress(chain_id, &cursor).await,
+        ),
+        Setting::SolCurrentScannedSignature => (
+            "solana_scanner".to_string(),
+            MAX_SOLANA_LAG,
+            solana_progress(&cursor).await,
+        ),
+    };
+
+    match progress {
+        Ok((head, position)) => {
+            let lag = head.saturating_sub(position);
+
+            Indexer {
+                name,
+                cursor,
+                head: Some(head),
+                lag: Some(lag),
+                lagging: lag > max_lag,
+                error: None,
+            }
+        }
+        Err(error) => {
+            error.trace(format!("Failed to read the head of {}", name));
+
+            Indexer {
+                name,
+                cursor,
+                head: None,
+                lag: None,
+                lagging: true,
+                error: Some("chain head is unavailable".to_owned()),
+            }
+        }
+    }
+}
+
+/// Returns the chain head and the next block the scanner will read
+async fn evm_progress(chain_id: u64, cursor: &str) -> Rs<(u64, u64)> {
+    let chain = SupportedChain::try_from(chain_id)?;
+    let client = try_create_public_client(chain)?;
+
+    let position = cursor.parse()?;
+    let head = client.get_block_number().await?;
+
+    Ok((head, position))
+}
+
+/// Returns the slot of the program's latest transaction and the slot of the scanner's cursor
+async fn solana_progress(cursor: &str) -> Rs<(u64, u64)> {
+    let client = RpcClient::new(shared::env::read(Env::SolanaRpc)?);
+    let cursor = cursor.parse::<Signature>()?;
+
+    let head = client
+        .get_signatures_for_address_with_config(
+            &pumpfun::ID,
+            GetConfirmedSignaturesForAddress2Config {
+                limit: Some(1),
+                ..Default::default()
+            },
+        )
+        .await?
+        .first()
+        .map(|tx| tx.slot)
+        .ok_or_else(|| AppErr::custom("program has no transactions"))?;
+
+    let position = client
+        .get_signature_statuses_with_history(&[cursor])
+        .await?
+        .value
+        .into_iter()
+        .flatten()
+        .next()
+        .map(|status| status.slot)
+        .ok_or_else(|| AppErr::custom(format!("cursor signature {} not found", cursor)))?;
+
+    Ok((head, position))
+}
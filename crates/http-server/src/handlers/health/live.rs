@@ -0,0 +1,18 @@
+use api_types::health::Liveness;
+use axum::Json;
+
+/// Liveness probe
+///
+/// Returns 200 as long as the process is able to serve requests.
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    operation_id = "liveness",
+    tag = "health",
+    responses((status = 200, description = "Process is alive", body = Liveness))
+)]
+pub async fn handler() -> Json<Liveness> {
+    Json(Liveness {
+        status: "ok".to_owned(),
+    })
+}
@@ -0,0 +1,49 @@
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::extractors::state::AppState;
+
+mod indexers;
+mod live;
+mod ready;
+
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(live::handler))
+        .routes(routes!(ready::handler))
+        .routes(routes!(indexers::handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use api_types::health::Readiness;
+    use axum::http::StatusCode;
+
+    use super::*;
+    use crate::testing;
+
+    #[tokio::test]
+    async fn readiness_hides_the_database_error() {
+        let (router, _) = routes().split_for_parts();
+        let router = router.with_state(AppState::memory());
+
+        let (status, readiness): (_, Readiness) =
+            testing::send(&router, "GET", "/health/ready", None, None::<()>).await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(readiness.status, "unavailable");
+        assert_eq!(readiness.error.as_deref(), Some("database is unreachable"));
+    }
+
+    #[tokio::test]
+    async fn readiness_pings_the_database() {
+        let (router, _) = routes().split_for_parts();
+        let router = router.with_state(testing::sqlite_state().await);
+
+        let (status, readiness): (_, Readiness) =
+            testing::send(&router, "GET", "/health/ready", None, None::<()>).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(readiness.status, "ready");
+        assert!(readiness.error.is_none());
+    }
+}
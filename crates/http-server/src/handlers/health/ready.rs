@@ -0,0 +1,39 @@
+use api_types::health::Readiness;
+use axum::{Json, extract::State, http::StatusCode};
+use database::sea_orm::DatabaseConnection;
+
+/// Readiness probe
+///
+/// Pings the database. Returns 503 when the connection is unusable.
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    operation_id = "readiness",
+    tag = "health",
+    responses(
+        (status = 200, description = "Ready to serve traffic", body = Readiness),
+        (status = 503, description = "Database is unreachable", body = Readiness),
+    )
+)]
+pub async fn handler(State(db): State<DatabaseConnection>) -> (StatusCode, Json<Readiness>) {
+    match db.ping().await {
+        Ok(()) => (
+            StatusCode::OK,
+            Json(Readiness {
+                status: "ready".to_owned(),
+                error: None,
+            }),
+        ),
+        Err(error) => {
+            tracing::warn!("readiness check failed: {}", error);
+
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(Readiness {
+                    status: "unavailable".to_owned(),
+                    error: Some("database is unreachable".to_owned()),
+                }),
+            )
+        }
+    }
+}
@@ -1,3 +1,6 @@
+pub mod admin;
 pub mod auth;
+pub mod health;
 pub mod users;
+pub mod webhooks;
 pub mod ws;
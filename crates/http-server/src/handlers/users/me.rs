@@ -1,26 +1,39 @@
+use std::sync::Arc;
+
+use api_types::users::MeResponse;
 use axum::{Json, extract::State};
-use database::{repositories, sea_orm::DatabaseConnection};
-use serde::Serialize;
+use database::repos::UserRepo;
 
 use crate::{
-    exception::{HttpException, HttpResult},
+    exception::{ErrorBody, HttpException, HttpResult},
     extractors::auth::Auth,
 };
 
-#[derive(Serialize)]
-pub struct Response {
-    address: String,
-}
-
+/// Get current user
+///
+/// Returns the authenticated user's wallet address.
+#[utoipa::path(
+    get,
+    path = "/users/me",
+    operation_id = "get_me",
+    tag = "users",
+    security(("BearerAuth" = [])),
+    responses(
+        (status = 200, description = "User info returned", body = MeResponse),
+        (status = 401, description = "Missing, invalid or expired token", body = ErrorBody),
+        (status = 404, description = "Token belongs to an unknown wallet", body = ErrorBody),
+    )
+)]
 pub async fn handler(
-    State(db): State<DatabaseConnection>,
+    State(users): State<Arc<dyn UserRepo>>,
     Auth(claims): Auth,
-) -> HttpResult<Json<Response>> {
-    let _ = repositories::users::find_by_wallet_address(&db, claims.address)
+) -> HttpResult<Json<MeResponse>> {
+    let _ = users
+        .find_by_wallet_address(claims.address)
         .await?
-        .ok_or_else(|| HttpException::internal("user not found"))?;
+        .ok_or_else(|| HttpException::not_found("user not found"))?;
 
-    let response = Response {
+    let response = MeResponse {
         address: claims.address.to_string(),
     };
 
@@ -1,9 +1,9 @@
-use axum::{Router, routing};
+use utoipa_axum::{router::OpenApiRouter, routes};
 
 use crate::extractors::state::AppState;
 
 mod me;
 
-pub fn routes() -> Router<AppState> {
-    Router::new().route("/users/me", routing::get(me::handler))
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new().routes(routes!(me::handler))
 }
@@ -0,0 +1,69 @@
+use api_types::webhooks::{CreatedWebhook, WebhookPayload};
+use axum::{Json, extract::State, http::StatusCode};
+use database::{
+    repositories::{self, webhooks::WebhookInput},
+    sea_orm::DatabaseConnection,
+};
+
+use crate::{
+    exception::{ErrorBody, HttpException, HttpResult},
+    extractors::{auth::Auth, validator::ValidatedPayload},
+    webhooks::signature,
+};
+
+const MAX_WEBHOOKS_PER_OWNER: u64 = 10;
+
+/// Register webhook
+///
+/// Indexed events matching the optional `chain` and `protocol` filters are `POST`ed
+/// to `url` as a `WebhookEvent`. The returned `secret` signs every delivery and is
+/// not shown again.
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    operation_id = "create_webhook",
+    tag = "webhooks",
+    security(("BearerAuth" = [])),
+    request_body = WebhookPayload,
+    responses(
+        (status = 201, description = "Webhook registered", body = CreatedWebhook),
+        (status = 400, description = "Invalid url or filters", body = ErrorBody),
+        (status = 401, description = "Missing, invalid or expired token", body = ErrorBody),
+        (status = 409, description = "Too many webhooks registered", body = ErrorBody),
+    )
+)]
+pub async fn handler(
+    State(db): State<DatabaseConnection>,
+    Auth(claims): Auth,
+    ValidatedPayload(payload): ValidatedPayload<WebhookPayload>,
+) -> HttpResult<(StatusCode, Json<CreatedWebhook>)> {
+    let count = repositories::webhooks::count_by_owner(&db, claims.address).await?;
+
+    if count >= MAX_WEBHOOKS_PER_OWNER {
+        return Err(HttpException::conflict(format!(
+            "at most {} webhooks can be registered",
+            MAX_WEBHOOKS_PER_OWNER
+        )));
+    }
+
+    let secret = signature::generate_secret();
+
+    let webhook = repositories::webhooks::create(
+        &db,
+        claims.address,
+        secret.clone(),
+        WebhookInput {
+            url: payload.url,
+            chain: payload.chain,
+            protocol: payload.protocol,
+        },
+    )
+    .await?;
+
+    let response = CreatedWebhook {
+        webhook: super::to_response(webhook),
+        secret,
+    };
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
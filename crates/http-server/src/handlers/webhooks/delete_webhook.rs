@@ -0,0 +1,38 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use database::{repositories, sea_orm::DatabaseConnection};
+
+use crate::{
+    exception::{ErrorBody, HttpException, HttpResult},
+    extractors::auth::Auth,
+};
+
+/// Delete webhook
+///
+/// Pending deliveries are dropped along with the delivery log.
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{id}",
+    operation_id = "delete_webhook",
+    tag = "webhooks",
+    security(("BearerAuth" = [])),
+    params(("id" = i32, Path, description = "Webhook id")),
+    responses(
+        (status = 204, description = "Webhook deleted"),
+        (status = 401, description = "Missing, invalid or expired token", body = ErrorBody),
+        (status = 404, description = "No such webhook owned by the caller", body = ErrorBody),
+    )
+)]
+pub async fn handler(
+    State(db): State<DatabaseConnection>,
+    Auth(claims): Auth,
+    Path(id): Path<i32>,
+) -> HttpResult<StatusCode> {
+    if !repositories::webhooks::delete(&db, id, claims.address).await? {
+        return Err(HttpException::not_found("webhook not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,37 @@
+use api_types::webhooks::Webhook;
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use database::{repositories, sea_orm::DatabaseConnection};
+
+use crate::{
+    exception::{ErrorBody, HttpException, HttpResult},
+    extractors::auth::Auth,
+};
+
+/// Get webhook
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}",
+    operation_id = "get_webhook",
+    tag = "webhooks",
+    security(("BearerAuth" = [])),
+    params(("id" = i32, Path, description = "Webhook id")),
+    responses(
+        (status = 200, description = "The webhook", body = Webhook),
+        (status = 401, description = "Missing, invalid or expired token", body = ErrorBody),
+        (status = 404, description = "No such webhook owned by the caller", body = ErrorBody),
+    )
+)]
+pub async fn handler(
+    State(db): State<DatabaseConnection>,
+    Auth(claims): Auth,
+    Path(id): Path<i32>,
+) -> HttpResult<Json<Webhook>> {
+    let webhook = repositories::webhooks::find_by_owner(&db, id, claims.address)
+        .await?
+        .ok_or_else(|| HttpException::not_found("webhook not found"))?;
+
+    Ok(Json(super::to_response(webhook)))
+}
@@ -0,0 +1,87 @@
+use api_types::webhooks::{WebhookDeliveriesResponse, WebhookDelivery};
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use database::repositories::{
+    self,
+    webhook_deliveries::{DeliverySort, DeliveryStatus},
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use validator::Validate;
+
+use crate::{
+    exception::{ErrorBody, HttpException, HttpResult},
+    extractors::{
+        auth::Auth,
+        pagination::{Filter, PageParams, Paginated},
+        state::ReadReplica,
+    },
+};
+
+#[derive(Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeliveriesFilter {
+    /// Only deliveries in this status
+    #[param(value_type = Option<String>, pattern = "^(pending|delivered|failed)$")]
+    status: Option<DeliveryStatus>,
+}
+
+impl Filter for DeliveriesFilter {
+    type Sort = DeliverySort;
+}
+
+/// List webhook deliveries
+///
+/// Returns the delivery log of a webhook, oldest first unless `order=desc`. `sort` is
+/// `created_at` (default), `next_attempt_at` or `attempts`.
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}/deliveries",
+    operation_id = "list_webhook_deliveries",
+    tag = "webhooks",
+    security(("BearerAuth" = [])),
+    params(("id" = i32, Path, description = "Webhook id"), PageParams, DeliveriesFilter),
+    responses(
+        (status = 200, description = "A page of deliveries", body = WebhookDeliveriesResponse),
+        (status = 400, description = "Invalid cursor or page params", body = ErrorBody),
+        (status = 401, description = "Missing, invalid or expired token", body = ErrorBody),
+        (status = 404, description = "No such webhook owned by the caller", body = ErrorBody),
+    )
+)]
+pub async fn handler(
+    State(ReadReplica(db)): State<ReadReplica>,
+    Auth(claims): Auth,
+    Path(id): Path<i32>,
+    Paginated { page, sort, filter }: Paginated<DeliveriesFilter>,
+) -> HttpResult<Json<WebhookDeliveriesResponse>> {
+    repositories::webhooks::find_by_owner(&db, id, claims.address)
+        .await?
+        .ok_or_else(|| HttpException::not_found("webhook not found"))?;
+
+    let page =
+        repositories::webhook_deliveries::list_by_webhook(&db, id, filter.status, sort, &page)
+            .await?;
+
+    let items = page
+        .items
+        .into_iter()
+        .map(|delivery| WebhookDelivery {
+            id: delivery.id,
+            status: delivery.status,
+            attempts: delivery.attempts,
+            payload: delivery.payload,
+            next_attempt_at: delivery.next_attempt_at.to_rfc3339(),
+            last_status_code: delivery.last_status_code.map(|code| code as u16),
+            last_error: delivery.last_error,
+            created_at: delivery.created_at.to_rfc3339(),
+            delivered_at: delivery.delivered_at.map(|at| at.to_rfc3339()),
+        })
+        .collect();
+
+    Ok(Json(WebhookDeliveriesResponse {
+        items,
+        next_cursor: page.next_cursor,
+    }))
+}
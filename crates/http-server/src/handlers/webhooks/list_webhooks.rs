@@ -0,0 +1,35 @@
+use api_types::webhooks::WebhooksResponse;
+use axum::{Json, extract::State};
+use database::repositories;
+
+use crate::{
+    exception::{ErrorBody, HttpResult},
+    extractors::{auth::Auth, state::ReadReplica},
+};
+
+/// List webhooks
+///
+/// Returns the caller's webhooks.
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    operation_id = "list_webhooks",
+    tag = "webhooks",
+    security(("BearerAuth" = [])),
+    responses(
+        (status = 200, description = "The caller's webhooks", body = WebhooksResponse),
+        (status = 401, description = "Missing, invalid or expired token", body = ErrorBody),
+    )
+)]
+pub async fn handler(
+    State(ReadReplica(db)): State<ReadReplica>,
+    Auth(claims): Auth,
+) -> HttpResult<Json<WebhooksResponse>> {
+    let webhooks = repositories::webhooks::list_by_owner(&db, claims.address)
+        .await?
+        .into_iter()
+        .map(super::to_response)
+        .collect();
+
+    Ok(Json(WebhooksResponse { webhooks }))
+}
@@ -0,0 +1,118 @@
+use api_types::webhooks::Webhook;
+use database::repositories::webhooks;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::extractors::state::AppState;
+
+mod create_webhook;
+mod delete_webhook;
+mod get_webhook;
+mod list_deliveries;
+mod list_webhooks;
+mod update_webhook;
+
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(create_webhook::handler, list_webhooks::handler))
+        .routes(routes!(
+            get_webhook::handler,
+            update_webhook::handler,
+            delete_webhook::handler
+        ))
+        .routes(routes!(list_deliveries::handler))
+}
+
+fn to_response(webhook: webhooks::Webhook) -> Webhook {
+    Webhook {
+        id: webhook.id,
+        url: webhook.url,
+        chain: webhook.chain,
+        protocol: webhook.protocol,
+        enabled: webhook.enabled,
+        consecutive_failures: webhook.consecutive_failures,
+        created_at: webhook.created_at.to_rfc3339(),
+        disabled_at: webhook.disabled_at.map(|at| at.to_rfc3339()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use api_types::webhooks::{CreatedWebhook, WebhookPayload, WebhooksResponse};
+    use axum::{Router, http::StatusCode};
+    use serde_json::Value;
+    use shared::UnionAddress;
+
+    use super::*;
+    use crate::testing;
+
+    const OWNER: &str = "0x00000000000000000000000000000000000000aa";
+
+    async fn router() -> Router {
+        let (router, _) = routes().split_for_parts();
+
+        router.with_state(testing::sqlite_state().await)
+    }
+
+    fn payload(url: &str) -> WebhookPayload {
+        WebhookPayload {
+            url: url.to_owned(),
+            chain: Some("1".to_owned()),
+            protocol: None,
+            enabled: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn registered_webhooks_are_listed_for_their_owner() {
+        let router = router().await;
+        let token = testing::token(OWNER.parse::<UnionAddress>().unwrap());
+
+        let (status, created): (_, CreatedWebhook) = testing::send(
+            &router,
+            "POST",
+            "/webhooks",
+            Some(&token),
+            Some(payload("https://example.com/hook")),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created.webhook.chain.as_deref(), Some("1"));
+        assert!(created.webhook.enabled);
+
+        let (status, listed): (_, WebhooksResponse) =
+            testing::send(&router, "GET", "/webhooks", Some(&token), None::<()>).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed.webhooks.len(), 1);
+        assert_eq!(listed.webhooks[0].id, created.webhook.id);
+        assert_eq!(listed.webhooks[0].url, "https://example.com/hook");
+
+        let other = testing::token(
+            "0x00000000000000000000000000000000000000bb"
+                .parse::<UnionAddress>()
+                .unwrap(),
+        );
+        let (_, listed): (_, WebhooksResponse) =
+            testing::send(&router, "GET", "/webhooks", Some(&other), None::<()>).await;
+
+        assert!(listed.webhooks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn internal_targets_are_rejected() {
+        let router = router().await;
+        let token = testing::token(OWNER.parse::<UnionAddress>().unwrap());
+
+        let (status, _): (_, Value) = testing::send(
+            &router,
+            "POST",
+            "/webhooks",
+            Some(&token),
+            Some(payload("http://169.254.169.254/latest/meta-data")),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
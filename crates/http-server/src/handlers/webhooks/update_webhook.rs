@@ -0,0 +1,52 @@
+use api_types::webhooks::{Webhook, WebhookPayload};
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use database::{
+    repositories::{self, webhooks::WebhookInput},
+    sea_orm::DatabaseConnection,
+};
+
+use crate::{
+    exception::{ErrorBody, HttpException, HttpResult},
+    extractors::{auth::Auth, validator::ValidatedPayload},
+};
+
+/// Update webhook
+///
+/// Replaces the url, filters and enabled flag. Re-enabling a webhook that was
+/// disabled after repeated failures resumes its pending deliveries.
+#[utoipa::path(
+    put,
+    path = "/webhooks/{id}",
+    operation_id = "update_webhook",
+    tag = "webhooks",
+    security(("BearerAuth" = [])),
+    params(("id" = i32, Path, description = "Webhook id")),
+    request_body = WebhookPayload,
+    responses(
+        (status = 200, description = "Webhook updated", body = Webhook),
+        (status = 400, description = "Invalid url or filters", body = ErrorBody),
+        (status = 401, description = "Missing, invalid or expired token", body = ErrorBody),
+        (status = 404, description = "No such webhook owned by the caller", body = ErrorBody),
+    )
+)]
+pub async fn handler(
+    State(db): State<DatabaseConnection>,
+    Auth(claims): Auth,
+    Path(id): Path<i32>,
+    ValidatedPayload(payload): ValidatedPayload<WebhookPayload>,
+) -> HttpResult<Json<Webhook>> {
+    let input = WebhookInput {
+        url: payload.url,
+        chain: payload.chain,
+        protocol: payload.protocol,
+    };
+
+    let webhook = repositories::webhooks::update(&db, id, claims.address, input, payload.enabled)
+        .await?
+        .ok_or_else(|| HttpException::not_found("webhook not found"))?;
+
+    Ok(Json(super::to_response(webhook)))
+}
@@ -1,9 +1,9 @@
-use axum::{Router, routing::get};
+use utoipa_axum::{router::OpenApiRouter, routes};
 
 use crate::extractors::state::AppState;
 
 mod random_u64;
 
-pub fn routes() -> Router<AppState> {
-    Router::new().route("/random-u64", get(random_u64::handler))
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new().routes(routes!(random_u64::handler))
 }
@@ -7,13 +7,34 @@ use fastwebsockets::Payload;
 use fastwebsockets::WebSocketError;
 use fastwebsockets::upgrade::{IncomingUpgrade, UpgradeFut};
 
+use crate::exception::ErrorBody;
 use crate::exception::HttpException;
 use crate::exception::HttpResult;
 
+/// Random u64 feed
+///
+/// Upgrades to a WebSocket. The server pushes a random `u64`, encoded as decimal
+/// text in a binary frame, every second. Pings are answered with pongs. When the
+/// server shuts down it sends a close frame with code 1001 (going away).
+#[utoipa::path(
+    get,
+    path = "/random-u64",
+    operation_id = "random_u64_feed",
+    tag = "ws",
+    responses(
+        (status = 101, description = "Switching protocols to WebSocket"),
+        (status = 500, description = "Upgrade failed", body = ErrorBody),
+    )
+)]
 pub async fn handler(req: IncomingUpgrade) -> HttpResult<impl IntoResponse> {
     let (response, fut) = req.upgrade().map_err(HttpException::internal)?;
 
+    let tracked = shared::shutdown::track();
+
     tokio::task::spawn(async move {
+        let _tracked = tracked;
+        let _connection = shared::metrics::ws_connection_opened("random_u64");
+
         if let Err(e) = handle_client(fut).await {
             tracing::error!("Error in websocket connection: {}", e);
         }
@@ -24,16 +45,7 @@ pub async fn handler(req: IncomingUpgrade) -> HttpResult<impl IntoResponse> {
 
 async fn handle_client(fut: UpgradeFut) -> Result<(), WebSocketError> {
     let mut ws = fastwebsockets::FragmentCollector::new(fut.await?);
-    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Frame>();
-
-    tokio::spawn(async move {
-        loop {
-            let rand = rand::random::<u64>().to_string();
-            let payload = Payload::Owned(rand.as_bytes().to_vec());
-            let _ = tx.send(Frame::binary(payload));
-            tokio::time::sleep(Duration::from_secs(1)).await;
-        }
-    });
+    let mut clock = tokio::time::interval(Duration::from_secs(1));
 
     loop {
         tokio::select! {
@@ -58,8 +70,14 @@ async fn handle_client(fut: UpgradeFut) -> Result<(), WebSocketError> {
                     _ => {}
                 }
             },
-            Some(frame) = rx.recv() => {
-                ws.write_frame(frame).await?;
+            _ = clock.tick() => {
+                let rand = rand::random::<u64>().to_string();
+                let payload = Payload::Owned(rand.as_bytes().to_vec());
+                ws.write_frame(Frame::binary(payload)).await?;
+            }
+            _ = shared::shutdown::wait() => {
+                ws.write_frame(Frame::close(1001, b"server shutting down")).await?;
+                return Ok(());
             }
         }
     }
@@ -1,50 +1,141 @@
-use axum::{Router, response::Html, routing::get};
-use shared::result::Rs;
-use tower_http::cors::CorsLayer;
+use std::time::Duration;
 
-use crate::extractors::state::AppState;
+use axum::{extract::DefaultBodyLimit, http::StatusCode, middleware, routing::get};
+use shared::{env::Env, result::Rs};
+use tower_http::{
+    compression::CompressionLayer, limit::RequestBodyLimitLayer, timeout::TimeoutLayer,
+};
+use utoipa::OpenApi;
+use utoipa_axum::router::OpenApiRouter;
 
+use crate::{
+    cache::{Cache, CachePolicy, CacheStore},
+    extractors::state::AppState,
+    middlewares::idempotency::Idempotency,
+    retention::Retention,
+    webhooks::Webhooks,
+};
+
+mod cache;
 mod common;
+mod config;
+mod docs;
 mod exception;
 mod extractors;
+mod feed;
+mod graphql;
 mod handlers;
+mod middlewares;
+mod retention;
+#[cfg(test)]
+mod testing;
+mod webhooks;
 
-/// Default server port
-const SERVER_PORT: u16 = 8080;
+/// How long open WebSockets get to close after the server stops accepting requests
+const WS_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[tokio::main]
 async fn main() -> Rs<()> {
-    shared::tracing::subscribe();
     shared::env::load();
+    shared::tracing::subscribe();
+
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.first().is_some_and(|command| command == "migrate") {
+        return migrate(&args[1..]).await;
+    }
+
+    shared::metrics::install()?;
+    shared::shutdown::listen();
+
+    let config = config::ServerConfig::from_env()?;
 
     let state = AppState::new().await?;
 
-    let app = Router::new()
-        .route("/", get(async || "hello !"))
-        .route(
-            "/docs/openapi.yml",
-            get(async || include_str!("../docs/openapi.yml")),
-        )
-        .route(
-            "/swagger",
-            get(async || Html(include_str!("../docs/swagger.html"))),
-        )
-        .route(
-            "/scalar",
-            get(async || Html(include_str!("../docs/scalar.html"))),
-        )
+    let idempotency = Idempotency::new(state.db.clone(), config.idempotency_ttl);
+    idempotency.spawn_purge();
+
+    let cache = Cache::new(
+        CacheStore::from_env().await?,
+        [(
+            "/health/indexers",
+            CachePolicy::new(Duration::from_secs(5)).invalidated_by_events(),
+        )],
+    );
+    cache.spawn_invalidation(state.feed.subscribe());
+
+    Webhooks::new(state.db.clone())?.spawn_delivery();
+
+    Retention::new(state.db.clone(), state.settings.clone())?.spawn_prune();
+
+    let (router, openapi) = OpenApiRouter::with_openapi(docs::ApiDoc::openapi())
+        .merge(handlers::health::routes())
         .merge(handlers::auth::routes())
         .merge(handlers::users::routes())
+        .merge(handlers::admin::routes())
+        .merge(handlers::webhooks::routes())
         .merge(handlers::ws::routes())
-        .layer(CorsLayer::permissive())
+        .split_for_parts();
+
+    let mut app = router
+        .route("/", get(async || "hello !"))
+        .merge(docs::routes(openapi))
+        .merge(graphql::routes(&state))
+        .layer(middleware::from_fn_with_state(
+            idempotency,
+            middlewares::idempotency::enforce,
+        ))
+        .layer(middleware::from_fn_with_state(
+            cache,
+            middlewares::cache::respond,
+        ))
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(config.body_limit))
+        .layer(TimeoutLayer::with_status_code(
+            StatusCode::REQUEST_TIMEOUT,
+            config.request_timeout,
+        ));
+
+    if config.compression {
+        app = app.layer(CompressionLayer::new());
+    }
+
+    let app = app
+        .layer(middleware::from_fn(middlewares::metrics::track))
+        .layer(middleware::from_fn(middlewares::request_id::propagate))
+        .layer(config.cors_layer())
         .with_state(state);
 
-    let addr = format!("0.0.0.0:{}", SERVER_PORT);
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    let listener = tokio::net::TcpListener::bind(config.addr).await?;
+
+    tracing::info!(profile = %config.profile, "Server is running {}", listener.local_addr()?);
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shared::shutdown::wait())
+        .await?;
+
+    // Upgraded connections outlive the server, give them time to send their close frames
+    if !shared::shutdown::drain(WS_DRAIN_TIMEOUT).await {
+        tracing::warn!(
+            "WebSocket connections still open after {:?}",
+            WS_DRAIN_TIMEOUT
+        );
+    }
 
-    tracing::info!("Server is running {}", listener.local_addr()?);
+    tracing::info!("Server stopped");
 
-    axum::serve(listener, app).await?;
+    shared::tracing::shutdown();
 
     Ok(())
 }
+
+/// `http-server migrate [up [<n>] | down [<n>] | status]` manages the schema instead of serving
+async fn migrate(args: &[String]) -> Rs<()> {
+    let db_url = shared::env::read(Env::DatabaseUrl)?;
+    let db = database::establish_connection(&db_url).await?;
+
+    let result = database::migrations::run_command(&db, args).await;
+
+    shared::tracing::shutdown();
+
+    result
+}
@@ -0,0 +1,285 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::{MatchedPath, Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+use crate::cache::{Cache, CachedResponse};
+
+/// `HIT` when the response came from the cache, `MISS` otherwise
+pub const X_CACHE: HeaderName = HeaderName::from_static("x-cache");
+
+/// Serves routes with a [`crate::cache::CachePolicy`] from the cache
+///
+/// Every cached response carries an `ETag`; a request whose `If-None-Match` matches it
+/// gets an empty 304. `Cache-Control: no-cache` makes clients revalidate each time, since
+/// entries can be invalidated server-side before their TTL.
+pub async fn respond(State(cache): State<Cache>, request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned());
+
+    let policy = route
+        .as_deref()
+        .filter(|_| request.method() == Method::GET)
+        .and_then(|route| Some((route, cache.policy(route)?)));
+
+    let Some((route, policy)) = policy else {
+        return next.run(request).await;
+    };
+
+    let key = cache
+        .key(
+            route,
+            request
+                .uri()
+                .path_and_query()
+                .map_or("/", |path| path.as_str()),
+        )
+        .await;
+    let if_none_match = request.headers().get(header::IF_NONE_MATCH).cloned();
+
+    if let Some(cached) = cache.store.get(&key).await {
+        return render(&cached, if_none_match.as_ref(), "HIT");
+    }
+
+    let response = next.run(request).await;
+
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(error) => {
+            tracing::error!("Failed to buffer response for caching: {}", error);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let cached = Arc::new(CachedResponse {
+        status: parts.status.as_u16(),
+        content_type: parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(ToOwned::to_owned),
+        etag: etag(&body),
+        body,
+    });
+
+    cache.store.set(key, cached.clone(), policy.ttl()).await;
+
+    render(&cached, if_none_match.as_ref(), "MISS")
+}
+
+fn render(
+    cached: &CachedResponse,
+    if_none_match: Option<&HeaderValue>,
+    outcome: &'static str,
+) -> Response {
+    let not_modified = if_none_match
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .split(',')
+                .map(|tag| tag.trim().trim_start_matches("W/"))
+                .any(|tag| tag == "*" || tag == cached.etag)
+        });
+
+    let mut headers = HeaderMap::new();
+    headers.insert(X_CACHE, HeaderValue::from_static(outcome));
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+
+    if let Ok(etag) = HeaderValue::from_str(&cached.etag) {
+        headers.insert(header::ETAG, etag);
+    }
+
+    if not_modified {
+        return (StatusCode::NOT_MODIFIED, headers).into_response();
+    }
+
+    if let Some(content_type) = cached
+        .content_type
+        .as_deref()
+        .and_then(|value| HeaderValue::from_str(value).ok())
+    {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+
+    let status = StatusCode::from_u16(cached.status).unwrap_or_default();
+
+    (status, headers, Body::from(cached.body.clone())).into_response()
+}
+
+/// Quoted hex of the first 16 bytes of the body's SHA-256
+fn etag(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    let hex: String = digest[..16]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    format!("\"{}\"", hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicU64, Ordering},
+        time::Duration,
+    };
+
+    use axum::{Router, middleware, routing::get};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::cache::{CachePolicy, CacheStore};
+
+    const ROUTE: &str = "/counter";
+    const MISSING: &str = "/missing";
+
+    /// A router whose handlers count their calls, so a hit is a response without a call
+    fn router(cache: Cache) -> (Router, Arc<AtomicU64>) {
+        let calls = Arc::new(AtomicU64::new(0));
+
+        let counter = {
+            let calls = calls.clone();
+            move || async move { calls.fetch_add(1, Ordering::Relaxed).to_string() }
+        };
+
+        let missing = {
+            let calls = calls.clone();
+            move || async move {
+                calls.fetch_add(1, Ordering::Relaxed);
+                StatusCode::NOT_FOUND
+            }
+        };
+
+        let router = Router::new()
+            .route(ROUTE, get(counter))
+            .route(MISSING, get(missing))
+            .layer(middleware::from_fn_with_state(cache, respond));
+
+        (router, calls)
+    }
+
+    fn cache() -> Cache {
+        let policy = || CachePolicy::new(Duration::from_secs(60));
+
+        Cache::new(
+            CacheStore::memory(100),
+            [(ROUTE, policy()), (MISSING, policy())],
+        )
+    }
+
+    async fn send(router: &Router, path: &str) -> (StatusCode, Option<String>, String) {
+        let request = Request::get(path).body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+
+        let status = response.status();
+        let outcome = response
+            .headers()
+            .get(X_CACHE)
+            .map(|value| value.to_str().unwrap().to_owned());
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        (status, outcome, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn repeated_requests_are_served_from_the_cache() {
+        let (router, calls) = router(cache());
+
+        let first = send(&router, ROUTE).await;
+        let second = send(&router, ROUTE).await;
+
+        assert_eq!(first, (StatusCode::OK, Some("MISS".into()), "0".into()));
+        assert_eq!(second, (StatusCode::OK, Some("HIT".into()), "0".into()));
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn queries_are_cached_separately() {
+        let (router, calls) = router(cache());
+
+        send(&router, ROUTE).await;
+        let other = send(&router, "/counter?page=2").await;
+
+        assert_eq!(other.1.as_deref(), Some("MISS"));
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn invalidation_drops_the_route_entries() {
+        let cache = cache();
+        let (router, calls) = router(cache.clone());
+
+        send(&router, ROUTE).await;
+        cache.invalidate(ROUTE).await;
+        let after = send(&router, ROUTE).await;
+
+        assert_eq!(after, (StatusCode::OK, Some("MISS".into()), "1".into()));
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn generation_is_shared_by_caches_on_the_same_store() {
+        let store = CacheStore::memory(100);
+        let policy = || CachePolicy::new(Duration::from_secs(60));
+        let first = Cache::new(store.clone(), [(ROUTE, policy())]);
+        let second = Cache::new(store, [(ROUTE, policy())]);
+
+        first.invalidate(ROUTE).await;
+
+        assert_eq!(
+            second.key(ROUTE, ROUTE).await,
+            first.key(ROUTE, ROUTE).await
+        );
+        assert_eq!(second.key(ROUTE, ROUTE).await, "/counter:1:/counter");
+    }
+
+    #[tokio::test]
+    async fn unsuccessful_responses_are_not_cached() {
+        let (router, calls) = router(cache());
+
+        let first = send(&router, MISSING).await;
+        let second = send(&router, MISSING).await;
+
+        assert_eq!(first.0, StatusCode::NOT_FOUND);
+        assert_eq!(first.1, None);
+        assert_eq!(second.1, None);
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn matching_etags_get_an_empty_304() {
+        let (router, _) = router(cache());
+
+        let request = Request::get(ROUTE).body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        let etag = response.headers().get(header::ETAG).unwrap().clone();
+
+        let request = Request::get(ROUTE)
+            .header(header::IF_NONE_MATCH, etag)
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert!(
+            to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,430 @@
+use std::time::Duration;
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::{OptionalFromRequestParts, Request, State},
+    http::{HeaderName, HeaderValue, Method, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use database::{
+    repositories::idempotency_keys::{self, Claim, StoredResponse},
+    sea_orm::DatabaseConnection,
+};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    exception::{ErrorCode, HttpException, HttpResult},
+    extractors::auth::Auth,
+};
+
+pub const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+/// Set on responses replayed from a previous request with the same key
+pub const IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+const MAX_KEY_LEN: usize = 255;
+const PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+#[derive(Clone)]
+pub struct Idempotency {
+    db: DatabaseConnection,
+    ttl: Duration,
+}
+
+impl Idempotency {
+    pub fn new(db: DatabaseConnection, ttl: Duration) -> Self {
+        Self { db, ttl }
+    }
+
+    /// Periodically deletes expired keys until shutdown
+    pub fn spawn_purge(&self) {
+        let db = self.db.clone();
+
+        tokio::spawn(async move {
+            loop {
+                match idempotency_keys::purge_expired(&db).await {
+                    Ok(0) => {}
+                    Ok(count) => tracing::info!("purged {} expired idempotency keys", count),
+                    Err(error) => error.trace("Failed to purge idempotency keys"),
+                }
+
+                tokio::select! {
+                    _ = tokio::time::sleep(PURGE_INTERVAL) => {}
+                    _ = shared::shutdown::wait() => break,
+                }
+            }
+        });
+    }
+}
+
+/// Makes mutating requests carrying an `Idempotency-Key` header safe to retry
+///
+/// Keys are scoped to the authenticated address, so only requests with a valid bearer
+/// token are covered: anonymous requests run as if no key was sent, and an invalid token
+/// is rejected with 401. The first request runs normally and its response is stored until the TTL expires; retries
+/// with the same key and payload get that response back with `Idempotent-Replayed:
+/// true`. A retry while the first request is still running is rejected with 409, and
+/// reusing a key for a different payload with 400. 5xx responses are not stored, so
+/// those can be retried.
+pub async fn enforce(
+    State(idempotency): State<Idempotency>,
+    request: Request,
+    next: Next,
+) -> HttpResult<Response> {
+    let is_mutating = matches!(
+        *request.method(),
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    );
+
+    let Some(key) = request
+        .headers()
+        .get(&IDEMPOTENCY_KEY)
+        .filter(|_| is_mutating)
+    else {
+        return Ok(next.run(request).await);
+    };
+
+    let key = key
+        .to_str()
+        .ok()
+        .filter(|key| !key.is_empty() && key.len() <= MAX_KEY_LEN)
+        .ok_or_else(|| HttpException::validate("Idempotency-Key must be 1-255 visible ASCII"))?
+        .to_owned();
+
+    let (mut parts, body) = request.into_parts();
+    let Some(Auth(claims)) =
+        <Auth as OptionalFromRequestParts<()>>::from_request_parts(&mut parts, &()).await?
+    else {
+        return Ok(next.run(Request::from_parts(parts, body)).await);
+    };
+    let scope = claims.address.to_string();
+
+    let body = to_bytes(body, usize::MAX)
+        .await
+        .map_err(|_| HttpException::bad_request("Failed to read request body"))?;
+
+    let fingerprint = fingerprint(&parts.method, parts.uri.path(), &body);
+
+    let db = &idempotency.db;
+
+    match idempotency_keys::claim(db, &key, &scope, &fingerprint, idempotency.ttl).await? {
+        Claim::Acquired => {}
+        Claim::InProgress => {
+            return Err(HttpException::conflict(
+                "A request with this Idempotency-Key is in progress",
+            )
+            .with_code(ErrorCode::IdempotencyKeyInProgress));
+        }
+        Claim::Mismatch => {
+            return Err(HttpException::bad_request(
+                "Idempotency-Key was already used for a different request",
+            )
+            .with_code(ErrorCode::IdempotencyKeyReused));
+        }
+        Claim::Completed(stored) => return Ok(replay(stored)),
+    }
+
+    let lock = Lock {
+        db: db.clone(),
+        key,
+        scope,
+        held: true,
+    };
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    lock.store(response).await
+}
+
+/// A claimed key, released when dropped before a response is stored, e.g. when the
+/// request times out or the client disconnects, so retries don't wait for the lock
+/// timeout
+struct Lock {
+    db: DatabaseConnection,
+    key: String,
+    scope: String,
+    held: bool,
+}
+
+impl Lock {
+    /// Persists `response` for the key, or releases the key if it shouldn't be replayed
+    async fn store(mut self, response: Response) -> HttpResult<Response> {
+        if response.status().is_server_error() {
+            idempotency_keys::release(&self.db, &self.key, &self.scope).await?;
+            self.held = false;
+            return Ok(response);
+        }
+
+        let response = store(&self.db, &self.key, &self.scope, response).await?;
+        self.held = false;
+
+        Ok(response)
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        if !self.held {
+            return;
+        }
+
+        let db = self.db.clone();
+        let key = std::mem::take(&mut self.key);
+        let scope = std::mem::take(&mut self.scope);
+
+        tokio::spawn(async move {
+            if let Err(error) = idempotency_keys::release(&db, &key, &scope).await {
+                error.trace("Failed to release idempotency key");
+            }
+        });
+    }
+}
+
+/// Stores `response` so retries with the key replay it
+async fn store(
+    db: &DatabaseConnection,
+    key: &str,
+    scope: &str,
+    response: Response,
+) -> HttpResult<Response> {
+    let (parts, body) = response.into_parts();
+
+    let body = to_bytes(body, usize::MAX)
+        .await
+        .map_err(HttpException::internal)?;
+
+    let stored = StoredResponse {
+        status_code: parts.status.as_u16(),
+        content_type: parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(ToOwned::to_owned),
+        body: body.to_vec(),
+    };
+
+    idempotency_keys::complete(db, key, scope, stored).await?;
+
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let mut response = Body::from(stored.body).into_response();
+
+    *response.status_mut() = stored.status_code.try_into().unwrap_or_default();
+
+    let headers = response.headers_mut();
+    headers.insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+
+    if let Some(content_type) = stored
+        .content_type
+        .and_then(|value| HeaderValue::from_str(&value).ok())
+    {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+
+    response
+}
+
+/// Hex-encoded SHA-256 of the method, path and body
+fn fingerprint(method: &Method, path: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_str());
+    hasher.update(b" ");
+    hasher.update(path);
+    hasher.update(b"\n");
+    hasher.update(body);
+
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    };
+
+    use axum::{Json, Router, http::StatusCode, middleware, routing::post};
+    use serde_json::{Value, json};
+    use tokio::sync::Notify;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{exception::ErrorBody, testing};
+
+    const EVM_ADDRESS: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+    const OTHER_EVM_ADDRESS: &str = "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359";
+
+    /// Counts handled requests, and holds `/slow` ones until `release` is notified
+    #[derive(Clone, Default)]
+    struct Handler {
+        calls: Arc<AtomicU64>,
+        entered: Arc<Notify>,
+        release: Arc<Notify>,
+    }
+
+    async fn router(handler: &Handler) -> Router {
+        let state = testing::sqlite_state().await;
+        let idempotency = Idempotency::new(state.db.clone(), Duration::from_secs(60));
+
+        let fast = handler.clone();
+        let slow = handler.clone();
+
+        Router::new()
+            .route(
+                "/fast",
+                post(async move |Json(body): Json<Value>| {
+                    let call = fast.calls.fetch_add(1, Ordering::SeqCst) + 1;
+
+                    (
+                        StatusCode::CREATED,
+                        Json(json!({ "call": call, "body": body })),
+                    )
+                }),
+            )
+            .route(
+                "/slow",
+                post(async move || {
+                    slow.entered.notify_one();
+                    slow.release.notified().await;
+
+                    StatusCode::NO_CONTENT
+                }),
+            )
+            .layer(middleware::from_fn_with_state(idempotency, enforce))
+    }
+
+    async fn send(
+        router: &Router,
+        uri: &str,
+        token: Option<&str>,
+        key: &str,
+        body: Value,
+    ) -> (StatusCode, bool, Value) {
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(IDEMPOTENCY_KEY, key);
+
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+
+        let response = router
+            .clone()
+            .oneshot(request.body(Body::from(body.to_string())).unwrap())
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let replayed = response.headers().contains_key(IDEMPOTENT_REPLAYED);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        (
+            status,
+            replayed,
+            serde_json::from_slice(&body).unwrap_or(Value::Null),
+        )
+    }
+
+    #[tokio::test]
+    async fn first_request_runs_and_retries_replay_it() {
+        let handler = Handler::default();
+        let router = router(&handler).await;
+        let token = testing::token(EVM_ADDRESS.parse::<shared::UnionAddress>().unwrap());
+
+        let first = send(&router, "/fast", Some(&token), "key", json!({ "a": 1 })).await;
+        let retry = send(&router, "/fast", Some(&token), "key", json!({ "a": 1 })).await;
+
+        assert_eq!(first.0, StatusCode::CREATED);
+        assert!(!first.1);
+        assert_eq!(retry.0, StatusCode::CREATED);
+        assert!(retry.1);
+        assert_eq!(retry.2, first.2);
+        assert_eq!(handler.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn keys_are_scoped_to_the_caller() {
+        let handler = Handler::default();
+        let router = router(&handler).await;
+        let token = testing::token(EVM_ADDRESS.parse::<shared::UnionAddress>().unwrap());
+        let other = testing::token(OTHER_EVM_ADDRESS.parse::<shared::UnionAddress>().unwrap());
+
+        send(&router, "/fast", Some(&token), "key", json!({})).await;
+        let (status, replayed, body) = send(&router, "/fast", Some(&other), "key", json!({})).await;
+
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(!replayed);
+        assert_eq!(body["call"], 2);
+    }
+
+    #[tokio::test]
+    async fn reusing_a_key_for_another_payload_is_rejected() {
+        let handler = Handler::default();
+        let router = router(&handler).await;
+        let token = testing::token(EVM_ADDRESS.parse::<shared::UnionAddress>().unwrap());
+
+        send(&router, "/fast", Some(&token), "key", json!({ "a": 1 })).await;
+        let (status, _, body) =
+            send(&router, "/fast", Some(&token), "key", json!({ "a": 2 })).await;
+
+        let body: ErrorBody = serde_json::from_value(body).unwrap();
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body.code, ErrorCode::IdempotencyKeyReused);
+        assert_eq!(handler.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn retry_while_in_progress_is_rejected() {
+        let handler = Handler::default();
+        let router = router(&handler).await;
+        let token = testing::token(EVM_ADDRESS.parse::<shared::UnionAddress>().unwrap());
+
+        let first = tokio::spawn({
+            let (router, token) = (router.clone(), token.clone());
+            async move { send(&router, "/slow", Some(&token), "key", json!({})).await }
+        });
+        handler.entered.notified().await;
+
+        let (status, _, body) = send(&router, "/slow", Some(&token), "key", json!({})).await;
+        let body: ErrorBody = serde_json::from_value(body).unwrap();
+
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body.code, ErrorCode::IdempotencyKeyInProgress);
+
+        handler.release.notify_one();
+        assert_eq!(first.await.unwrap().0, StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn anonymous_requests_are_not_covered() {
+        let handler = Handler::default();
+        let router = router(&handler).await;
+
+        let first = send(&router, "/fast", None, "key", json!({})).await;
+        let second = send(&router, "/fast", None, "key", json!({})).await;
+
+        assert!(!first.1 && !second.1);
+        assert_eq!(second.2["call"], 2);
+    }
+
+    #[tokio::test]
+    async fn invalid_tokens_are_rejected() {
+        let handler = Handler::default();
+        let router = router(&handler).await;
+        testing::set_access_token_key();
+
+        let (status, _, _) = send(&router, "/fast", Some("garbage"), "key", json!({})).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(handler.calls.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,30 @@
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+
+/// Records latency and status of every request, labelled by its route template
+pub async fn track(req: Request, next: Next) -> Response {
+    let start = Instant::now();
+
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| "unmatched".to_owned());
+
+    let response = next.run(req).await;
+
+    shared::metrics::record_http_request(
+        method,
+        route,
+        response.status().as_u16(),
+        start.elapsed(),
+    );
+
+    response
+}
@@ -0,0 +1,4 @@
+pub mod cache;
+pub mod idempotency;
+pub mod metrics;
+pub mod request_id;
@@ -0,0 +1,242 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use alloy::{eips::BlockNumberOrTag, providers::Provider};
+use chrono::Utc;
+use database::{
+    repos::SettingRepo,
+    repositories::{log_memos, outbox, settings::Setting},
+    sea_orm::DatabaseConnection,
+};
+use evm_lib::{SupportedChain, client::try_create_public_client};
+use shared::{
+    env::{self, Env},
+    result::{AppErr, Rs},
+};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::signature::Signature;
+use strum::IntoEnumIterator;
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+/// Memos deleted per statement, keeping each delete short
+const PRUNE_BATCH: u64 = 5_000;
+/// Pause between batches, so pruning doesn't starve the indexers
+const BATCH_PAUSE: Duration = Duration::from_millis(100);
+const SOLANA_CHAIN: &str = "solana";
+/// How long delivered outbox messages are kept when `OUTBOX_RETENTION_DAYS` is unset
+const DEFAULT_OUTBOX_RETENTION: Duration = Duration::from_secs(7 * 24 * 3600);
+/// Chain of memos recorded before memos were tagged with one
+const UNTAGGED_CHAIN: &str = "";
+
+/// How long `log_memo` rows are kept, per chain, and delivered `outbox` rows
+///
+/// `LOG_MEMO_RETENTION_DAYS_CHAIN_<chain>` sets a chain's horizon, e.g.
+/// `LOG_MEMO_RETENTION_DAYS_CHAIN_56` or `LOG_MEMO_RETENTION_DAYS_CHAIN_SOLANA`, and
+/// `LOG_MEMO_RETENTION_DAYS` the others'. Chains without a horizon are kept forever.
+/// `OUTBOX_RETENTION_DAYS` sets how long messages are kept once delivered, 7 by default.
+#[derive(Debug)]
+pub struct RetentionPolicy {
+    horizons: HashMap<String, Duration>,
+    outbox: Duration,
+}
+
+impl RetentionPolicy {
+    pub fn from_env() -> Rs<Self> {
+        let default = read_days(Env::LogMemoRetentionDays)?;
+
+        let chains = SupportedChain::iter()
+            .map(|chain| chain.to_chain_id().to_string())
+            .chain([SOLANA_CHAIN.to_owned()]);
+
+        let mut horizons = HashMap::new();
+
+        for chain in chains {
+            let horizon = read_days(Env::LogMemoRetentionDaysChain(chain.clone()))?.or(default);
+
+            if let Some(horizon) = horizon {
+                horizons.insert(chain, horizon);
+            }
+        }
+
+        if let Some(default) = default {
+            horizons.insert(UNTAGGED_CHAIN.to_owned(), default);
+        }
+
+        Ok(Self {
+            horizons,
+            outbox: read_days(Env::OutboxRetentionDays)?.unwrap_or(DEFAULT_OUTBOX_RETENTION),
+        })
+    }
+}
+
+/// Deletes memos past their chain's horizon and delivered outbox messages past theirs,
+/// in bounded batches
+///
+/// A scanner dedupes the logs it reaches against the memos the streams recorded ahead
+/// of it, so memos newer than the oldest scanner cursor are never pruned, whatever
+/// the horizon. When a cursor's time can't be resolved the run is skipped.
+pub struct Retention {
+    db: DatabaseConnection,
+    settings: Arc<dyn SettingRepo>,
+    policy: RetentionPolicy,
+}
+
+impl Retention {
+    pub fn new(db: DatabaseConnection, settings: Arc<dyn SettingRepo>) -> Rs<Self> {
+        Ok(Self {
+            db,
+            settings,
+            policy: RetentionPolicy::from_env()?,
+        })
+    }
+
+    pub fn spawn_prune(self) {
+        tracing::info!(policy = ?self.policy, "Pruning log memos and outbox messages");
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(error) = self.prune_memos().await {
+                    error.trace("Failed to prune log memos");
+                }
+
+                if let Err(error) = self.prune_outbox().await {
+                    error.trace("Failed to prune outbox messages");
+                }
+
+                tokio::select! {
+                    _ = tokio::time::sleep(PRUNE_INTERVAL) => {}
+                    _ = shared::shutdown::wait() => break,
+                }
+            }
+        });
+    }
+
+    async fn prune_memos(&self) -> Rs<()> {
+        if self.policy.horizons.is_empty() {
+            return Ok(());
+        }
+
+        let now = Utc::now().timestamp();
+        let oldest_cursor = self.oldest_cursor_time().await?;
+
+        for (chain, horizon) in &self.policy.horizons {
+            let mut before = now - horizon.as_secs() as i64;
+
+            if let Some(oldest_cursor) = oldest_cursor {
+                before = before.min(oldest_cursor);
+            }
+
+            let mut deleted = 0;
+
+            loop {
+                let batch = log_memos::prune(&self.db, chain, before, PRUNE_BATCH).await?;
+                deleted += batch;
+
+                if batch < PRUNE_BATCH {
+                    break;
+                }
+
+                tokio::select! {
+                    _ = tokio::time::sleep(BATCH_PAUSE) => {}
+                    _ = shared::shutdown::wait() => return Ok(()),
+                }
+            }
+
+            if deleted > 0 {
+                tracing::info!(chain, before, deleted, "Pruned log memos");
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn prune_outbox(&self) -> Rs<()> {
+        let before = Utc::now()
+            - chrono::Duration::from_std(self.policy.outbox)
+                .map_err(|error| AppErr::custom(error.to_string()))?;
+
+        let mut deleted = 0;
+
+        loop {
+            let batch = outbox::prune_delivered(&self.db, before, PRUNE_BATCH).await?;
+            deleted += batch;
+
+            if batch < PRUNE_BATCH {
+                break;
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(BATCH_PAUSE) => {}
+                _ = shared::shutdown::wait() => return Ok(()),
+            }
+        }
+
+        if deleted > 0 {
+            tracing::info!(%before, deleted, "Pruned outbox messages");
+        }
+
+        Ok(())
+    }
+
+    /// Block time of the oldest position a scanner will resume from, `None` without scanners
+    async fn oldest_cursor_time(&self) -> Rs<Option<i64>> {
+        let mut oldest = None::<i64>;
+
+        for (setting, cursor) in self.settings.all().await? {
+            let time = match setting {
+                Setting::EvmScannedBlock(chain_id) => evm_cursor_time(chain_id, &cursor).await?,
+                Setting::SolCurrentScannedSignature => solana_cursor_time(&cursor).await?,
+            };
+
+            if let Some(time) = time {
+                oldest = Some(oldest.map_or(time, |oldest| oldest.min(time)));
+            }
+        }
+
+        Ok(oldest)
+    }
+}
+
+/// Time of the block the scanner reads next, `None` when it isn't mined yet
+async fn evm_cursor_time(chain_id: u64, cursor: &str) -> Rs<Option<i64>> {
+    let client = try_create_public_client(SupportedChain::try_from(chain_id)?)?;
+    let block = cursor.parse::<u64>()?;
+
+    let time = client
+        .get_block_by_number(BlockNumberOrTag::Number(block))
+        .await?
+        .map(|block| block.header.timestamp as i64);
+
+    Ok(time)
+}
+
+/// Time of the block holding the newest transaction the scanner processed
+async fn solana_cursor_time(cursor: &str) -> Rs<Option<i64>> {
+    let client = RpcClient::new(env::read(Env::SolanaRpc)?);
+    let cursor = cursor.parse::<Signature>()?;
+
+    let slot = client
+        .get_signature_statuses_with_history(&[cursor])
+        .await?
+        .value
+        .into_iter()
+        .flatten()
+        .next()
+        .map(|status| status.slot)
+        .ok_or_else(|| AppErr::custom(format!("cursor signature {} not found", cursor)))?;
+
+    Ok(Some(client.get_block_time(slot).await?))
+}
+
+fn read_days(key: Env) -> Rs<Option<Duration>> {
+    let name = key.key();
+
+    env::read(key)
+        .ok()
+        .map(|raw| {
+            raw.trim()
+                .parse::<u64>()
+                .map(|days| Duration::from_secs(days * 24 * 3600))
+                .map_err(|_| AppErr::invalid_input(format!("invalid {}: {}", name, raw)))
+        })
+        .transpose()
+}
@@ -0,0 +1,82 @@
+use std::sync::Once;
+
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{Request, StatusCode, header},
+};
+use database::repos::Repos;
+use serde::{Serialize, de::DeserializeOwned};
+use shared::{UnionAddress, env::Env};
+use tower::ServiceExt;
+
+use crate::{
+    common,
+    extractors::state::{AppState, ReadReplica},
+    feed::EventFeed,
+};
+
+/// State backed by a fresh, migrated in-memory SQLite database
+pub async fn sqlite_state() -> AppState {
+    let db = database::establish_connection("sqlite::memory:")
+        .await
+        .unwrap();
+    database::migrations::check(&db).await.unwrap();
+
+    let feed = EventFeed::spawn(db.clone());
+    let repos = Repos::sql(db.clone(), None);
+
+    AppState::with_repos(db.clone(), ReadReplica(db), feed, repos)
+}
+
+/// A bearer token for `address`, signed with the test key
+pub fn token<A: Into<UnionAddress>>(address: A) -> String {
+    set_access_token_key();
+
+    common::jwt::sign(address).unwrap()
+}
+
+/// Sets `ACCESS_TOKEN_KEY`, which tokens are signed and checked with
+pub fn set_access_token_key() {
+    static SET: Once = Once::new();
+
+    // SAFETY: set once, to the same value, before any token is signed or checked
+    SET.call_once(|| unsafe { std::env::set_var(Env::AccessTokenKey.key().as_ref(), "test") });
+}
+
+/// Sends a JSON request to `router`, returning the status and the decoded body
+pub async fn send<B, R>(
+    router: &Router,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<B>,
+) -> (StatusCode, R)
+where
+    B: Serialize,
+    R: DeserializeOwned,
+{
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json");
+
+    if let Some(token) = token {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+
+    let body = body.map_or_else(Body::empty, |body| {
+        Body::from(serde_json::to_vec(&body).unwrap())
+    });
+
+    let response = router
+        .clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+    (status, serde_json::from_slice(&body).unwrap())
+}
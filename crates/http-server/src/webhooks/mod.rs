@@ -0,0 +1,237 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::http::HeaderName;
+use chrono::Utc;
+use database::{
+    repositories::{
+        webhook_deliveries::{self, Attempt},
+        webhooks,
+    },
+    sea_orm::DatabaseConnection,
+};
+use futures_util::future::join_all;
+use shared::result::{AppErr, Rs};
+
+pub mod signature;
+mod target;
+
+pub const WEBHOOK_ID: HeaderName = HeaderName::from_static("x-webhook-id");
+pub const WEBHOOK_DELIVERY: HeaderName = HeaderName::from_static("x-webhook-delivery");
+pub const WEBHOOK_TIMESTAMP: HeaderName = HeaderName::from_static("x-webhook-timestamp");
+pub const WEBHOOK_SIGNATURE: HeaderName = HeaderName::from_static("x-webhook-signature");
+
+/// Attempts before a delivery is given up
+const MAX_ATTEMPTS: i32 = 10;
+/// Failed deliveries in a row before a webhook is disabled
+const DISABLE_AFTER: i32 = 20;
+const BACKOFF_BASE: Duration = Duration::from_secs(10);
+const BACKOFF_MAX: Duration = Duration::from_secs(3600);
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+/// Must outlast a delivery, or a slow one could be claimed and sent again
+const LEASE: Duration = Duration::from_secs(60);
+const BATCH_SIZE: u64 = 32;
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Sends webhook deliveries with retries
+///
+/// Deliveries are enqueued by outbox-relay from the persisted outbox, so none are lost
+/// to a dropped notification, and several server instances can share the work. Only
+/// public addresses are connected to and redirects aren't followed, so a webhook can't
+/// reach internal services.
+#[derive(Clone)]
+pub struct Webhooks {
+    db: DatabaseConnection,
+    http: reqwest::Client,
+}
+
+impl Webhooks {
+    pub fn new(db: DatabaseConnection) -> Rs<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            .dns_resolver(Arc::new(target::PublicResolver))
+            .user_agent(concat!("webhooks/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|error| AppErr::custom(error.to_string()))?;
+
+        Ok(Self { db, http })
+    }
+
+    /// Sends due deliveries until shutdown
+    pub fn spawn_delivery(&self) {
+        let webhooks = self.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let delivered = match webhooks.deliver_due().await {
+                    Ok(count) => count,
+                    Err(error) => {
+                        error.trace("Failed to deliver webhooks");
+                        0
+                    }
+                };
+
+                // A full batch means more may be due already
+                if delivered as u64 == BATCH_SIZE {
+                    continue;
+                }
+
+                tokio::select! {
+                    _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                    _ = shared::shutdown::wait() => return,
+                }
+            }
+        });
+    }
+
+    /// Sends one batch of due deliveries, returning how many were attempted
+    async fn deliver_due(&self) -> Rs<usize> {
+        let deliveries = webhook_deliveries::claim_due(&self.db, BATCH_SIZE, LEASE).await?;
+
+        if deliveries.is_empty() {
+            return Ok(0);
+        }
+
+        let targets = webhooks::find_by_ids(
+            &self.db,
+            deliveries.iter().map(|delivery| delivery.webhook_id),
+        )
+        .await?;
+
+        let tasks = deliveries.iter().filter_map(|delivery| {
+            let webhook = targets
+                .iter()
+                .find(|webhook| webhook.id == delivery.webhook_id)?;
+
+            Some(async move {
+                let attempt = self
+                    .send(
+                        &webhook.url,
+                        &webhook.secret,
+                        webhook.id,
+                        delivery.id,
+                        &delivery.payload,
+                    )
+                    .await;
+
+                if let Err(error) = self
+                    .record(webhook.id, delivery.id, delivery.attempts + 1, attempt)
+                    .await
+                {
+                    error.trace("Failed to record webhook delivery");
+                }
+            })
+        });
+
+        join_all(tasks).await;
+
+        Ok(deliveries.len())
+    }
+
+    async fn send(
+        &self,
+        url: &str,
+        secret: &str,
+        webhook_id: i32,
+        delivery_id: i32,
+        payload: &str,
+    ) -> Result<u16, SendError> {
+        target::check(url).map_err(|error| SendError {
+            status_code: None,
+            error,
+        })?;
+
+        let timestamp = Utc::now().timestamp();
+
+        let response = self
+            .http
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(WEBHOOK_ID, webhook_id)
+            .header(WEBHOOK_DELIVERY, delivery_id)
+            .header(WEBHOOK_TIMESTAMP, timestamp)
+            .header(
+                WEBHOOK_SIGNATURE,
+                signature::sign(secret, timestamp, payload.as_bytes()),
+            )
+            .body(payload.to_owned())
+            .send()
+            .await
+            .map_err(|error| SendError {
+                status_code: None,
+                error: error.to_string(),
+            })?;
+
+        let status = response.status();
+
+        if status.is_success() {
+            Ok(status.as_u16())
+        } else {
+            Err(SendError {
+                status_code: Some(status.as_u16()),
+                error: format!("responded {}", status),
+            })
+        }
+    }
+
+    async fn record(
+        &self,
+        webhook_id: i32,
+        delivery_id: i32,
+        attempts: i32,
+        result: Result<u16, SendError>,
+    ) -> Rs<()> {
+        let attempt = match result {
+            Ok(status_code) => {
+                webhooks::record_success(&self.db, webhook_id).await?;
+                Attempt::Delivered { status_code }
+            }
+            Err(SendError { status_code, error }) => {
+                if webhooks::record_failure(&self.db, webhook_id, DISABLE_AFTER).await? {
+                    tracing::warn!(webhook_id, "Webhook disabled after repeated failures");
+                }
+
+                let retry_at = (attempts < MAX_ATTEMPTS)
+                    .then(|| shared::retry::backoff(BACKOFF_BASE, BACKOFF_MAX, attempts as u32))
+                    .and_then(|delay| chrono::Duration::from_std(delay).ok())
+                    .map(|delay| Utc::now() + delay);
+
+                Attempt::Failed {
+                    status_code,
+                    error,
+                    retry_at,
+                }
+            }
+        };
+
+        webhook_deliveries::record(&self.db, delivery_id, attempt).await
+    }
+}
+
+struct SendError {
+    /// Absent when no response was received
+    status_code: Option<u16>,
+    error: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_targets_must_be_public() {
+        assert!(target::check("https://example.com/hook").is_ok());
+        assert!(target::check("https://93.184.215.14/hook").is_ok());
+
+        for url in [
+            "http://127.0.0.1/hook",
+            "http://10.0.0.1/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://[fd00::1]/hook",
+            "http://[::ffff:192.168.0.1]/hook",
+        ] {
+            assert!(target::check(url).is_err(), "{}", url);
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use std::net::{IpAddr, SocketAddr};
+
+use reqwest::{
+    Url,
+    dns::{Addrs, Name, Resolve, Resolving},
+};
+use shared::validators::is_public_ip;
+
+/// Resolves names to their public addresses only
+///
+/// Checking the addresses actually connected to, rather than the url at registration,
+/// keeps a name that later resolves to an internal host from reaching it.
+pub struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public_ip(addr.ip()))
+                .collect::<Vec<SocketAddr>>();
+
+            if addrs.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Checks a url's literal ip host, which bypasses the resolver
+pub fn check(url: &str) -> Result<(), String> {
+    let url = url.parse::<Url>().map_err(|error| error.to_string())?;
+
+    let ip = match url.host() {
+        Some(url::Host::Ipv4(ip)) => IpAddr::from(ip),
+        Some(url::Host::Ipv6(ip)) => IpAddr::from(ip),
+        _ => return Ok(()),
+    };
+
+    if is_public_ip(ip) {
+        Ok(())
+    } else {
+        Err(format!("{} is not a public address", ip))
+    }
+}
@@ -0,0 +1,232 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use database::{repositories::outbox, sea_orm::DatabaseConnection};
+use shared::{env::Env, result::Rs};
+
+use crate::sink::Sink;
+
+mod sink;
+mod webhooks;
+
+/// Messages claimed per round
+const BATCH_SIZE: u64 = 100;
+/// How long a claim holds before another relay may publish the message
+const LEASE: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_millis(1_000);
+/// First wait before retrying a failed message, doubled after each failure
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(600);
+
+#[tokio::main]
+async fn main() -> Rs<()> {
+    shared::env::load();
+    shared::tracing::subscribe();
+    shared::metrics::install()?;
+    shared::shutdown::listen();
+
+    let db_url = shared::env::read(Env::DatabaseUrl)?;
+
+    let db = database::establish_connection(&db_url).await?;
+    database::migrations::check(&db).await?;
+    let sink = Sink::from_env(&db).await?;
+
+    tracing::info!("Outbox relay started, publishing to {}", sink.name());
+
+    // A round always runs to completion, so claimed messages are settled before exiting
+    loop {
+        let full = match relay(&db, &sink).await {
+            Ok(relayed) => relayed as u64 == BATCH_SIZE,
+            Err(error) => {
+                error.trace("Relay failed");
+                false
+            }
+        };
+
+        // A full batch likely left more due, so the next round starts right away
+        if full && !shared::shutdown::requested() {
+            continue;
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = shared::shutdown::wait() => break,
+        }
+    }
+
+    tracing::info!("Outbox relay stopped");
+    shared::tracing::shutdown();
+
+    Ok(())
+}
+
+/// Publishes a batch of due messages in order, returns how many were claimed
+///
+/// Indexed events are fanned out to matching webhooks before being published, so a
+/// delivery is enqueued for every persisted event even if the sink drops it. Each
+/// message is marked delivered as soon as it is published, so a later failure can't
+/// get it published again. Messages that fail are retried with exponential backoff,
+/// without ever being dropped; a failure to record either outcome is logged and the
+/// claim simply lapses, republishing the message after the lease.
+#[tracing::instrument(skip_all)]
+async fn relay(db: &DatabaseConnection, sink: &Sink) -> Rs<usize> {
+    let messages = outbox::claim_due(db, BATCH_SIZE, LEASE).await?;
+    let mut delivered = 0;
+
+    for message in &messages {
+        let published = match webhooks::dispatch(db, message).await {
+            Ok(_) => sink.publish(message).await,
+            Err(error) => Err(error),
+        };
+
+        match published {
+            Ok(()) => {
+                delivered += 1;
+
+                if let Err(error) = outbox::mark_delivered(db, message.id).await {
+                    error.trace("Failed to mark outbox message delivered");
+                }
+            }
+            Err(error) => {
+                let backoff = shared::retry::backoff(
+                    INITIAL_BACKOFF,
+                    MAX_BACKOFF,
+                    message.attempts as u32 + 1,
+                );
+                let retry_at = Utc::now() + chrono::Duration::from_std(backoff).unwrap_or_default();
+
+                tracing::warn!(
+                    id = message.id,
+                    topic = message.topic,
+                    attempts = message.attempts + 1,
+                    "Failed to publish outbox message: {}",
+                    error
+                );
+
+                if let Err(error) =
+                    outbox::record_failure(db, message.id, error.to_string(), retry_at).await
+                {
+                    error.trace("Failed to record outbox message failure");
+                }
+            }
+        }
+    }
+
+    if delivered > 0 {
+        tracing::debug!(delivered, "Published outbox messages");
+    }
+
+    Ok(messages.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use api_types::webhooks::WebhookEvent;
+    use database::{
+        notify::IndexedEvent,
+        repositories::{
+            outbox::NewOutboxMessage,
+            webhook_deliveries,
+            webhooks::{self, WebhookInput},
+        },
+    };
+    use shared::UnionAddress;
+
+    use super::*;
+
+    async fn db() -> DatabaseConnection {
+        let db = database::establish_connection("sqlite::memory:")
+            .await
+            .unwrap();
+        database::migrations::check(&db).await.unwrap();
+
+        db
+    }
+
+    fn message(key: &str) -> NewOutboxMessage {
+        NewOutboxMessage {
+            topic: "test".to_owned(),
+            key: key.to_owned(),
+            payload: format!(r#"{{"key":"{}"}}"#, key),
+        }
+    }
+
+    #[tokio::test]
+    async fn relay_publishes_due_messages_once_in_order() {
+        let db = db().await;
+        let path = std::env::temp_dir().join(format!("outbox-relay-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let sink = Sink::file(path.to_str().unwrap()).await.unwrap();
+
+        outbox::enqueue(&db, vec![message("a"), message("b")])
+            .await
+            .unwrap();
+
+        assert_eq!(relay(&db, &sink).await.unwrap(), 2);
+        // Delivered messages aren't claimed again
+        assert_eq!(relay(&db, &sink).await.unwrap(), 0);
+
+        let published = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let published = published
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(published.len(), 2);
+        assert_eq!(published[0]["key"], "a");
+        assert_eq!(published[0]["topic"], "test");
+        assert_eq!(published[0]["payload"]["key"], "a");
+        assert_eq!(published[1]["key"], "b");
+    }
+
+    #[tokio::test]
+    async fn relay_enqueues_webhook_deliveries_for_indexed_events() {
+        let db = db().await;
+        let path =
+            std::env::temp_dir().join(format!("outbox-webhooks-{}.jsonl", std::process::id()));
+        let sink = Sink::file(path.to_str().unwrap()).await.unwrap();
+
+        let owner = "0x00000000000000000000000000000000000000aa"
+            .parse::<UnionAddress>()
+            .unwrap();
+        let input = |protocol: &str| WebhookInput {
+            url: "https://example.com/hook".to_owned(),
+            chain: None,
+            protocol: Some(protocol.to_owned()),
+        };
+        let matching = webhooks::create(&db, owner, "secret".to_owned(), input("pumpfun"))
+            .await
+            .unwrap();
+        webhooks::create(&db, owner, "secret".to_owned(), input("uniswap_v3"))
+            .await
+            .unwrap();
+
+        let event = IndexedEvent {
+            chain: "solana".to_owned(),
+            protocol: "pumpfun".to_owned(),
+            tx_hash: "sig".to_owned(),
+            log_ix: 2,
+        };
+        let messages = vec![
+            NewOutboxMessage::indexed_event(&event).unwrap(),
+            message("other"),
+        ];
+        outbox::enqueue(&db, messages).await.unwrap();
+
+        // Nothing was announced on the live feed, the outbox row alone is enough
+        assert_eq!(relay(&db, &sink).await.unwrap(), 2);
+        std::fs::remove_file(&path).unwrap();
+
+        let deliveries = webhook_deliveries::claim_due(&db, 10, LEASE).await.unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].webhook_id, matching.id);
+        assert_eq!(deliveries[0].event_key, "solana:sig:2");
+
+        let payload = serde_json::from_str::<WebhookEvent>(&deliveries[0].payload).unwrap();
+        assert_eq!(payload.webhook_id, matching.id);
+        assert_eq!(payload.tx_hash, "sig");
+        assert_eq!(payload.log_ix, 2);
+    }
+}
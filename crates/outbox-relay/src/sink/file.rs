@@ -0,0 +1,31 @@
+use tokio::{
+    fs::{File, OpenOptions},
+    io::AsyncWriteExt,
+    sync::Mutex,
+};
+
+use shared::result::Rs;
+
+/// Appends one line per message, flushed before the message counts as delivered
+pub struct FileSink(Mutex<File>);
+
+impl FileSink {
+    pub async fn open(path: &str) -> Rs<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+
+        Ok(Self(Mutex::new(file)))
+    }
+
+    pub async fn append(&self, line: &str) -> Rs<()> {
+        let mut file = self.0.lock().await;
+
+        file.write_all(format!("{}\n", line).as_bytes()).await?;
+        file.flush().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+use database::{notify, repositories::outbox::OutboxMessage, sea_orm::DatabaseConnection};
+use serde::Serialize;
+use shared::{
+    env::{self, Env},
+    result::{AppErr, Rs},
+};
+
+mod file;
+#[cfg(feature = "redis")]
+mod redis;
+
+/// Where outbox messages are published, selected with `OUTBOX_SINK`
+pub enum Sink {
+    /// One JSON line per message on stdout
+    Stdout,
+    /// One JSON line per message appended to a file
+    File(file::FileSink),
+    /// Postgres NOTIFY on `outbox_<topic>`
+    Notify(DatabaseConnection),
+    /// An entry per message on the Redis stream named after the topic
+    #[cfg(feature = "redis")]
+    Redis(redis::RedisSink),
+}
+
+/// A message as published, carrying its id and key so consumers can drop redeliveries
+#[derive(Serialize)]
+struct Envelope<'a> {
+    id: i64,
+    topic: &'a str,
+    key: &'a str,
+    payload: serde_json::Value,
+}
+
+impl Sink {
+    /// `stdout` (default), `notify`, `file:<path>` or a `redis://` url
+    pub async fn from_env(db: &DatabaseConnection) -> Rs<Self> {
+        let sink = env::read(Env::OutboxSink).unwrap_or_else(|_| "stdout".to_owned());
+
+        match sink.as_str() {
+            "stdout" => Ok(Self::Stdout),
+            "notify" => Ok(Self::Notify(db.clone())),
+            sink if sink.starts_with("file:") => Self::file(sink.trim_start_matches("file:")).await,
+            #[cfg(feature = "redis")]
+            sink if sink.starts_with("redis://") || sink.starts_with("rediss://") => {
+                Ok(Self::Redis(redis::RedisSink::connect(sink).await?))
+            }
+            #[cfg(not(feature = "redis"))]
+            sink if sink.starts_with("redis://") || sink.starts_with("rediss://") => {
+                Err(AppErr::invalid_input(
+                    "OUTBOX_SINK is a Redis url, but outbox-relay was built without the redis feature",
+                ))
+            }
+            sink => Err(AppErr::invalid_input(format!(
+                "invalid OUTBOX_SINK: {}",
+                sink
+            ))),
+        }
+    }
+
+    /// Appends to the file at `path`, creating it if needed
+    pub async fn file(path: &str) -> Rs<Self> {
+        Ok(Self::File(file::FileSink::open(path).await?))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Stdout => "stdout",
+            Self::File(_) => "file",
+            Self::Notify(_) => "notify",
+            #[cfg(feature = "redis")]
+            Self::Redis(_) => "redis",
+        }
+    }
+
+    /// Publishes `message`, at least once: a failure after the sink accepted it means
+    /// it is published again on retry
+    pub async fn publish(&self, message: &OutboxMessage) -> Rs<()> {
+        match self {
+            Self::Stdout => {
+                println!("{}", envelope(message)?);
+                Ok(())
+            }
+            Self::File(sink) => sink.append(&envelope(message)?).await,
+            Self::Notify(db) => {
+                let channel = format!("outbox_{}", message.topic);
+                notify::notify(db, &channel, &[envelope(message)?]).await
+            }
+            #[cfg(feature = "redis")]
+            Self::Redis(sink) => sink.publish(message).await,
+        }
+    }
+}
+
+fn envelope(message: &OutboxMessage) -> Rs<String> {
+    let payload = serde_json::from_str(&message.payload)
+        .unwrap_or_else(|_| serde_json::Value::String(message.payload.clone()));
+
+    serde_json::to_string(&Envelope {
+        id: message.id,
+        topic: &message.topic,
+        key: &message.message_key,
+        payload,
+    })
+    .map_err(|error| AppErr::custom(error.to_string()))
+}
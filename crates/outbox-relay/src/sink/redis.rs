@@ -0,0 +1,36 @@
+use database::repositories::outbox::OutboxMessage;
+use redis::aio::ConnectionManager;
+use shared::result::{AppErr, Rs};
+
+/// Adds each message to the Redis stream named after its topic
+///
+/// A local `redis-server` stands in for the broker in development
+pub struct RedisSink(ConnectionManager);
+
+impl RedisSink {
+    pub async fn connect(url: &str) -> Rs<Self> {
+        let client = redis::Client::open(url).map_err(redis_err)?;
+        let manager = ConnectionManager::new(client).await.map_err(redis_err)?;
+
+        Ok(Self(manager))
+    }
+
+    pub async fn publish(&self, message: &OutboxMessage) -> Rs<()> {
+        redis::cmd("XADD")
+            .arg(&message.topic)
+            .arg("*")
+            .arg("id")
+            .arg(message.id)
+            .arg("key")
+            .arg(&message.message_key)
+            .arg("payload")
+            .arg(&message.payload)
+            .exec_async(&mut self.0.clone())
+            .await
+            .map_err(redis_err)
+    }
+}
+
+fn redis_err(error: redis::RedisError) -> AppErr {
+    AppErr::custom(format!("redis: {}", error))
+}
@@ -0,0 +1,47 @@
+use api_types::webhooks::WebhookEvent;
+use database::{
+    notify::IndexedEvent,
+    repositories::{
+        outbox::{INDEXED_EVENT_TOPIC, OutboxMessage},
+        webhook_deliveries::{self, NewDelivery},
+        webhooks,
+    },
+    sea_orm::DatabaseConnection,
+};
+use shared::result::{AppErr, Rs};
+
+/// Enqueues a delivery per webhook matching the indexed event in `message`
+///
+/// Enqueueing is idempotent per webhook and event, so a message relayed again doesn't
+/// deliver twice. Messages of other topics are ignored. Returns how many were enqueued.
+pub async fn dispatch(db: &DatabaseConnection, message: &OutboxMessage) -> Rs<u64> {
+    if message.topic != INDEXED_EVENT_TOPIC {
+        return Ok(0);
+    }
+
+    let event = serde_json::from_str::<IndexedEvent>(&message.payload)
+        .map_err(|error| AppErr::custom(error.to_string()))?;
+    let targets = webhooks::find_matching(db, &event.chain, &event.protocol).await?;
+
+    let deliveries = targets
+        .into_iter()
+        .map(|webhook| {
+            let payload = serde_json::to_string(&WebhookEvent {
+                webhook_id: webhook.id,
+                chain: event.chain.clone(),
+                protocol: event.protocol.clone(),
+                tx_hash: event.tx_hash.clone(),
+                log_ix: event.log_ix,
+            })
+            .map_err(|error| AppErr::custom(error.to_string()))?;
+
+            Ok(NewDelivery {
+                webhook_id: webhook.id,
+                event_key: message.message_key.clone(),
+                payload,
+            })
+        })
+        .collect::<Rs<Vec<_>>>()?;
+
+    webhook_deliveries::enqueue(db, deliveries).await
+}
@@ -1,15 +1,44 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, str::FromStr};
 
-use crate::result::Rs;
+use crate::result::{AppErr, Rs};
 
 pub enum Env {
     DatabaseUrl,
+    DatabaseReadUrl,
+    DbMaxConnections,
+    DbMinConnections,
+    DbAcquireTimeoutSecs,
+    DbIdleTimeoutSecs,
+    DbStatementTimeoutMs,
+    DbConnectRetries,
     AccessTokenKey,
     SolanaRpc,
     SolanaWsRpc,
     EvmWsRpc(u64),
     PubEvmRpc(u64),
     PriEvmRpc(u64),
+    MetricsAddr,
+    LogFormat,
+    OtlpEndpoint,
+    OtelServiceName,
+    AppProfile,
+    ServerAddr,
+    CorsAllowedOrigins,
+    CorsAllowedMethods,
+    RequestTimeoutSecs,
+    BodyLimitBytes,
+    Compression,
+    IdempotencyTtlSecs,
+    RedisUrl,
+    CacheMaxEntries,
+    AdminAddresses,
+    MigrationCheck,
+    LogMemoRetentionDays,
+    LogMemoRetentionDaysChain(String),
+    OutboxSink,
+    OutboxRetentionDays,
+    EventMaxAttempts,
+    EventRetryBackoffMs,
 }
 
 /// Loads environment variables from .env file if present
@@ -25,16 +54,70 @@ pub fn read(env: Env) -> Rs<String> {
     std::env::var(env.key().as_ref()).map_err(Into::into)
 }
 
+/// Reads an optional environment variable
+///
+/// Configuration readers take it as their lookup so tests can pass their own instead
+pub fn var(env: &Env) -> Option<String> {
+    std::env::var(env.key().as_ref()).ok()
+}
+
+/// Parses the variable `lookup` finds for `key`, using `default` when it is not set
+pub fn parse_or<T: FromStr>(
+    lookup: &impl Fn(&Env) -> Option<String>,
+    key: Env,
+    default: T,
+) -> Rs<T> {
+    match lookup(&key) {
+        Some(raw) => raw
+            .trim()
+            .parse()
+            .map_err(|_| AppErr::invalid_input(format!("invalid {}: {}", key.key(), raw))),
+        None => Ok(default),
+    }
+}
+
 impl Env {
-    fn key(&self) -> Cow<'static, str> {
+    /// Name of the environment variable
+    pub fn key(&self) -> Cow<'static, str> {
         match self {
             Self::DatabaseUrl => "DATABASE_URL".into(),
+            Self::DatabaseReadUrl => "DATABASE_READ_URL".into(),
+            Self::DbMaxConnections => "DB_MAX_CONNECTIONS".into(),
+            Self::DbMinConnections => "DB_MIN_CONNECTIONS".into(),
+            Self::DbAcquireTimeoutSecs => "DB_ACQUIRE_TIMEOUT_SECS".into(),
+            Self::DbIdleTimeoutSecs => "DB_IDLE_TIMEOUT_SECS".into(),
+            Self::DbStatementTimeoutMs => "DB_STATEMENT_TIMEOUT_MS".into(),
+            Self::DbConnectRetries => "DB_CONNECT_RETRIES".into(),
             Self::AccessTokenKey => "ACCESS_TOKEN_KEY".into(),
             Self::EvmWsRpc(chain) => format!("WS_RPC_CHAIN_{}", chain).into(),
             Self::PubEvmRpc(chain) => format!("PUBLIC_RPC_CHAIN_{}", chain).into(),
             Self::PriEvmRpc(chain) => format!("PRIVATE_RPC_CHAIN_{}", chain).into(),
             Self::SolanaRpc => "SOLANA_RPC".into(),
             Self::SolanaWsRpc => "SOLANA_WS_RPC".into(),
+            Self::MetricsAddr => "METRICS_ADDR".into(),
+            Self::LogFormat => "LOG_FORMAT".into(),
+            Self::OtlpEndpoint => "OTEL_EXPORTER_OTLP_ENDPOINT".into(),
+            Self::OtelServiceName => "OTEL_SERVICE_NAME".into(),
+            Self::AppProfile => "APP_PROFILE".into(),
+            Self::ServerAddr => "SERVER_ADDR".into(),
+            Self::CorsAllowedOrigins => "CORS_ALLOWED_ORIGINS".into(),
+            Self::CorsAllowedMethods => "CORS_ALLOWED_METHODS".into(),
+            Self::RequestTimeoutSecs => "REQUEST_TIMEOUT_SECS".into(),
+            Self::BodyLimitBytes => "BODY_LIMIT_BYTES".into(),
+            Self::Compression => "COMPRESSION".into(),
+            Self::IdempotencyTtlSecs => "IDEMPOTENCY_TTL_SECS".into(),
+            Self::RedisUrl => "REDIS_URL".into(),
+            Self::CacheMaxEntries => "CACHE_MAX_ENTRIES".into(),
+            Self::AdminAddresses => "ADMIN_ADDRESSES".into(),
+            Self::MigrationCheck => "MIGRATION_CHECK".into(),
+            Self::LogMemoRetentionDays => "LOG_MEMO_RETENTION_DAYS".into(),
+            Self::LogMemoRetentionDaysChain(chain) => {
+                format!("LOG_MEMO_RETENTION_DAYS_CHAIN_{}", chain.to_uppercase()).into()
+            }
+            Self::OutboxSink => "OUTBOX_SINK".into(),
+            Self::OutboxRetentionDays => "OUTBOX_RETENTION_DAYS".into(),
+            Self::EventMaxAttempts => "EVENT_MAX_ATTEMPTS".into(),
+            Self::EventRetryBackoffMs => "EVENT_RETRY_BACKOFF_MS".into(),
         }
     }
 }
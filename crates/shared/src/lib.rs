@@ -8,12 +8,15 @@ use crate::result::AppErr;
 
 pub mod arg;
 pub mod env;
+pub mod metrics;
 pub mod result;
+pub mod retry;
+pub mod shutdown;
 pub mod tracing;
 pub mod util;
 pub mod validators;
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum UnionAddress {
     Evm(Address),
     Sol(Pubkey),
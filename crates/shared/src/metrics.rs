@@ -0,0 +1,141 @@
+use std::{future::Future, net::SocketAddr, time::Duration, time::Instant};
+
+use metrics::{counter, gauge, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder};
+
+use crate::{
+    env::Env,
+    result::{AppErr, Rs},
+};
+
+/// Latency buckets (seconds) shared by every `*_seconds` histogram
+const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Installs the Prometheus recorder and serves metrics on `METRICS_ADDR`
+///
+/// When `METRICS_ADDR` is not set no recorder is installed and every
+/// helper in this module becomes a no-op
+///
+/// Must be called from within a tokio runtime
+pub fn install() -> Rs<()> {
+    let Ok(addr) = crate::env::read(Env::MetricsAddr) else {
+        tracing::info!("METRICS_ADDR not set, metrics exporter disabled");
+        return Ok(());
+    };
+
+    let addr = addr
+        .parse::<SocketAddr>()
+        .map_err(|error| AppErr::custom(format!("invalid METRICS_ADDR {}: {}", addr, error)))?;
+
+    PrometheusBuilder::new()
+        .with_http_listener(addr)
+        .set_buckets_for_metric(Matcher::Suffix("_seconds".into()), LATENCY_BUCKETS)
+        .and_then(|builder| builder.install())
+        .map_err(|error| {
+            AppErr::custom(format!("failed to install metrics exporter: {}", error))
+        })?;
+
+    tracing::info!("Metrics exporter listening on {}", addr);
+
+    Ok(())
+}
+
+/// Records one served HTTP request
+pub fn record_http_request(method: String, route: String, status: u16, elapsed: Duration) {
+    let labels = [
+        ("method", method),
+        ("route", route),
+        ("status", status.to_string()),
+    ];
+
+    counter!("http_requests_total", &labels).increment(1);
+    histogram!("http_request_duration_seconds", &labels).record(elapsed.as_secs_f64());
+}
+
+/// Keeps a WebSocket connection counted as open until dropped, so a task that panics
+/// still closes it
+#[must_use]
+pub struct WsConnection(String);
+
+impl Drop for WsConnection {
+    fn drop(&mut self) {
+        gauge!("ws_connections", "endpoint" => self.0.clone()).decrement(1);
+    }
+}
+
+/// Marks a WebSocket connection (server or client side) as open until the returned
+/// guard is dropped
+pub fn ws_connection_opened(endpoint: &str) -> WsConnection {
+    let endpoint = endpoint.to_owned();
+    gauge!("ws_connections", "endpoint" => endpoint.clone()).increment(1);
+    counter!("ws_connections_total", "endpoint" => endpoint.clone()).increment(1);
+
+    WsConnection(endpoint)
+}
+
+/// Records how far a scanner got and how far it trails the chain head
+pub fn record_scan_progress(chain: &str, scanned: u64, head: u64) {
+    let chain = chain.to_owned();
+    gauge!("indexer_scanned_block", "chain" => chain.clone()).set(scanned as f64);
+    gauge!("indexer_lag_blocks", "chain" => chain).set(head.saturating_sub(scanned) as f64);
+}
+
+/// Records `count` events processed by an indexer
+///
+/// `outcome` distinguishes freshly processed events from deduplicated and dead-lettered ones
+pub fn record_events_handled(source: &'static str, outcome: &'static str, count: u64) {
+    counter!("events_handled_total", "source" => source, "outcome" => outcome).increment(count);
+}
+
+/// Records a single database statement
+pub fn record_db_query(elapsed: Duration, failed: bool) {
+    let status = if failed { "error" } else { "ok" };
+    histogram!("db_query_duration_seconds", "status" => status).record(elapsed.as_secs_f64());
+}
+
+/// Awaits an RPC call, counting calls, errors and latency per chain and method
+pub async fn track_rpc<T, E, F>(chain: &str, method: &'static str, call: F) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    let labels = [("chain", chain.to_owned()), ("method", method.to_owned())];
+    let start = Instant::now();
+
+    let result = call.await;
+
+    counter!("rpc_requests_total", &labels).increment(1);
+    histogram!("rpc_request_duration_seconds", &labels).record(start.elapsed().as_secs_f64());
+
+    if result.is_err() {
+        counter!("rpc_errors_total", &labels).increment(1);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use metrics_exporter_prometheus::PrometheusBuilder;
+
+    use super::*;
+
+    #[test]
+    fn ws_connection_is_closed_when_its_task_panics() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+
+        metrics::with_local_recorder(&recorder, || {
+            let _ = std::panic::catch_unwind(|| {
+                let _connection = ws_connection_opened("test");
+                panic!("socket task panicked");
+            });
+        });
+
+        let rendered = handle.render();
+
+        assert!(rendered.contains("ws_connections{endpoint=\"test\"} 0"));
+        assert!(rendered.contains("ws_connections_total{endpoint=\"test\"} 1"));
+    }
+}
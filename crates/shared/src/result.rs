@@ -16,6 +16,12 @@ pub enum AppErr {
         location: Location,
     },
 
+    #[error("InvalidInput: {message}")]
+    InvalidInput {
+        message: Cow<'static, str>,
+        location: Location,
+    },
+
     #[error("ParseInt: {src}")]
     ParseInt {
         src: std::num::ParseIntError,
@@ -126,6 +132,7 @@ impl AppErr {
     pub fn location(&self) -> Location {
         match self {
             AppErr::Custom { location, .. } => location,
+            AppErr::InvalidInput { location, .. } => location,
             AppErr::Database { location, .. } => location,
             AppErr::EvmRpc { location, .. } => location,
             AppErr::Io { location, .. } => location,
@@ -153,4 +160,13 @@ impl AppErr {
             location: core::panic::Location::caller(),
         }
     }
+
+    /// An error caused by caller-supplied data rather than by the system
+    #[track_caller]
+    pub fn invalid_input<E: Into<Cow<'static, str>>>(message: E) -> AppErr {
+        AppErr::InvalidInput {
+            message: message.into(),
+            location: core::panic::Location::caller(),
+        }
+    }
 }
@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+use crate::{
+    env::{self, Env, parse_or},
+    result::Rs,
+};
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+const DEFAULT_RETRY_BACKOFF_MS: u64 = 200;
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(10);
+
+/// How often an event is retried before it is dead-lettered, read from the environment
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// `EVENT_MAX_ATTEMPTS`, attempts including the first one
+    pub max_attempts: u32,
+    /// `EVENT_RETRY_BACKOFF_MS`, wait before the first retry, doubled after each one
+    pub initial_backoff: Duration,
+}
+
+/// Exponential backoff: the wait before retry `attempt`, counting from 1
+///
+/// The first retry waits `initial`, each one after twice the previous, up to `max`.
+pub fn backoff(initial: Duration, max: Duration, attempt: u32) -> Duration {
+    initial
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(max)
+}
+
+impl RetryPolicy {
+    pub fn from_env() -> Rs<Self> {
+        Self::from_lookup(&env::var)
+    }
+
+    fn from_lookup(lookup: &impl Fn(&Env) -> Option<String>) -> Rs<Self> {
+        Ok(Self {
+            max_attempts: Ord::max(
+                parse_or(lookup, Env::EventMaxAttempts, DEFAULT_MAX_ATTEMPTS)?,
+                1,
+            ),
+            initial_backoff: Duration::from_millis(parse_or(
+                lookup,
+                Env::EventRetryBackoffMs,
+                DEFAULT_RETRY_BACKOFF_MS,
+            )?),
+        })
+    }
+
+    /// Whether an event that failed `attempts` times is retried again
+    pub fn should_retry(&self, attempts: u32) -> bool {
+        attempts < self.max_attempts
+    }
+
+    /// Wait before retry `attempt`, counting from 1 for the first retry
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        backoff(self.initial_backoff, MAX_RETRY_BACKOFF, attempt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INITIAL: Duration = Duration::from_millis(500);
+    const MAX: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        assert_eq!(backoff(INITIAL, MAX, 0), INITIAL);
+        assert_eq!(backoff(INITIAL, MAX, 1), INITIAL);
+        assert_eq!(backoff(INITIAL, MAX, 2), INITIAL * 2);
+        assert_eq!(backoff(INITIAL, MAX, 4), INITIAL * 8);
+        assert_eq!(backoff(INITIAL, MAX, 8), MAX);
+        assert_eq!(backoff(INITIAL, MAX, 33), MAX);
+        assert_eq!(backoff(INITIAL, MAX, u32::MAX), MAX);
+    }
+
+    #[test]
+    fn backoff_never_exceeds_a_maximum_below_the_initial_wait() {
+        assert_eq!(backoff(MAX, INITIAL, 1), INITIAL);
+    }
+
+    fn policy(vars: &[(&str, &str)]) -> Rs<RetryPolicy> {
+        RetryPolicy::from_lookup(&|key: &Env| {
+            vars.iter()
+                .find(|(name, _)| *name == key.key())
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn retry_policy_defaults_apply_when_unset() {
+        let policy = policy(&[]).unwrap();
+
+        assert_eq!(policy.max_attempts, DEFAULT_MAX_ATTEMPTS);
+        assert_eq!(
+            policy.initial_backoff,
+            Duration::from_millis(DEFAULT_RETRY_BACKOFF_MS)
+        );
+    }
+
+    #[test]
+    fn retry_policy_reads_its_variables() {
+        let policy = policy(&[
+            ("EVENT_MAX_ATTEMPTS", "5"),
+            ("EVENT_RETRY_BACKOFF_MS", "50"),
+        ])
+        .unwrap();
+
+        assert_eq!(policy.max_attempts, 5);
+        assert_eq!(policy.initial_backoff, Duration::from_millis(50));
+        assert!(policy.should_retry(4));
+        assert!(!policy.should_retry(5));
+        assert_eq!(policy.backoff(1), Duration::from_millis(50));
+        assert_eq!(policy.backoff(3), Duration::from_millis(200));
+        assert_eq!(policy.backoff(u32::MAX), MAX_RETRY_BACKOFF);
+    }
+
+    #[test]
+    fn retry_policy_always_makes_one_attempt() {
+        let policy = policy(&[("EVENT_MAX_ATTEMPTS", "0")]).unwrap();
+
+        assert_eq!(policy.max_attempts, 1);
+        assert!(!policy.should_retry(1));
+    }
+
+    #[test]
+    fn retry_policy_rejects_invalid_values() {
+        assert!(policy(&[("EVENT_MAX_ATTEMPTS", "three")]).is_err());
+        assert!(policy(&[("EVENT_RETRY_BACKOFF_MS", "-1")]).is_err());
+    }
+}
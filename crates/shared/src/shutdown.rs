@@ -0,0 +1,94 @@
+use std::{sync::LazyLock, time::Duration};
+
+use tokio::sync::watch;
+
+static SHUTDOWN: LazyLock<watch::Sender<bool>> = LazyLock::new(|| watch::channel(false).0);
+static TRACKED: LazyLock<watch::Sender<usize>> = LazyLock::new(|| watch::channel(0).0);
+
+/// Keeps a detached task, such as an upgraded WebSocket, counted until dropped, so
+/// [`drain`] can wait for it
+pub struct Tracked(());
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        TRACKED.send_modify(|count| *count -= 1);
+    }
+}
+
+/// Spawns a task that requests shutdown on SIGINT (Ctrl+C) or SIGTERM
+///
+/// Must be called from within a tokio runtime
+pub fn listen() {
+    tokio::spawn(async {
+        signal().await;
+        tracing::info!("Shutdown signal received, finishing in-flight work");
+        request();
+    });
+}
+
+/// Requests shutdown, waking every [`wait`] caller
+pub fn request() {
+    SHUTDOWN.send_replace(true);
+}
+
+/// Whether shutdown has been requested
+pub fn requested() -> bool {
+    *SHUTDOWN.borrow()
+}
+
+/// Resolves once shutdown has been requested, immediately if it already was
+///
+/// Meant to be raced in `tokio::select!` against work that can be safely abandoned,
+/// such as a sleep or a socket read, never against a write in progress
+pub async fn wait() {
+    let mut rx = SHUTDOWN.subscribe();
+    // The sender lives in a static, so it's never dropped
+    let _ = rx.wait_for(|requested| *requested).await;
+}
+
+/// Counts the calling task as in flight until the returned guard is dropped
+pub fn track() -> Tracked {
+    TRACKED.send_modify(|count| *count += 1);
+    Tracked(())
+}
+
+/// Waits up to `timeout` for every [`track`]ed task to finish, returns whether they did
+pub async fn drain(timeout: Duration) -> bool {
+    let mut rx = TRACKED.subscribe();
+
+    tokio::time::timeout(timeout, rx.wait_for(|count| *count == 0))
+        .await
+        .is_ok()
+}
+
+async fn signal() {
+    let ctrl_c = async {
+        if let Err(error) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {}", error);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut stream) => {
+                stream.recv().await;
+            }
+            Err(error) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", error);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
@@ -1,8 +1,15 @@
+use std::sync::OnceLock;
+
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_sdk::{Resource, trace::SdkTracerProvider};
 use strum::IntoEnumIterator;
+use tracing_subscriber::{EnvFilter, Layer, Registry};
 
-/// Initializes the tracing subscriber with stdout and stderr layers
-///
-/// Logs at ERROR level go to stderr, all other levels to stdout
+use crate::env::Env;
+
+static TRACER_PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
 
 #[derive(strum::Display, strum::EnumIter)]
 #[strum(serialize_all = "snake_case")]
@@ -14,31 +21,240 @@ pub enum Crate {
     SolLib,
     SolanaScanner,
     SolanaStream,
+    Database,
+    Shared,
+}
+
+/// Output format of the stdout/stderr layers, selected with `LOG_FORMAT`
+#[derive(strum::EnumString, Default, Clone, Copy)]
+#[strum(serialize_all = "snake_case")]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Pretty,
+    /// One JSON object per line, including the current span and its parents
+    Json,
 }
 
+/// Initializes the tracing subscriber with stdout and stderr layers
+///
+/// Logs at ERROR level go to stderr, all other levels to stdout. `LOG_FORMAT=json`
+/// switches both to JSON lines, and setting `OTEL_EXPORTER_OTLP_ENDPOINT` additionally
+/// exports spans over OTLP/HTTP (e.g. `http://localhost:4318` for a local collector)
+///
+/// Must be called after [`crate::env::load`] so `.env` values are visible
 pub fn subscribe() {
+    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+    let format = crate::env::read(Env::LogFormat)
+        .ok()
+        .and_then(|format| format.parse().ok())
+        .unwrap_or_default();
+
+    let mut layers = fmt_layers(format);
+
+    if crate::env::read(Env::OtlpEndpoint).is_ok() {
+        match otlp_layer() {
+            Ok(layer) => layers.push(layer),
+            Err(error) => eprintln!("Failed to initialize OTLP exporter: {}", error),
+        }
+    }
+
+    tracing_subscriber::registry().with(layers).init();
+}
+
+/// Flushes buffered spans to the OTLP collector, if one is configured
+pub fn shutdown() {
+    if let Some(provider) = TRACER_PROVIDER.get()
+        && let Err(error) = provider.shutdown()
+    {
+        eprintln!("Failed to flush OTLP spans: {}", error);
+    }
+}
+
+fn crate_filter() -> EnvFilter {
+    Crate::iter().fold(EnvFilter::from_default_env(), |filter, c| {
+        filter.add_directive(c.to_string().parse().expect("Invalid filter directive"))
+    })
+}
+
+fn fmt_layers(format: LogFormat) -> Vec<BoxedLayer> {
     use tracing::Level;
-    use tracing_subscriber::{
-        EnvFilter, Layer, filter, fmt, layer::SubscriberExt, util::SubscriberInitExt,
-    };
+    use tracing_subscriber::{filter, fmt};
+
+    let not_error = filter::filter_fn(|metadata| *metadata.level() != Level::ERROR);
+
+    match format {
+        LogFormat::Pretty => vec![
+            fmt::layer()
+                .with_writer(std::io::stdout)
+                .with_filter(crate_filter())
+                .with_filter(not_error)
+                .boxed(),
+            fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_filter(filter::LevelFilter::ERROR)
+                .boxed(),
+        ],
+        LogFormat::Json => vec![
+            fmt::layer()
+                .json()
+                .with_current_span(true)
+                .with_span_list(true)
+                .with_writer(std::io::stdout)
+                .with_filter(crate_filter())
+                .with_filter(not_error)
+                .boxed(),
+            fmt::layer()
+                .json()
+                .with_current_span(true)
+                .with_span_list(true)
+                .with_writer(std::io::stderr)
+                .with_filter(filter::LevelFilter::ERROR)
+                .boxed(),
+        ],
+    }
+}
+
+fn otlp_layer() -> Result<BoxedLayer, opentelemetry_otlp::ExporterBuildError> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .build()?;
+
+    let provider = otlp_provider(exporter);
+    let tracer = provider.tracer("web3-indexer");
+    let _ = TRACER_PROVIDER.set(provider);
+
+    let layer = tracing_opentelemetry::layer()
+        .with_tracer(tracer)
+        .with_filter(crate_filter())
+        .boxed();
 
-    let out_layer = fmt::layer()
-        .with_writer(std::io::stdout)
-        .with_filter(
-            Crate::iter().fold(EnvFilter::from_default_env(), |filter, c| {
-                filter.add_directive(c.to_string().parse().expect("Invalid filter directive"))
-            }),
+    Ok(layer)
+}
+
+/// Batches spans to `exporter`, tagged with the service name
+fn otlp_provider(exporter: opentelemetry_otlp::SpanExporter) -> SdkTracerProvider {
+    SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(service_name())
+                .build(),
         )
-        .with_filter(filter::filter_fn(|metadata| {
-            *metadata.level() != Level::ERROR
-        }));
-
-    let err_layer = fmt::layer()
-        .with_writer(std::io::stderr)
-        .with_filter(filter::LevelFilter::ERROR);
-
-    tracing_subscriber::registry()
-        .with(out_layer)
-        .with(err_layer)
-        .init();
+        .build()
+}
+
+/// `OTEL_SERVICE_NAME` if set, otherwise the running binary's name
+fn service_name() -> String {
+    crate::env::read(Env::OtelServiceName)
+        .ok()
+        .or_else(|| {
+            std::env::current_exe().ok().and_then(|path| {
+                path.file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+            })
+        })
+        .unwrap_or_else(|| "unknown_service".to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::mpsc,
+        thread,
+    };
+
+    use opentelemetry_otlp::WithExportConfig;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    /// A request received by [`collector`]
+    struct Export {
+        request_line: String,
+        content_type: String,
+        body: Vec<u8>,
+    }
+
+    /// Starts a local OTLP/HTTP collector answering one export, returns its traces url
+    fn collector() -> (String, mpsc::Receiver<Export>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/v1/traces", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+
+            let (mut content_type, mut content_length) = (String::new(), 0);
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+
+                let Some((name, value)) = line.trim_end().split_once(": ") else {
+                    break;
+                };
+
+                match name.to_ascii_lowercase().as_str() {
+                    "content-type" => content_type = value.to_owned(),
+                    "content-length" => content_length = value.parse().unwrap(),
+                    _ => {}
+                }
+            }
+
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .unwrap();
+
+            let _ = tx.send(Export {
+                request_line: request_line.trim_end().to_owned(),
+                content_type,
+                body,
+            });
+        });
+
+        (url, rx)
+    }
+
+    #[test]
+    fn spans_are_exported_to_the_collector() {
+        let (url, exports) = collector();
+
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .with_endpoint(url)
+            .build()
+            .unwrap();
+        let provider = otlp_provider(exporter);
+
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("otlp_test_span").in_scope(|| tracing::info!("inside"));
+        });
+
+        provider.shutdown().unwrap();
+
+        let export = exports.recv().unwrap();
+
+        assert_eq!(export.request_line, "POST /v1/traces HTTP/1.1");
+        assert_eq!(export.content_type, "application/x-protobuf");
+        assert!(
+            export
+                .body
+                .windows(b"otlp_test_span".len())
+                .any(|window| window == b"otlp_test_span")
+        );
+    }
 }
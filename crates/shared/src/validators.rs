@@ -1,3 +1,5 @@
+use std::net::IpAddr;
+
 use alloy::primitives::Address;
 use solana_sdk::pubkey::Pubkey;
 use validator::ValidationError;
@@ -10,10 +12,12 @@ pub fn validate_solana_pubkey(val: &str) -> Result<(), ValidationError> {
         .map_err(|_| ValidationError::new("invalid_solana_pubkey"))
 }
 
+/// A 0x-prefixed address, alloy alone also accepts it bare
 pub fn validate_evm_address(val: &str) -> Result<(), ValidationError> {
-    val.parse::<Address>()
+    val.strip_prefix("0x")
+        .and_then(|_| val.parse::<Address>().ok())
         .map(|_| ())
-        .map_err(|_| ValidationError::new("invalid_evm_address"))
+        .ok_or_else(|| ValidationError::new("invalid_evm_address"))
 }
 
 pub fn validate_union_address(val: &str) -> Result<(), ValidationError> {
@@ -33,3 +37,64 @@ pub fn validate_solana_signature(val: &str) -> Result<(), ValidationError> {
         .map(|_| ())
         .map_err(|_| ValidationError::new("invalid_solana_signature"))
 }
+
+/// An http(s) url whose host isn't a loopback, private or link-local target
+///
+/// Only literal hosts can be checked here, names are checked once resolved.
+pub fn validate_http_url(val: &str) -> Result<(), ValidationError> {
+    let url = val
+        .parse::<url::Url>()
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .ok_or_else(|| ValidationError::new("invalid_http_url"))?;
+
+    let public = match url.host() {
+        Some(url::Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+            domain != "localhost" && !domain.ends_with(".localhost")
+        }
+        Some(url::Host::Ipv4(ip)) => is_public_ip(ip.into()),
+        Some(url::Host::Ipv6(ip)) => is_public_ip(ip.into()),
+        None => return Err(ValidationError::new("invalid_http_url")),
+    };
+
+    public
+        .then_some(())
+        .ok_or_else(|| ValidationError::new("non_public_http_url"))
+}
+
+/// Whether `ip` is a public internet address, rather than a loopback, private,
+/// link-local, shared, multicast or reserved one
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+
+            !(first == 0
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (first == 100 && (64..128).contains(&second))
+                // Reserved, including broadcast
+                || first >= 240)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_ip(ip.into());
+            }
+
+            let first = ip.segments()[0];
+
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                // Unique local, fc00::/7
+                || first & 0xfe00 == 0xfc00
+                // Link-local, fe80::/10
+                || first & 0xffc0 == 0xfe80)
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::io;
+
 use bytes::Bytes;
 use fastwebsockets::FragmentCollector;
 use fastwebsockets::WebSocketError;
@@ -24,12 +26,16 @@ pub type FrameCollector = FragmentCollector<TokioIo<Upgraded>>;
 /// 
 /// # Arguments
 /// * `uri` - The WebSocket URI to connect to
-/// 
+///
 /// # Returns
-/// A frame collector for reading/writing WebSocket frames
+/// A frame collector for reading/writing WebSocket frames, or an `InvalidInput` I/O
+/// error when `uri` has no valid host
 pub async fn connect(uri: &Uri) -> Result<FrameCollector, WebSocketError> {
-    let host = uri.host().expect("not found host");
-    let port = uri.port_u16().unwrap_or(443);
+    let host = uri.host().ok_or_else(|| invalid_input("uri has no host"))?;
+    let is_tls = uri
+        .scheme_str()
+        .is_some_and(|schema| schema == "https" || schema == "wss");
+    let port = uri.port_u16().unwrap_or(if is_tls { 443 } else { 80 });
 
     let stream = TcpStream::connect((host, port)).await?;
 
@@ -45,15 +51,13 @@ pub async fn connect(uri: &Uri) -> Result<FrameCollector, WebSocketError> {
         )
         .header(header::SEC_WEBSOCKET_VERSION, 13)
         .body(Empty::<Bytes>::new())
-        .expect("invalid req");
+        .map_err(|error| invalid_input(error.to_string()))?;
 
-    let (ws, _) = if uri
-        .scheme_str()
-        .is_some_and(|schema| schema == "https" || schema == "wss")
-    {
+    let (ws, _) = if is_tls {
         let connector = tls_connector();
 
-        let domain = ServerName::try_from(host.to_owned()).expect("invalid domain");
+        let domain = ServerName::try_from(host.to_owned())
+            .map_err(|error| invalid_input(error.to_string()))?;
         let tls_stream = connector.connect(domain, stream).await?;
 
         handshake::client(&TokioExecutor::new(), req, tls_stream).await?
@@ -63,3 +67,23 @@ pub async fn connect(uri: &Uri) -> Result<FrameCollector, WebSocketError> {
 
     Ok(FragmentCollector::new(ws))
 }
+
+fn invalid_input<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> WebSocketError {
+    io::Error::new(io::ErrorKind::InvalidInput, error).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn uri_without_host_is_an_error() {
+        let uri = Uri::from_static("/random-u64");
+
+        let Err(WebSocketError::IoError(error)) = connect(&uri).await else {
+            panic!("expected an I/O error");
+        };
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
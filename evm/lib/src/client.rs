@@ -32,25 +32,39 @@ pub type WalletClient = FillProvider<
 >;
 
 /// Creates a public client for reading blockchain state
+///
+/// # Panics
+/// Panics if the chain's RPC environment variables are missing or invalid
 pub fn create_public_client(chain: SupportedChain) -> PublicClient {
+    try_create_public_client(chain).unwrap()
+}
+
+/// Creates a public client, returning an error if the chain's RPCs are not configured
+pub fn try_create_public_client(chain: SupportedChain) -> Rs<PublicClient> {
     let chain_id = chain.to_chain_id();
-    let client = create_root_client(chain_id);
+    let client = create_root_client(chain_id)?;
 
-    ProviderBuilder::new()
+    let provider = ProviderBuilder::new()
         .disable_recommended_fillers()
         .with_chain_id(chain_id)
-        .connect_client(client)
+        .connect_client(client);
+
+    Ok(provider)
 }
 
-/// Creates a wallet client for signing and sending transactions
+/// Creates a wallet client for signing and sending transactions, returning an error if
+/// the chain's RPCs are not configured
 ///
 /// # Arguments
 /// * `chain` - The chain ID to connect to
 /// * `signers` - List of private key signers to register
-pub fn create_wallet_client(chain: SupportedChain, signers: Vec<PrivateKeySigner>) -> WalletClient {
+pub fn create_wallet_client(
+    chain: SupportedChain,
+    signers: Vec<PrivateKeySigner>,
+) -> Rs<WalletClient> {
     let chain_id = chain.to_chain_id();
 
-    let client = create_root_client(chain_id);
+    let client = create_root_client(chain_id)?;
 
     let wallet = signers
         .into_iter()
@@ -59,11 +73,13 @@ pub fn create_wallet_client(chain: SupportedChain, signers: Vec<PrivateKeySigner
             wallet
         });
 
-    ProviderBuilder::new()
+    let provider = ProviderBuilder::new()
         .disable_recommended_fillers()
         .with_chain_id(chain_id)
         .wallet(wallet)
-        .connect_client(client)
+        .connect_client(client);
+
+    Ok(provider)
 }
 
 /// Trait for sending EIP-1559 transactions with automatic gas estimation
@@ -159,11 +175,11 @@ impl SendEip1559 for WalletClient {
     }
 }
 
-fn create_root_client(chain_id: u64) -> RpcClient {
+fn create_root_client(chain_id: u64) -> Rs<RpcClient> {
     let fallback_layer =
         FallbackLayer::default().with_active_transport_count(NonZeroUsize::new(2).unwrap());
 
-    let (public_rpc, private_rpc) = read_rpcs_by_chain(chain_id).unwrap();
+    let (public_rpc, private_rpc) = read_rpcs_by_chain(chain_id)?;
 
     let transports = [Http::new(private_rpc), Http::new(public_rpc)].to_vec();
 
@@ -171,7 +187,7 @@ fn create_root_client(chain_id: u64) -> RpcClient {
         .layer(fallback_layer)
         .service(transports);
 
-    RpcClient::builder().transport(transport, false)
+    Ok(RpcClient::builder().transport(transport, false))
 }
 
 fn read_rpcs_by_chain(chain_id: u64) -> Rs<(Url, Url)> {
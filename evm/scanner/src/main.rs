@@ -5,16 +5,21 @@ use alloy::{
     providers::Provider,
     rpc::types::{Filter, FilterBlockOption},
 };
-use database::repositories::settings::Setting;
-use database::{repositories, sea_orm::DatabaseConnection};
+use database::repositories::settings::EvmScannedBlock;
+use database::{
+    repositories,
+    sea_orm::{DatabaseConnection, TransactionTrait},
+};
 use evm_lib::{
     SupportedChain,
     client::{PublicClient, create_public_client},
     uniswap_v2::UniswapPoolV2::UniswapPoolV2Events,
     uniswap_v3::UniswapPoolV3::UniswapPoolV3Events,
 };
-use futures_util::future::try_join_all;
-use shared::{env::Env, result::Rs};
+use shared::{
+    env::Env,
+    result::{AppErr, Rs},
+};
 use tokio::time::sleep;
 
 const SCAN_FREQUENCY: Duration = Duration::from_millis(6_000);
@@ -23,32 +28,30 @@ const SCAN_FREQUENCY: Duration = Duration::from_millis(6_000);
 async fn main() {
     shared::env::load();
     shared::tracing::subscribe();
+    shared::metrics::install().unwrap();
+    shared::shutdown::listen();
     let chain_id = shared::arg::parse_chain_id_arg();
     bootstrap(chain_id).await.unwrap();
+    shared::tracing::shutdown();
 }
 
 async fn bootstrap(chain_id: u64) -> Rs<()> {
     let db_url = shared::env::read(Env::DatabaseUrl)?;
     let db = database::establish_connection(&db_url).await?;
+    database::migrations::check(&db).await?;
 
     let chain = SupportedChain::try_from(chain_id)?;
     let client = create_public_client(chain);
 
     let current_scanned_block = {
-        let scanned_block =
-            repositories::settings::get(&db, Setting::EvmScannedBlock(chain_id)).await?;
+        let scanned_block = repositories::settings::get(&db, EvmScannedBlock(chain_id)).await?;
 
         if let Some(scanned_block) = scanned_block {
-            scanned_block.parse()?
+            scanned_block
         } else {
             let latest_block = client.get_block_number().await?;
-            repositories::settings::insert(
-                &db,
-                Setting::EvmScannedBlock(chain_id),
-                latest_block.to_string(),
-            )
-            .await?;
-            latest_block
+            repositories::settings::get_or_insert(&db, EvmScannedBlock(chain_id), latest_block)
+                .await?
         }
     };
 
@@ -71,7 +74,18 @@ async fn bootstrap(chain_id: u64) -> Rs<()> {
 
     tracing::info!("starting scanner from block {}", current_scanned_block);
 
+    // A scan always runs to completion, so the cursor is persisted before exiting
     loop {
+        // The admin API or another scanner may have moved the cursor since the last scan
+        match repositories::settings::get(&db, EvmScannedBlock(chain_id)).await {
+            Ok(Some(stored)) if filter.get_from_block() != Some(stored) => {
+                tracing::info!("cursor moved to block {}", stored);
+                filter = filter.from_block(stored);
+            }
+            Ok(_) => {}
+            Err(error) => error.trace("Failed to reload cursor"),
+        }
+
         match scan(&client, &db, chain, &mut filter).await {
             Ok(next) => {
                 filter = filter.from_block(next);
@@ -81,17 +95,31 @@ async fn bootstrap(chain_id: u64) -> Rs<()> {
             }
         };
 
-        sleep(SCAN_FREQUENCY).await;
+        tokio::select! {
+            _ = sleep(SCAN_FREQUENCY) => {}
+            _ = shared::shutdown::wait() => break,
+        }
     }
+
+    tracing::info!(
+        "scanner stopped, next block {}",
+        filter.get_from_block().unwrap_or_default()
+    );
+
+    Ok(())
 }
 
+#[tracing::instrument(skip_all, fields(chain = chain.to_chain_id()))]
 async fn scan(
     client: &PublicClient,
     db: &DatabaseConnection,
     chain: SupportedChain,
     filter: &mut Filter,
 ) -> Rs<u64> {
-    let latest_block = client.get_block_number().await?;
+    let label = chain.to_chain_id().to_string();
+
+    let latest_block =
+        shared::metrics::track_rpc(&label, "eth_blockNumber", client.get_block_number()).await?;
     let from_block = filter.get_from_block().unwrap_or(latest_block);
 
     if from_block > latest_block {
@@ -109,23 +137,50 @@ async fn scan(
         to_block: Some(BlockNumberOrTag::Number(to_block)),
     };
 
-    let logs = client.get_logs(filter).await?;
-
-    let tasks = logs.iter().map(|log| evm_stream::handle_log(db, log));
-
-    try_join_all(tasks).await?;
+    let mut logs =
+        shared::metrics::track_rpc(&label, "eth_getLogs", client.get_logs(filter)).await?;
+
+    // One lookup for the range: its last block is never older than any log in it
+    if logs.iter().any(|log| log.block_timestamp.is_none()) {
+        let block = shared::metrics::track_rpc(
+            &label,
+            "eth_getBlockByNumber",
+            client
+                .get_block_by_number(BlockNumberOrTag::Number(to_block))
+                .into_future(),
+        )
+        .await?
+        .ok_or_else(|| AppErr::custom(format!("block {} not found", to_block)))?;
 
-    tracing::trace!("scanned from {} to {} successfully", from_block, to_block,);
+        evm_stream::fill_block_timestamps(&mut logs, block.header.timestamp);
+    }
 
     let next_block = to_block + 1;
 
-    repositories::settings::set(
-        db,
-        Setting::EvmScannedBlock(chain.to_chain_id()),
-        next_block.to_string(),
+    // The range and the cursor move commit together, so a crash can't skip or repeat it
+    let txn = db.begin().await?;
+    let handled = evm_stream::handle_logs(&txn, chain, &logs).await?;
+
+    let advanced = repositories::settings::compare_and_swap(
+        &txn,
+        EvmScannedBlock(chain.to_chain_id()),
+        &from_block,
+        &next_block,
     )
     .await?;
 
+    if !advanced {
+        tracing::warn!("cursor was moved during the scan, discarding the range");
+        return Ok(from_block);
+    }
+
+    txn.commit().await?;
+    handled.announce(db).await;
+
+    tracing::trace!("scanned from {} to {} successfully", from_block, to_block);
+
+    shared::metrics::record_scan_progress(&label, to_block, latest_block);
+
     Ok(next_block)
 }
 
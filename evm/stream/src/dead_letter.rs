@@ -0,0 +1,117 @@
+use alloy::rpc::types::Log;
+use database::{
+    repositories::failed_events::{self, FailedEvent},
+    sea_orm::DatabaseConnection,
+};
+use evm_lib::SupportedChain;
+use shared::{
+    result::{AppErr, Rs},
+    retry::RetryPolicy,
+};
+
+/// Source of the logs this stream dead-letters
+const SOURCE: &str = evm_stream::DEAD_LETTER_SOURCE;
+/// Dead letters replayed per page
+const REPLAY_PAGE: u64 = 100;
+
+/// Handles `log`, retrying per `policy`, and dead-letters it once the attempts run out
+pub async fn handle_log(
+    db: &DatabaseConnection,
+    chain: SupportedChain,
+    log: &Log,
+    policy: &RetryPolicy,
+) {
+    let mut attempts = 0;
+
+    let error = loop {
+        attempts += 1;
+
+        match evm_stream::handle_log(db, chain, log).await {
+            Ok(()) => return,
+            Err(error) if policy.should_retry(attempts) => {
+                error.trace("Failed to handle log, retrying");
+
+                // Dead-lettered rather than dropped when shutdown cuts the retries short
+                tokio::select! {
+                    _ = tokio::time::sleep(policy.backoff(attempts)) => {}
+                    _ = shared::shutdown::wait() => break error,
+                }
+            }
+            Err(error) => break error,
+        }
+    };
+
+    error.trace("Failed to handle log, dead-lettering it");
+
+    if let Err(error) = dead_letter(db, chain, log, error, attempts).await {
+        error.trace("Failed to dead-letter log");
+    }
+}
+
+async fn dead_letter(
+    db: &DatabaseConnection,
+    chain: SupportedChain,
+    log: &Log,
+    error: AppErr,
+    attempts: u32,
+) -> Rs<()> {
+    let event = evm_stream::failed_event(chain, log, &error, attempts)?;
+
+    failed_events::record(db, event).await
+}
+
+/// `evm-stream replay <chain_id>` replays the chain's dead letters through the handler
+///
+/// Logs handled before are skipped by the handler, so replaying twice is harmless.
+/// Those failing again stay dead-lettered with their attempts bumped.
+pub async fn replay(db: &DatabaseConnection, chain: SupportedChain) -> Rs<()> {
+    let chain_label = chain.to_chain_id().to_string();
+    let (mut resolved, mut failed, mut after) = (0, 0, 0);
+
+    loop {
+        let events =
+            failed_events::list_unresolved(db, SOURCE, &chain_label, after, REPLAY_PAGE).await?;
+
+        for event in &events {
+            match replay_one(db, chain, event).await {
+                Ok(()) => {
+                    failed_events::resolve(db, event.id).await?;
+                    resolved += 1;
+                }
+                Err(error) => {
+                    tracing::warn!(
+                        id = event.id,
+                        key = event.event_key,
+                        "Replay failed: {}",
+                        error
+                    );
+                    failed_events::record_replay_failure(db, event.id, error.to_string()).await?;
+                    failed += 1;
+                }
+            }
+        }
+
+        match events.last() {
+            Some(last) if events.len() as u64 == REPLAY_PAGE && !shared::shutdown::requested() => {
+                after = last.id;
+            }
+            _ => break,
+        }
+    }
+
+    tracing::info!(
+        chain = chain_label,
+        resolved,
+        failed,
+        "Replayed dead letters"
+    );
+
+    Ok(())
+}
+
+async fn replay_one(db: &DatabaseConnection, chain: SupportedChain, event: &FailedEvent) -> Rs<()> {
+    let log = serde_json::from_str::<Log>(&event.payload)
+        .map_err(|error| AppErr::custom(format!("invalid dead letter payload: {}", error)))?;
+
+    evm_stream::handle_log(db, chain, &log).await
+}
@@ -1,9 +1,21 @@
 use alloy::{primitives::Log, rpc::types::Log as RpcLog, sol_types::SolEventInterface};
-use database::{repositories::log_memos, sea_orm::DatabaseConnection};
+use database::{
+    notify::{self, IndexedEvent},
+    repositories::{
+        failed_events::{self, NewFailedEvent},
+        log_memos::{self, NewLogMemo},
+        outbox::{self, NewOutboxMessage},
+    },
+    sea_orm::{DatabaseConnection, DatabaseTransaction, TransactionTrait},
+};
 use evm_lib::{
-    uniswap_v2::UniswapPoolV2::UniswapPoolV2Events, uniswap_v3::UniswapPoolV3::UniswapPoolV3Events,
+    SupportedChain, uniswap_v2::UniswapPoolV2::UniswapPoolV2Events,
+    uniswap_v3::UniswapPoolV3::UniswapPoolV3Events,
 };
-use shared::result::Rs;
+use shared::result::{AppErr, Rs};
+
+/// Source logs of this crate are dead-lettered under, replayed by `evm-stream replay`
+pub const DEAD_LETTER_SOURCE: &str = "evm_stream";
 
 #[derive(Debug)]
 enum Event {
@@ -29,25 +41,254 @@ impl Event {
 
         Ok(event)
     }
+
+    fn protocol(&self) -> &'static str {
+        match self {
+            Self::UniswapV3(_) => "uniswap_v3",
+            Self::UniswapV2(_) => "uniswap_v2",
+        }
+    }
 }
 
-pub async fn handle_log(db: &DatabaseConnection, log: &RpcLog) -> Rs<()> {
-    let hash = log.transaction_hash.unwrap_or_default();
-    let log_ix = log.log_index.unwrap_or_default() as i32;
-    let timestamp = log.block_timestamp.unwrap_or_default() as i64;
+/// Logs persisted by [`handle_logs`], to announce once its transaction commits
+#[derive(Debug, Default)]
+pub struct Handled {
+    pub processed: u64,
+    pub duplicates: u64,
+    /// Logs that couldn't be decoded, dead-lettered instead of handled
+    pub dead_lettered: u64,
+    events: Vec<IndexedEvent>,
+}
 
-    if log_memos::is_existed(db, hash, log_ix).await? {
-        return Ok(());
-    }
+impl Handled {
+    /// Records metrics and notifies listeners, only call it after committing
+    pub async fn announce(self, db: &DatabaseConnection) {
+        shared::metrics::record_events_handled("evm", "processed", self.processed);
+        shared::metrics::record_events_handled("evm", "duplicate", self.duplicates);
+        shared::metrics::record_events_handled("evm", "dead_lettered", self.dead_lettered);
 
-    if let Some(event) = Event::decode_log(log)? {
-        match event {
-            Event::UniswapV2(_event) => {}
-            Event::UniswapV3(_event) => {}
-        };
+        // Listeners only use this to refresh caches and feeds, the events are persisted
+        notify::publish_many(db, &self.events)
+            .await
+            .unwrap_or_else(|error| error.trace("Failed to publish indexed events"));
     }
+}
+
+#[tracing::instrument(
+    skip_all,
+    fields(
+        tx = ?log.transaction_hash,
+        log_ix = ?log.log_index,
+        block = ?log.block_number,
+    )
+)]
+pub async fn handle_log(db: &DatabaseConnection, chain: SupportedChain, log: &RpcLog) -> Rs<()> {
+    // Fails rather than dead-lettering in the batch, so the caller's retries and replays
+    // see a log that still can't be decoded
+    Event::decode_log(log)?;
 
-    log_memos::save(db, hash, log_ix, timestamp).await?;
+    // Dropping the transaction on an error rolls the memo back too
+    let txn = db.begin().await?;
+    let handled = handle_logs(&txn, chain, std::slice::from_ref(log)).await?;
+    txn.commit().await?;
+
+    handled.announce(db).await;
 
     Ok(())
 }
+
+/// `log` as a dead letter, replayed through [`handle_log`]
+pub fn failed_event(
+    chain: SupportedChain,
+    log: &RpcLog,
+    error: &AppErr,
+    attempts: u32,
+) -> Rs<NewFailedEvent> {
+    Ok(NewFailedEvent {
+        source: DEAD_LETTER_SOURCE.to_owned(),
+        chain: chain.to_chain_id().to_string(),
+        key: format!(
+            "{}:{}",
+            log.transaction_hash.unwrap_or_default(),
+            log.log_index.unwrap_or_default()
+        ),
+        payload: serde_json::to_string(log).map_err(|error| AppErr::custom(error.to_string()))?,
+        error: error.to_string(),
+        attempts,
+    })
+}
+
+/// Sets the block time of logs the node sent without one to `fallback`
+///
+/// Most nodes omit `blockTimestamp`, and a memo's time decides when it may be pruned,
+/// so `fallback` must not be earlier than the blocks' own time, e.g. that of the last
+/// block of a scanned range, or the time a streamed log was received.
+pub fn fill_block_timestamps(logs: &mut [RpcLog], fallback: u64) {
+    for log in logs {
+        log.block_timestamp.get_or_insert(fallback);
+    }
+}
+
+/// Persists a batch of logs in `txn`: decodes them all, records their memos in bulk,
+/// then writes events only for the logs whose memo this call inserted
+///
+/// Domain writes go through `txn`, so they commit together with the memos and the
+/// outbox messages announcing the events downstream. A log another worker already
+/// recorded, or one repeated in the batch, is counted as a duplicate and skipped. A log
+/// that can't be decoded is dead-lettered without a memo, so the rest of the batch is
+/// still handled and a replay once decoding is fixed handles it.
+#[tracing::instrument(skip_all, fields(logs = logs.len()))]
+pub async fn handle_logs(
+    txn: &DatabaseTransaction,
+    chain: SupportedChain,
+    logs: &[RpcLog],
+) -> Rs<Handled> {
+    let mut dead_lettered = 0;
+    let mut decoded = Vec::with_capacity(logs.len());
+
+    for log in logs {
+        let hash = log.transaction_hash.unwrap_or_default();
+        let log_ix = log.log_index.unwrap_or_default() as i32;
+        let timestamp = log.block_timestamp.unwrap_or_default() as i64;
+
+        match Event::decode_log(log) {
+            Ok(event) => decoded.push((hash, log_ix, timestamp, event)),
+            Err(error) => {
+                tracing::warn!(
+                    %hash,
+                    log_ix,
+                    "Failed to decode log, dead-lettering it: {}",
+                    error
+                );
+                failed_events::record(txn, failed_event(chain, log, &error, 1)?).await?;
+                dead_lettered += 1;
+            }
+        }
+    }
+
+    let memos = decoded
+        .iter()
+        .map(|(hash, log_ix, timestamp, _)| NewLogMemo {
+            chain: chain.to_chain_id().to_string(),
+            hash: (*hash).into(),
+            log_ix: *log_ix,
+            timestamp: *timestamp,
+        })
+        .collect();
+
+    let mut claimed = log_memos::save_many(txn, memos).await?;
+
+    // Removing each claimed key as it is taken also drops repeats within the batch
+    let fresh = decoded
+        .into_iter()
+        .filter(|(hash, log_ix, ..)| claimed.remove(&(hash.to_string(), *log_ix)))
+        .collect::<Vec<_>>();
+
+    let mut handled = Handled {
+        processed: fresh.len() as u64,
+        duplicates: (logs.len() - dead_lettered as usize - fresh.len()) as u64,
+        dead_lettered,
+        events: Vec::new(),
+    };
+
+    let mut uniswap_v2 = Vec::new();
+    let mut uniswap_v3 = Vec::new();
+
+    for (hash, log_ix, _, event) in fresh {
+        if let Some(event) = event {
+            handled.events.push(IndexedEvent {
+                chain: chain.to_chain_id().to_string(),
+                protocol: event.protocol().to_owned(),
+                tx_hash: hash.to_string(),
+                log_ix,
+            });
+
+            match event {
+                Event::UniswapV2(event) => uniswap_v2.push(event),
+                Event::UniswapV3(event) => uniswap_v3.push(event),
+            };
+        }
+    }
+
+    tracing::trace!(
+        uniswap_v2 = uniswap_v2.len(),
+        uniswap_v3 = uniswap_v3.len(),
+        "decoded events"
+    );
+
+    let messages = handled
+        .events
+        .iter()
+        .map(NewOutboxMessage::indexed_event)
+        .collect::<Rs<Vec<_>>>()?;
+
+    outbox::enqueue(txn, messages).await?;
+
+    Ok(handled)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{B256, LogData};
+
+    use super::*;
+
+    async fn db() -> DatabaseConnection {
+        let db = database::establish_connection("sqlite::memory:")
+            .await
+            .unwrap();
+        database::migrations::check(&db).await.unwrap();
+
+        db
+    }
+
+    fn log(topic0: B256, log_ix: u64) -> RpcLog {
+        RpcLog {
+            inner: Log {
+                address: Default::default(),
+                data: LogData::new_unchecked(vec![topic0], Default::default()),
+            },
+            transaction_hash: Some(B256::repeat_byte(7)),
+            log_index: Some(log_ix),
+            block_timestamp: Some(1),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn undecodable_logs_are_dead_lettered_without_failing_the_batch() {
+        let db = db().await;
+        // A Uniswap V2 event without its data can't be decoded
+        let logs = [
+            log(UniswapPoolV2Events::SELECTORS[0].into(), 0),
+            log(B256::repeat_byte(1), 1),
+        ];
+
+        let txn = db.begin().await.unwrap();
+        let handled = handle_logs(&txn, SupportedChain::Bsc, &logs).await.unwrap();
+        txn.commit().await.unwrap();
+
+        assert_eq!(handled.processed, 1);
+        assert_eq!(handled.duplicates, 0);
+        assert_eq!(handled.dead_lettered, 1);
+
+        let hash = B256::repeat_byte(7);
+        assert!(!log_memos::is_existed(&db, hash, 0).await.unwrap());
+        assert!(log_memos::is_existed(&db, hash, 1).await.unwrap());
+
+        let chain = SupportedChain::Bsc.to_chain_id().to_string();
+        let dead = failed_events::list_unresolved(&db, DEAD_LETTER_SOURCE, &chain, 0, 10)
+            .await
+            .unwrap();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].event_key, format!("{}:0", hash));
+
+        // A replay still fails, so the dead letter isn't resolved by mistake
+        let payload = serde_json::from_str::<RpcLog>(&dead[0].payload).unwrap();
+        assert!(
+            handle_log(&db, SupportedChain::Bsc, &payload)
+                .await
+                .is_err()
+        );
+    }
+}
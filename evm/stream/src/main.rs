@@ -12,9 +12,12 @@ use evm_lib::{
 };
 use fastwebsockets::{Frame, OpCode, Payload, WebSocketError};
 use hyper::Uri;
-use shared::{env::Env, result::Rs};
+use shared::{env::Env, result::Rs, retry::RetryPolicy};
 use tokio::time::sleep;
+use tracing::Instrument;
+use ws_client::FrameCollector;
 
+mod dead_letter;
 mod extractor;
 
 const PING_INTERVAL: Duration = Duration::from_millis(30_000);
@@ -29,32 +32,80 @@ async fn main() -> Rs<()> {
     let chain_id = shared::arg::parse_chain_id_arg();
     let chain = SupportedChain::try_from(chain_id)?;
 
-    let db_url = shared::env::read(Env::DatabaseUrl)?;
-    let ws_rpc = shared::env::read(Env::EvmWsRpc(chain_id))?;
+    if std::env::args()
+        .nth(1)
+        .is_some_and(|command| command == "replay")
+    {
+        return replay(chain).await;
+    }
 
-    let uri = Uri::from_str(&ws_rpc)?;
+    shared::metrics::install()?;
+    shared::shutdown::listen();
+
+    let db_url = shared::env::read(Env::DatabaseUrl)?;
 
     let db = database::establish_connection(&db_url).await?;
+    database::migrations::check(&db).await?;
 
-    loop {
-        if let Err(err) = bootstrap(&db, &uri, chain).await {
+    let ws_rpc = shared::env::read(Env::EvmWsRpc(chain_id))?;
+    let uri = Uri::from_str(&ws_rpc)?;
+    let policy = RetryPolicy::from_env()?;
+
+    while !shared::shutdown::requested() {
+        if let Err(err) = bootstrap(&db, &uri, chain, &policy).await {
             tracing::error!("WebSocketError >> {}", err);
         }
 
-        sleep(DELAY_RECONNECT).await;
+        tokio::select! {
+            _ = sleep(DELAY_RECONNECT) => {}
+            _ = shared::shutdown::wait() => {}
+        }
     }
+
+    tracing::info!("Stream stopped");
+    shared::tracing::shutdown();
+
+    Ok(())
+}
+
+/// `evm-stream replay <chain_id>` replays the dead-lettered logs instead of streaming
+async fn replay(chain: SupportedChain) -> Rs<()> {
+    shared::shutdown::listen();
+
+    let db_url = shared::env::read(Env::DatabaseUrl)?;
+    let db = database::establish_connection(&db_url).await?;
+    database::migrations::check(&db).await?;
+
+    let result = dead_letter::replay(&db, chain).await;
+
+    shared::tracing::shutdown();
+
+    result
 }
 
 async fn bootstrap(
     db: &DatabaseConnection,
     uri: &Uri,
     chain: SupportedChain,
+    policy: &RetryPolicy,
 ) -> Result<(), WebSocketError> {
-    let mut ping_clock = tokio::time::interval(PING_INTERVAL);
-
     let mut ws = ws_client::connect(uri).await?;
     tracing::info!("WebSocket connected {}", uri);
 
+    let endpoint = format!("evm_stream_chain_{}", chain.to_chain_id());
+    let _connection = shared::metrics::ws_connection_opened(&endpoint);
+
+    subscribe(db, &mut ws, chain, policy).await
+}
+
+async fn subscribe(
+    db: &DatabaseConnection,
+    ws: &mut FrameCollector,
+    chain: SupportedChain,
+    policy: &RetryPolicy,
+) -> Result<(), WebSocketError> {
+    let mut ping_clock = tokio::time::interval(PING_INTERVAL);
+
     let filter = Filter::new()
         .address(vec![
             chain.usdt_weth_pool_v2_address(),
@@ -84,17 +135,26 @@ async fn bootstrap(
     loop {
         tokio::select! {
             frame = ws.read_frame() => {
-                if let Some(log) = extractor::extract_frame(frame?, &mut ws).await? {
-                    evm_stream::handle_log(db, &log)
-                        .await
-                        .unwrap_or_else(|error| {
-                            error.trace("handle log error");
-                        });
+                if let Some(mut log) = extractor::extract_frame(frame?, ws).await? {
+                    // Streamed logs are fresh, so receiving them is as late as their block
+                    evm_stream::fill_block_timestamps(
+                        std::slice::from_mut(&mut log),
+                        chrono::Utc::now().timestamp() as u64,
+                    );
+
+                    let span = tracing::info_span!("ws_frame", chain = chain.to_chain_id());
+                    dead_letter::handle_log(db, chain, &log, policy)
+                        .instrument(span)
+                        .await;
                 }
             }
             _ = ping_clock.tick() => {
                 ws.write_frame(Frame::new(true, OpCode::Ping, None, Payload::Borrowed(b"ping"))).await?;
             }
+            _ = shared::shutdown::wait() => {
+                ws.write_frame(Frame::close(1000, b"")).await?;
+                return Ok(());
+            }
         }
     }
 }
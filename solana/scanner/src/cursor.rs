@@ -1,5 +1,5 @@
 use database::repositories;
-use database::repositories::settings::Setting;
+use database::repositories::settings::{SolCurrentScannedSignature, Text};
 use database::sea_orm::DatabaseConnection;
 use shared::result::Rs;
 use solana_client::nonblocking::rpc_client::RpcClient;
@@ -8,8 +8,8 @@ use solana_sdk::signature::Signature;
 use crate::signature::get_the_first_signature;
 
 pub async fn load_or_init_cursor(db: &DatabaseConnection, client: &RpcClient) -> Rs<Signature> {
-    if let Some(sig) = repositories::settings::get(db, Setting::SolCurrentScannedSignature).await? {
-        Ok(sig.parse()?)
+    if let Some(Text(sig)) = repositories::settings::get(db, SolCurrentScannedSignature).await? {
+        Ok(sig)
     } else {
         tracing::trace!("Finding the first signature of program...");
 
@@ -17,8 +17,9 @@ pub async fn load_or_init_cursor(db: &DatabaseConnection, client: &RpcClient) ->
             .await?
             .expect("not found the first signature");
 
-        repositories::settings::insert(db, Setting::SolCurrentScannedSignature, sig.to_string())
-            .await?;
+        let Text(sig) =
+            repositories::settings::get_or_insert(db, SolCurrentScannedSignature, Text(sig))
+                .await?;
 
         Ok(sig)
     }
@@ -0,0 +1,142 @@
+use database::{
+    repositories::failed_events::{self, FailedEvent, NewFailedEvent},
+    sea_orm::DatabaseConnection,
+};
+use shared::result::{AppErr, Rs};
+use solana_sdk::signature::Signature;
+use solana_stream::SOLANA_CHAIN;
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+use crate::handler::handle_tx;
+
+/// Source of the transactions this scanner dead-letters
+const SOURCE: &str = "solana_scanner";
+/// Dead letters replayed per page
+const REPLAY_PAGE: u64 = 100;
+
+/// Records the fetched transaction `signature` failed to be handled with, so it can be
+/// replayed without the RPC node
+pub async fn dead_letter(
+    db: &DatabaseConnection,
+    signature: &str,
+    txn: &EncodedConfirmedTransactionWithStatusMeta,
+    error: AppErr,
+    attempts: u32,
+) -> Rs<()> {
+    let event = NewFailedEvent {
+        source: SOURCE.to_owned(),
+        chain: SOLANA_CHAIN.to_owned(),
+        key: signature.to_owned(),
+        payload: serde_json::to_string(txn).map_err(|error| AppErr::custom(error.to_string()))?,
+        error: error.to_string(),
+        attempts,
+    };
+
+    failed_events::record(db, event).await
+}
+
+/// `solana-scanner replay` replays the dead-lettered transactions through the handler
+///
+/// Events handled before are skipped by the handler, so replaying twice is harmless.
+/// Those failing again stay dead-lettered with their attempts bumped.
+pub async fn replay(db: &DatabaseConnection) -> Rs<()> {
+    let (mut resolved, mut failed, mut after) = (0, 0, 0);
+
+    loop {
+        let events =
+            failed_events::list_unresolved(db, SOURCE, SOLANA_CHAIN, after, REPLAY_PAGE).await?;
+
+        for event in &events {
+            match replay_one(db, event).await {
+                Ok(()) => {
+                    failed_events::resolve(db, event.id).await?;
+                    resolved += 1;
+                }
+                Err(error) => {
+                    tracing::warn!(
+                        id = event.id,
+                        key = event.event_key,
+                        "Replay failed: {}",
+                        error
+                    );
+                    failed_events::record_replay_failure(db, event.id, error.to_string()).await?;
+                    failed += 1;
+                }
+            }
+        }
+
+        match events.last() {
+            Some(last) if events.len() as u64 == REPLAY_PAGE && !shared::shutdown::requested() => {
+                after = last.id;
+            }
+            _ => break,
+        }
+    }
+
+    tracing::info!(resolved, failed, "Replayed dead letters");
+
+    Ok(())
+}
+
+async fn replay_one(db: &DatabaseConnection, event: &FailedEvent) -> Rs<()> {
+    let signature = event.event_key.parse::<Signature>()?;
+    let txn = serde_json::from_str::<EncodedConfirmedTransactionWithStatusMeta>(&event.payload)
+        .map_err(|error| AppErr::custom(format!("invalid dead letter payload: {}", error)))?;
+
+    handle_tx(db, signature, &txn).await
+}
+
+#[cfg(test)]
+mod tests {
+    use database::repositories::log_memos;
+
+    use super::*;
+    use crate::testing::{self, signature};
+
+    #[tokio::test]
+    async fn replay_resolves_dead_letters_that_now_succeed() {
+        let db = testing::db().await;
+        let txn = testing::pumpfun_tx().await;
+
+        dead_letter(
+            &db,
+            &signature().to_string(),
+            &txn,
+            AppErr::custom("down"),
+            3,
+        )
+        .await
+        .unwrap();
+
+        replay(&db).await.unwrap();
+
+        assert!(log_memos::is_existed(&db, signature(), 0).await.unwrap());
+        let open = failed_events::list_unresolved(&db, SOURCE, SOLANA_CHAIN, 0, 10)
+            .await
+            .unwrap();
+        assert!(open.is_empty());
+    }
+
+    #[tokio::test]
+    async fn replay_keeps_dead_letters_that_fail_again() {
+        let db = testing::db().await;
+        let event = NewFailedEvent {
+            source: SOURCE.to_owned(),
+            chain: SOLANA_CHAIN.to_owned(),
+            key: signature().to_string(),
+            payload: "not a transaction".to_owned(),
+            error: "down".to_owned(),
+            attempts: 3,
+        };
+        failed_events::record(&db, event).await.unwrap();
+
+        replay(&db).await.unwrap();
+
+        let open = failed_events::list_unresolved(&db, SOURCE, SOLANA_CHAIN, 0, 10)
+            .await
+            .unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].attempts, 4);
+        assert!(open[0].error.contains("invalid dead letter payload"));
+    }
+}
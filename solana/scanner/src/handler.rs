@@ -1,51 +1,138 @@
 use database::sea_orm::DatabaseConnection;
 use futures_util::future::join_all;
-use shared::result::Rs;
+use shared::{
+    result::{AppErr, Rs},
+    retry::RetryPolicy,
+};
 use sol_lib::pumpfun;
 use solana_client::rpc_response::{OptionSerializer, RpcConfirmedTransactionStatusWithSignature};
 use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_sdk::signature::Signature;
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
 
-use crate::{COMMITMENT, CONCURRENCY_SIGNATURE};
+use crate::{COMMITMENT, CONCURRENCY_SIGNATURE, METRICS_CHAIN, dead_letter::dead_letter};
 
+/// Why a transaction couldn't be consumed
+enum Failure {
+    /// The transaction couldn't be fetched, which is on the RPC node rather than on it
+    Fetch(AppErr),
+    /// The fetched transaction failed to be handled
+    Handle(Box<EncodedConfirmedTransactionWithStatusMeta>, AppErr),
+}
+
+/// Handles `txs`, retrying failures per `policy`, and dead-letters those that run out
+/// of attempts so a transaction that keeps failing can't hold the cursor back
+///
+/// Fails when a transaction can't be fetched or dead-lettered, or when shutdown cuts the
+/// retries short, so the cursor stays put and the next scan retries it.
 pub async fn consume_txs(
     db: &DatabaseConnection,
     client: &RpcClient,
-    mut txs: Vec<RpcConfirmedTransactionStatusWithSignature>,
-) {
+    txs: Vec<RpcConfirmedTransactionStatusWithSignature>,
+    policy: &RetryPolicy,
+) -> Rs<()> {
+    let mut txs: Vec<_> = txs.into_iter().map(|tx| (tx, 0)).collect();
+    let mut round = 0;
+
     while !txs.is_empty() {
-        let batch: Vec<_> = txs.drain(..txs.len().min(CONCURRENCY_SIGNATURE)).collect();
+        if round > 0 {
+            tokio::select! {
+                _ = tokio::time::sleep(policy.backoff(round)) => {}
+                // The cursor stays put, so the next run retries them
+                _ = shared::shutdown::wait() => {
+                    return Err(AppErr::custom("shutting down with transactions left to retry"));
+                }
+            }
+        }
+        round += 1;
 
-        let results = join_all(batch.iter().map(|tx| handle_tx(client, db, tx))).await;
+        let mut retries = Vec::new();
 
-        for (tx, result) in batch.into_iter().zip(results) {
-            if let Err(err) = result {
-                tracing::warn!("retry {} error {}", tx.signature, err);
-                txs.push(tx);
+        while !txs.is_empty() {
+            let batch: Vec<_> = txs.drain(..txs.len().min(CONCURRENCY_SIGNATURE)).collect();
+
+            let results = join_all(batch.iter().map(|(tx, _)| consume_tx(client, db, tx))).await;
+
+            for ((tx, attempts), result) in batch.into_iter().zip(results) {
+                let Err(failure) = result else {
+                    continue;
+                };
+                let attempts = attempts + 1;
+
+                match failure {
+                    Failure::Fetch(err) | Failure::Handle(_, err)
+                        if policy.should_retry(attempts) =>
+                    {
+                        tracing::warn!("retry {} error {}", tx.signature, err);
+                        retries.push((tx, attempts));
+                    }
+                    Failure::Fetch(err) => return Err(err),
+                    Failure::Handle(txn, err) => {
+                        tracing::error!(
+                            "dead-lettering {} after {} attempts, error {}",
+                            tx.signature,
+                            attempts,
+                            err
+                        );
+                        dead_letter(db, &tx.signature, &txn, err, attempts).await?;
+                    }
+                }
             }
         }
+
+        txs = retries;
     }
+
+    Ok(())
 }
 
-async fn handle_tx(
+#[tracing::instrument(skip_all, fields(signature = %tx.signature, slot = tx.slot))]
+async fn consume_tx(
     client: &RpcClient,
     db: &DatabaseConnection,
     tx: &RpcConfirmedTransactionStatusWithSignature,
-) -> Rs<()> {
+) -> Result<(), Failure> {
     tracing::trace!("processing signature {}", tx.signature);
-    let signature = tx.signature.parse()?;
 
+    let signature = tx
+        .signature
+        .parse::<Signature>()
+        .map_err(|error| Failure::Fetch(error.into()))?;
+    let txn = fetch_tx(client, &signature).await.map_err(Failure::Fetch)?;
+
+    handle_tx(db, signature, &txn)
+        .await
+        .map_err(|error| Failure::Handle(Box::new(txn), error))
+}
+
+async fn fetch_tx(
+    client: &RpcClient,
+    signature: &Signature,
+) -> Rs<EncodedConfirmedTransactionWithStatusMeta> {
     let config = RpcTransactionConfig {
         max_supported_transaction_version: Some(0),
         commitment: Some(COMMITMENT),
         encoding: None,
     };
 
-    let txn = client
-        .get_transaction_with_config(&signature, config)
-        .await?;
+    let txn = shared::metrics::track_rpc(
+        METRICS_CHAIN,
+        "getTransaction",
+        client.get_transaction_with_config(signature, config),
+    )
+    .await?;
+
+    Ok(txn)
+}
 
-    if let Some(meta) = txn.transaction.meta
-        && let OptionSerializer::Some(logs) = meta.log_messages
+/// Handles the pump.fun events logged by a fetched transaction
+pub async fn handle_tx(
+    db: &DatabaseConnection,
+    signature: Signature,
+    txn: &EncodedConfirmedTransactionWithStatusMeta,
+) -> Rs<()> {
+    if let Some(meta) = &txn.transaction.meta
+        && let OptionSerializer::Some(logs) = &meta.log_messages
     {
         let timestamp = txn.block_time.unwrap_or_default();
         let events = pumpfun::utils::Event::from_logs(logs);
@@ -55,3 +142,89 @@ async fn handle_tx(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use database::{
+        repositories::{failed_events, log_memos},
+        sea_orm::ConnectionTrait,
+    };
+    use solana_client::rpc_request::RpcRequest;
+    use solana_stream::SOLANA_CHAIN;
+
+    use super::*;
+    use crate::testing::{self, signature};
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::ZERO,
+        }
+    }
+
+    /// Answers `getTransaction` with `txn` `times` times
+    fn client(txn: &EncodedConfirmedTransactionWithStatusMeta, times: usize) -> RpcClient {
+        let response = serde_json::to_value(txn).unwrap();
+        let mocks = std::iter::repeat_n((RpcRequest::GetTransaction, response), times).collect();
+
+        RpcClient::new_mock_with_mocks_map("succeeds", mocks)
+    }
+
+    async fn dead_letters(db: &DatabaseConnection) -> Vec<failed_events::FailedEvent> {
+        failed_events::list_unresolved(db, "solana_scanner", SOLANA_CHAIN, 0, 10)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn consumed_transactions_are_handled() {
+        let db = testing::db().await;
+        let client = client(&testing::pumpfun_tx().await, 1);
+
+        consume_txs(&db, &client, vec![testing::listed(signature())], &policy(3))
+            .await
+            .unwrap();
+
+        assert!(log_memos::is_existed(&db, signature(), 0).await.unwrap());
+        assert!(dead_letters(&db).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn transactions_failing_every_attempt_are_dead_lettered() {
+        let db = testing::db().await;
+        let txn = testing::pumpfun_tx().await;
+        let client = client(&txn, 2);
+
+        // Handling fails on every attempt, while dead-lettering still works
+        db.execute_unprepared("DROP TABLE log_memo").await.unwrap();
+
+        consume_txs(&db, &client, vec![testing::listed(signature())], &policy(2))
+            .await
+            .unwrap();
+
+        let dead = dead_letters(&db).await;
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].event_key, signature().to_string());
+        assert_eq!(dead[0].attempts, 2);
+
+        // Kept whole, so it can be replayed without the RPC node
+        let payload =
+            serde_json::from_str::<EncodedConfirmedTransactionWithStatusMeta>(&dead[0].payload)
+                .unwrap();
+        assert_eq!(payload.slot, txn.slot);
+    }
+
+    #[tokio::test]
+    async fn fetch_failures_fail_the_scan() {
+        let db = testing::db().await;
+        let client = RpcClient::new_mock("fails".to_owned());
+
+        let result =
+            consume_txs(&db, &client, vec![testing::listed(signature())], &policy(2)).await;
+
+        assert!(result.is_err());
+        assert!(dead_letters(&db).await.is_empty());
+    }
+}
@@ -1,9 +1,9 @@
 use std::time::Duration;
 
 use database::repositories;
-use database::repositories::settings::Setting;
+use database::repositories::settings::{SolCurrentScannedSignature, Text};
 use database::sea_orm::DatabaseConnection;
-use shared::{env::Env, result::Rs};
+use shared::{env::Env, result::Rs, retry::RetryPolicy};
 use sol_lib::pumpfun;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_commitment_config::CommitmentConfig;
@@ -16,47 +16,135 @@ use crate::signature::retrieve_txs;
 const CONCURRENCY_SIGNATURE: usize = 30;
 const COMMITMENT: CommitmentConfig = CommitmentConfig::finalized();
 const SCAN_FREQUENCY: Duration = Duration::from_millis(6_000);
+const METRICS_CHAIN: &str = "solana";
 
 mod cursor;
+mod dead_letter;
 mod handler;
 mod signature;
+#[cfg(test)]
+mod testing;
 
 #[tokio::main]
 async fn main() -> Rs<()> {
     shared::env::load();
     shared::tracing::subscribe();
 
+    if std::env::args()
+        .nth(1)
+        .is_some_and(|command| command == "replay")
+    {
+        return replay().await;
+    }
+
+    shared::metrics::install()?;
+    shared::shutdown::listen();
+
     let rpc_url = shared::env::read(Env::SolanaRpc)?;
     let db_url = shared::env::read(Env::DatabaseUrl)?;
 
     let client = RpcClient::new(rpc_url);
 
     let db = database::establish_connection(&db_url).await?;
+    database::migrations::check(&db).await?;
+
+    let policy = RetryPolicy::from_env()?;
     let mut cursor = load_or_init_cursor(&db, &client).await?;
 
     tracing::info!("Event scanner started on {}", pumpfun::ID);
     tracing::info!("Starting from signature {}", cursor);
 
+    // A scan always runs to completion, so the cursor is persisted before exiting
     loop {
-        if let Err(error) = scan(&db, &client, &mut cursor).await {
+        match stored_cursor(&db).await {
+            Ok(Some(stored)) if stored != cursor => {
+                tracing::info!("Cursor moved to signature {}", stored);
+                cursor = stored;
+            }
+            Ok(_) => {}
+            Err(error) => error.trace("Failed to reload cursor"),
+        }
+
+        if let Err(error) = scan(&db, &client, &mut cursor, &policy).await {
             error.trace("Scan failed");
         }
 
-        tokio::time::sleep(SCAN_FREQUENCY).await;
+        tokio::select! {
+            _ = tokio::time::sleep(SCAN_FREQUENCY) => {}
+            _ = shared::shutdown::wait() => break,
+        }
     }
+
+    tracing::info!("Event scanner stopped at signature {}", cursor);
+    shared::tracing::shutdown();
+
+    Ok(())
 }
 
-async fn scan(db: &DatabaseConnection, client: &RpcClient, cursor: &mut Signature) -> Rs<()> {
+/// `solana-scanner replay` replays the dead-lettered transactions instead of scanning
+async fn replay() -> Rs<()> {
+    shared::shutdown::listen();
+
+    let db_url = shared::env::read(Env::DatabaseUrl)?;
+    let db = database::establish_connection(&db_url).await?;
+    database::migrations::check(&db).await?;
+
+    let result = dead_letter::replay(&db).await;
+
+    shared::tracing::shutdown();
+
+    result
+}
+
+#[tracing::instrument(skip_all, fields(%cursor))]
+async fn scan(
+    db: &DatabaseConnection,
+    client: &RpcClient,
+    cursor: &mut Signature,
+    policy: &RetryPolicy,
+) -> Rs<()> {
+    let head = shared::metrics::track_rpc(
+        METRICS_CHAIN,
+        "getSlot",
+        client.get_slot_with_commitment(COMMITMENT),
+    )
+    .await?;
+
     let sigs = retrieve_txs(client, cursor).await?;
-    let next_curor = sigs.first().and_then(|tx| tx.signature.parse().ok());
+    let next_curor = sigs
+        .first()
+        .and_then(|tx| tx.signature.parse::<Signature>().ok());
+    let scanned_slot = sigs.first().map(|tx| tx.slot).unwrap_or(head);
 
-    consume_txs(db, client, sigs).await;
+    consume_txs(db, client, sigs, policy).await?;
 
     if let Some(next_curor) = next_curor {
-        *cursor = next_curor;
-        repositories::settings::set(db, Setting::SolCurrentScannedSignature, cursor.to_string())
-            .await?;
+        let advanced = repositories::settings::compare_and_swap(
+            db,
+            SolCurrentScannedSignature,
+            &Text(*cursor),
+            &Text(next_curor),
+        )
+        .await?;
+
+        if advanced {
+            *cursor = next_curor;
+        } else {
+            tracing::warn!("Cursor was moved during the scan, keeping the new position");
+        }
     }
 
+    shared::metrics::record_scan_progress(METRICS_CHAIN, scanned_slot, head);
+
     Ok(())
 }
+
+/// The persisted cursor, which the admin API or another scanner may have moved since the
+/// last scan
+async fn stored_cursor(db: &DatabaseConnection) -> Rs<Option<Signature>> {
+    let stored = repositories::settings::get(db, SolCurrentScannedSignature)
+        .await?
+        .map(|Text(signature)| signature);
+
+    Ok(stored)
+}
@@ -6,14 +6,16 @@ use solana_client::{
 };
 use solana_sdk::signature::Signature;
 
-use crate::COMMITMENT;
+use crate::{COMMITMENT, METRICS_CHAIN};
 
 pub async fn retrieve_txs(
     client: &RpcClient,
     cursor: &Signature,
 ) -> Rs<Vec<RpcConfirmedTransactionStatusWithSignature>> {
-    let mut page = client
-        .get_signatures_for_address_with_config(
+    let mut page = shared::metrics::track_rpc(
+        METRICS_CHAIN,
+        "getSignaturesForAddress",
+        client.get_signatures_for_address_with_config(
             &pumpfun::ID,
             GetConfirmedSignaturesForAddress2Config {
                 commitment: Some(COMMITMENT),
@@ -21,8 +23,9 @@ pub async fn retrieve_txs(
                 before: None,
                 limit: None,
             },
-        )
-        .await?;
+        ),
+    )
+    .await?;
 
     if page.is_empty() {
         tracing::trace!("No new tx found");
@@ -32,8 +35,10 @@ pub async fn retrieve_txs(
     let mut before = page.last().and_then(|tx| tx.signature.parse().ok());
 
     loop {
-        let order_page = client
-            .get_signatures_for_address_with_config(
+        let order_page = shared::metrics::track_rpc(
+            METRICS_CHAIN,
+            "getSignaturesForAddress",
+            client.get_signatures_for_address_with_config(
                 &pumpfun::ID,
                 GetConfirmedSignaturesForAddress2Config {
                     commitment: Some(COMMITMENT),
@@ -41,8 +46,9 @@ pub async fn retrieve_txs(
                     before,
                     limit: None,
                 },
-            )
-            .await?;
+            ),
+        )
+        .await?;
 
         if order_page.is_empty() {
             return Ok(page);
@@ -0,0 +1,52 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use database::sea_orm::DatabaseConnection;
+use sol_lib::pumpfun::events::AdminSetIdlAuthorityEvent;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_response::{OptionSerializer, RpcConfirmedTransactionStatusWithSignature},
+};
+use solana_sdk::signature::Signature;
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
+
+pub fn signature() -> Signature {
+    Signature::from([7; 64])
+}
+
+/// A fresh in-memory SQLite database with every migration applied
+pub async fn db() -> DatabaseConnection {
+    let db = database::establish_connection("sqlite::memory:")
+        .await
+        .unwrap();
+    database::migrations::check(&db).await.unwrap();
+
+    db
+}
+
+/// The signature as listed by `getSignaturesForAddress`
+pub fn listed(signature: Signature) -> RpcConfirmedTransactionStatusWithSignature {
+    RpcConfirmedTransactionStatusWithSignature {
+        signature: signature.to_string(),
+        slot: 2,
+        err: None,
+        memo: None,
+        block_time: Some(1),
+        confirmation_status: None,
+    }
+}
+
+/// A transaction logging one pump.fun event
+pub async fn pumpfun_tx() -> EncodedConfirmedTransactionWithStatusMeta {
+    // The mock sender answers with a fixed transaction, which only needs the logs
+    let mut txn = RpcClient::new_mock("succeeds".to_owned())
+        .get_transaction(&signature(), UiTransactionEncoding::Json)
+        .await
+        .unwrap();
+
+    let mut data = AdminSetIdlAuthorityEvent::DISCRIMINATOR.to_vec();
+    data.extend([0; 32]);
+
+    txn.transaction.meta.as_mut().unwrap().log_messages =
+        OptionSerializer::Some(vec![format!("Program data: {}", STANDARD.encode(data))]);
+
+    txn
+}
@@ -5,6 +5,10 @@ use solana_client::rpc_response::{Response, RpcLogsResponse};
 use solana_sdk::signature::Signature;
 
 /// Processes a Solana log response and extracts/handles events
+#[tracing::instrument(
+    skip_all,
+    fields(signature = %res.value.signature, slot = res.context.slot)
+)]
 pub async fn handle_log_from_ws(
     db: &DatabaseConnection,
     res: Response<RpcLogsResponse>,
@@ -1,37 +1,171 @@
-use database::{repositories::log_memos, sea_orm::DatabaseConnection};
-use futures_util::future::try_join_all;
-use shared::result::Rs;
+use database::{
+    notify::{self, IndexedEvent},
+    repositories::{
+        log_memos::{self, NewLogMemo},
+        outbox::{self, NewOutboxMessage},
+    },
+    sea_orm::{DatabaseConnection, DatabaseTransaction, TransactionTrait},
+};
+use shared::{UnionTxHash, result::Rs};
 use sol_lib::pumpfun;
 use solana_sdk::signature::Signature;
 
+/// Chain label of Solana memos and events
+pub const SOLANA_CHAIN: &str = "solana";
+
+/// Handles the events of one transaction atomically, either all of them are persisted
+/// along with their memos and outbox messages or none are
+#[tracing::instrument(skip_all, fields(%signature, events = events.len()))]
 pub async fn handle_events(
     db: &DatabaseConnection,
     signature: Signature,
     timestamp: i64,
     events: Vec<pumpfun::utils::Event>,
 ) -> Rs<()> {
-    let iter = events
-        .into_iter()
-        .enumerate()
-        .map(|(log_ix, event)| handle_event(db, signature, log_ix as i32, timestamp, event));
+    let total = events.len();
+
+    // Dropping the transaction on an error rolls the memos back too
+    let txn = db.begin().await?;
+    let handled = persist_events(&txn, signature, timestamp, events).await?;
+    txn.commit().await?;
+
+    let duplicates = (total - handled.len()) as u64;
+    shared::metrics::record_events_handled("solana", "processed", handled.len() as u64);
+    shared::metrics::record_events_handled("solana", "duplicate", duplicates);
 
-    try_join_all(iter).await?;
+    // Listeners only use this to refresh caches and feeds, the events are persisted
+    notify::publish_many(db, &handled)
+        .await
+        .unwrap_or_else(|error| error.trace("Failed to publish indexed events"));
 
     Ok(())
 }
 
-async fn handle_event(
-    db: &DatabaseConnection,
+/// Persists the events in `txn`: records their memos in bulk, then writes only the
+/// events whose memo this call inserted, and enqueues an outbox message for each
+///
+/// Domain writes go through `txn`, so they commit together with the memos and the
+/// outbox messages. Returns the events written, to announce once `txn` commits.
+async fn persist_events(
+    txn: &DatabaseTransaction,
     signature: Signature,
-    log_ix: i32,
     timestamp: i64,
-    _event: pumpfun::utils::Event,
-) -> Rs<()> {
-    if log_memos::is_existed(db, signature, log_ix).await? {
-        return Ok(());
+    events: Vec<pumpfun::utils::Event>,
+) -> Rs<Vec<IndexedEvent>> {
+    let memos = (0..events.len())
+        .map(|log_ix| NewLogMemo {
+            chain: SOLANA_CHAIN.to_owned(),
+            hash: signature.into(),
+            log_ix: log_ix as i32,
+            timestamp,
+        })
+        .collect();
+
+    let claimed = log_memos::save_many(txn, memos).await?;
+    let hash = UnionTxHash::from(signature).to_string();
+
+    let mut handled = Vec::with_capacity(claimed.len());
+
+    for (log_ix, _event) in events.into_iter().enumerate() {
+        let log_ix = log_ix as i32;
+
+        // Recorded by another worker before, so handled there
+        if !claimed.contains(&(hash.clone(), log_ix)) {
+            continue;
+        }
+
+        // Domain writes of `_event` go through `txn`
+
+        handled.push(IndexedEvent {
+            chain: SOLANA_CHAIN.to_owned(),
+            protocol: "pumpfun".to_owned(),
+            tx_hash: signature.to_string(),
+            log_ix,
+        });
     }
 
-    log_memos::save(db, signature, log_ix, timestamp).await?;
+    let messages = handled
+        .iter()
+        .map(NewOutboxMessage::indexed_event)
+        .collect::<Rs<Vec<_>>>()?;
 
-    Ok(())
+    outbox::enqueue(txn, messages).await?;
+
+    Ok(handled)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use database::{repositories::outbox::OutboxMessage, sea_orm::ConnectionTrait};
+    use sol_lib::pumpfun::events::AdminSetIdlAuthorityEvent;
+
+    use super::*;
+
+    async fn db() -> DatabaseConnection {
+        let db = database::establish_connection("sqlite::memory:")
+            .await
+            .unwrap();
+        database::migrations::check(&db).await.unwrap();
+
+        db
+    }
+
+    fn events(count: usize) -> Vec<pumpfun::utils::Event> {
+        (0..count)
+            .map(|_| {
+                pumpfun::utils::Event::AdminSetIdlAuthorityEvent(AdminSetIdlAuthorityEvent {
+                    idl_authority: Default::default(),
+                })
+            })
+            .collect()
+    }
+
+    async fn outbox(db: &DatabaseConnection) -> Vec<OutboxMessage> {
+        outbox::claim_due(db, 10, Duration::from_secs(60))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn events_are_persisted_once() {
+        let db = db().await;
+        let signature = Signature::from([7; 64]);
+
+        handle_events(&db, signature, 1, events(2)).await.unwrap();
+        handle_events(&db, signature, 1, events(2)).await.unwrap();
+
+        assert!(log_memos::is_existed(&db, signature, 0).await.unwrap());
+        assert!(log_memos::is_existed(&db, signature, 1).await.unwrap());
+
+        let messages = outbox(&db).await;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].message_key, format!("solana:{}:0", signature));
+        assert_eq!(messages[1].message_key, format!("solana:{}:1", signature));
+    }
+
+    #[tokio::test]
+    async fn a_failing_domain_write_rolls_back_memos_and_outbox_messages() {
+        let db = db().await;
+        let signature = Signature::from([7; 64]);
+
+        let txn = db.begin().await.unwrap();
+        persist_events(&txn, signature, 1, events(2)).await.unwrap();
+        let write = txn
+            .execute_unprepared("INSERT INTO missing_table VALUES (1)")
+            .await;
+        assert!(write.is_err());
+        // Dropped on the error, as `?` would
+        drop(txn);
+
+        assert!(!log_memos::is_existed(&db, signature, 0).await.unwrap());
+        assert!(outbox(&db).await.is_empty());
+
+        // Nothing was claimed, so a retry handles the events rather than skip them
+        handle_events(&db, signature, 1, events(2)).await.unwrap();
+
+        assert!(log_memos::is_existed(&db, signature, 0).await.unwrap());
+        assert_eq!(outbox(&db).await.len(), 2);
+    }
 }
@@ -13,6 +13,8 @@ use solana_client::rpc_config::{
     CommitmentConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter,
 };
 use tokio::time::sleep;
+use tracing::Instrument;
+use ws_client::FrameCollector;
 
 use crate::handler::handle_log_from_ws;
 
@@ -29,6 +31,8 @@ static ID: AtomicU64 = AtomicU64::new(1);
 async fn main() -> Rs<()> {
     shared::env::load();
     shared::tracing::subscribe();
+    shared::metrics::install()?;
+    shared::shutdown::listen();
 
     let db_url = shared::env::read(Env::DatabaseUrl)?;
     let ws_rpc = shared::env::read(Env::SolanaWsRpc)?;
@@ -36,22 +40,37 @@ async fn main() -> Rs<()> {
     let uri = Uri::from_str(&ws_rpc)?;
 
     let db = database::establish_connection(&db_url).await?;
+    database::migrations::check(&db).await?;
 
-    loop {
+    while !shared::shutdown::requested() {
         if let Err(err) = bootstrap(&db, &uri).await {
             tracing::error!("WebSocket connection error, reconnecting... {}", err);
         }
 
-        sleep(DELAY_RECONNECT).await;
+        tokio::select! {
+            _ = sleep(DELAY_RECONNECT) => {}
+            _ = shared::shutdown::wait() => {}
+        }
     }
+
+    tracing::info!("Stream stopped");
+    shared::tracing::shutdown();
+
+    Ok(())
 }
 
 async fn bootstrap(db: &DatabaseConnection, uri: &Uri) -> Result<(), WebSocketError> {
-    let mut ping_clock = tokio::time::interval(PING_INTERVAL);
-
     let mut ws = ws_client::connect(uri).await?;
     tracing::info!("WebSocket connected {}", uri);
 
+    let _connection = shared::metrics::ws_connection_opened("solana_stream");
+
+    subscribe(db, &mut ws).await
+}
+
+async fn subscribe(db: &DatabaseConnection, ws: &mut FrameCollector) -> Result<(), WebSocketError> {
+    let mut ping_clock = tokio::time::interval(PING_INTERVAL);
+
     let filter = RpcTransactionLogsFilter::Mentions(vec![pumpfun::ID.to_string()]);
 
     let config = RpcTransactionLogsConfig {
@@ -74,8 +93,9 @@ async fn bootstrap(db: &DatabaseConnection, uri: &Uri) -> Result<(), WebSocketEr
     loop {
         tokio::select! {
             frame = ws.read_frame() => {
-                if let Some(res) = extractor::extract_frame(&mut ws, frame?).await? {
-                    match handle_log_from_ws(db, res).await {
+                if let Some(res) = extractor::extract_frame(ws, frame?).await? {
+                    let span = tracing::info_span!("ws_frame");
+                    match handle_log_from_ws(db, res).instrument(span).await {
                         Ok(Some(signature)) => tracing::info!("Processed transaction {}", signature),
                         Ok(None) => {},
                         Err(error) => error.trace("Failed to handle log"),
@@ -85,6 +105,10 @@ async fn bootstrap(db: &DatabaseConnection, uri: &Uri) -> Result<(), WebSocketEr
             _ = ping_clock.tick() => {
                 ws.write_frame(Frame::new(true, OpCode::Ping, None, Payload::Borrowed(b"ping"))).await?;
             },
+            _ = shared::shutdown::wait() => {
+                ws.write_frame(Frame::close(1000, b"")).await?;
+                return Ok(());
+            },
         }
     }
 }